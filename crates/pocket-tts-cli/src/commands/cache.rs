@@ -0,0 +1,124 @@
+//! Cache command implementation
+//!
+//! Provides `pocket-tts cache stats` and `pocket-tts cache prune` for the
+//! synthesis cache that `pocket-tts serve --cache-dir` fills.
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use owo_colors::OwoColorize;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::synthesis_cache::SynthesisCache;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+#[derive(Parser, Debug)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheAction {
+    /// Show the number, size and age of cached phrases
+    Stats(StatsArgs),
+    /// Delete old or least recently used phrases
+    Prune(PruneArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct StatsArgs {
+    /// Cache directory (as passed to `serve --cache-dir`)
+    #[arg(long)]
+    pub dir: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct PruneArgs {
+    /// Cache directory (as passed to `serve --cache-dir`)
+    #[arg(long)]
+    pub dir: PathBuf,
+
+    /// Delete least recently used phrases until at most this many MB remain
+    #[arg(long)]
+    pub max_mb: Option<u64>,
+
+    /// Delete phrases not used for this many days
+    #[arg(long)]
+    pub older_than_days: Option<u64>,
+
+    /// Delete every cached phrase
+    #[arg(long, conflicts_with_all = ["max_mb", "older_than_days"])]
+    pub all: bool,
+}
+
+pub fn run(args: CacheArgs) -> Result<()> {
+    match args.action {
+        CacheAction::Stats(args) => stats(args),
+        CacheAction::Prune(args) => prune(args),
+    }
+}
+
+fn stats(args: StatsArgs) -> Result<()> {
+    let cache = open(&args.dir)?;
+    let stats = cache.stats()?;
+    println!("  {} {}", "Cache:".bold(), cache.dir().display());
+    println!("  Entries:   {}", stats.entries);
+    println!("  Size:      {}", format_bytes(stats.bytes));
+    if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+        println!("  Last used: {} (least recent)", format_age(oldest));
+        println!("             {} (most recent)", format_age(newest));
+    }
+    Ok(())
+}
+
+fn prune(args: PruneArgs) -> Result<()> {
+    if !args.all && args.max_mb.is_none() && args.older_than_days.is_none() {
+        anyhow::bail!("Nothing to prune: pass --max-mb, --older-than-days or --all");
+    }
+    let cache = open(&args.dir)?;
+    let max_bytes = match (args.all, args.max_mb) {
+        (true, _) => 0,
+        (false, Some(mb)) => mb.saturating_mul(BYTES_PER_MB),
+        (false, None) => u64::MAX,
+    };
+    let max_age = args
+        .older_than_days
+        .map(|days| Duration::from_secs(days.saturating_mul(24 * 3600)));
+    let pruned = cache.prune(max_bytes, max_age)?;
+    let remaining = cache.stats()?;
+    println!(
+        "  {} Removed {} entries ({}), {} entries ({}) remain",
+        "✓".green(),
+        pruned.removed,
+        format_bytes(pruned.freed_bytes),
+        remaining.entries,
+        format_bytes(remaining.bytes)
+    );
+    Ok(())
+}
+
+fn open(dir: &PathBuf) -> Result<SynthesisCache> {
+    if !dir.is_dir() {
+        anyhow::bail!("Cache directory {:?} not found", dir);
+    }
+    SynthesisCache::open(dir, u64::MAX)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / BYTES_PER_MB as f64)
+}
+
+fn format_age(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs();
+    match secs {
+        0..60 => format!("{}s ago", secs),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
@@ -0,0 +1,109 @@
+//! Codec command implementation
+//!
+//! Provides `pocket-tts codec encode` and `pocket-tts codec decode` for
+//! compressing arbitrary audio with the Mimi neural codec.
+
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use owo_colors::OwoColorize;
+use pocket_tts::{EncodedAudio, LatentPrecision, TTSModel};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+pub struct CodecArgs {
+    #[command(subcommand)]
+    pub action: CodecAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CodecAction {
+    /// Encode a WAV file into a compact .mimi file
+    Encode(EncodeArgs),
+    /// Decode a .mimi file back into a WAV file
+    Decode(DecodeArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct EncodeArgs {
+    /// Input WAV file (any sample rate, mixed down to mono)
+    pub input: PathBuf,
+
+    /// Output .mimi file
+    pub output: PathBuf,
+
+    /// Latent storage precision: i8 (smallest), i16 or f32 (lossless latents)
+    #[arg(long, default_value = "i8")]
+    pub precision: LatentPrecision,
+
+    /// Model variant (default: b6369a24)
+    #[arg(long, default_value = "b6369a24")]
+    pub variant: String,
+
+    /// Suppress all output except errors
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct DecodeArgs {
+    /// Input .mimi file
+    pub input: PathBuf,
+
+    /// Output WAV file
+    pub output: PathBuf,
+
+    /// Model variant (must match the one used to encode)
+    #[arg(long, default_value = "b6369a24")]
+    pub variant: String,
+
+    /// Suppress all output except errors
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+pub fn run(args: CodecArgs) -> Result<()> {
+    match args.action {
+        CodecAction::Encode(args) => encode(args),
+        CodecAction::Decode(args) => decode(args),
+    }
+}
+
+fn encode(args: EncodeArgs) -> Result<()> {
+    let model = TTSModel::load(&args.variant)?;
+    let (audio, sample_rate) = pocket_tts::audio::read_wav(&args.input)?;
+    let encoded = model.encode_audio(&audio, sample_rate)?;
+    encoded.write(&args.output, args.precision)?;
+
+    if !args.quiet {
+        let size = std::fs::metadata(&args.output)?.len();
+        println!(
+            "  {} Encoded {:.2}s into {} frames ({} bytes, {:.1} kbps at {})",
+            "✓".green(),
+            encoded.duration_secs(),
+            encoded.num_frames(),
+            size,
+            encoded.bitrate(args.precision) / 1000.0,
+            args.precision
+        );
+        println!("    Output: {}", args.output.display().cyan());
+    }
+    Ok(())
+}
+
+fn decode(args: DecodeArgs) -> Result<()> {
+    let model = TTSModel::load(&args.variant)?;
+    let encoded = EncodedAudio::read(&args.input, &model.device)?;
+    let audio = model.decode_audio(&encoded)?;
+    pocket_tts::audio::write_wav(&args.output, &audio, encoded.sample_rate)?;
+
+    if !args.quiet {
+        println!(
+            "  {} Decoded {} frames into {:.2}s of audio",
+            "✓".green(),
+            encoded.num_frames(),
+            encoded.duration_secs()
+        );
+        println!("    Output: {}", args.output.display().cyan());
+    }
+    Ok(())
+}
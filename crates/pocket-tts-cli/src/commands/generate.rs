@@ -3,31 +3,72 @@
 //! Provides `pocket-tts generate` for text-to-speech synthesis.
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use indicatif::{ProgressBar, ProgressStyle};
 use owo_colors::OwoColorize;
-use pocket_tts::TTSModel;
+use pocket_tts::markdown::MarkdownOptions;
+use pocket_tts::ssml::SsmlItem;
+use pocket_tts::{
+    GenerationOptions, InputFormat, Lexicon, PauseConfig, TTSModel, UnsupportedCharPolicy,
+};
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use crate::voice::{PREDEFINED_VOICES, resolve_voice};
+use crate::voice::{PREDEFINED_VOICES, SpecVoiceResolver, VoicePreprocessArgs, resolve_voices};
 
 /// Default text shown when user runs without --text
 pub const DEFAULT_TEXT: &str =
     "Hello world! I am Pocket TTS, running blazingly fast in Rust. I hope you'll like me.";
 
+/// Handling of characters the tokenizer cannot encode (emoji, CJK, ...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnsupportedChars {
+    /// Remove them
+    Strip,
+    /// Replace typographic punctuation and accented letters with ASCII,
+    /// remove the rest
+    Transliterate,
+    /// Fail before generating
+    Error,
+}
+
+impl From<UnsupportedChars> for UnsupportedCharPolicy {
+    fn from(value: UnsupportedChars) -> Self {
+        match value {
+            UnsupportedChars::Strip => Self::Strip,
+            UnsupportedChars::Transliterate => Self::Transliterate,
+            UnsupportedChars::Error => Self::Error,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 pub struct GenerateArgs {
     /// Text to synthesize (defaults to a greeting if not specified)
     #[arg(short, long, default_value = DEFAULT_TEXT)]
     pub text: String,
 
+    /// Read the text from a file instead of --text; `.md` and `.markdown`
+    /// files are read as Markdown, anything else as plain text
+    #[arg(short, long, conflicts_with = "text")]
+    pub input_file: Option<PathBuf>,
+
+    /// Sentence read in place of Markdown code blocks (skipped by default)
+    #[arg(long)]
+    pub code_block_phrase: Option<String>,
+
     /// Voice for synthesis. Can be:
     /// - Predefined name: alba, marius, javert, jean, fantine, cosette, eponine, azelma
     /// - Path to .wav file for voice cloning
     /// - Path to .safetensors embeddings file
     /// - HuggingFace URL: hf://owner/repo/file.wav
+    ///
+    /// Repeat with WAV clips of one speaker to clone from all of them.
     #[arg(short, long)]
-    pub voice: Option<String>,
+    pub voice: Vec<String>,
+
+    #[command(flatten)]
+    pub voice_preprocessing: VoicePreprocessArgs,
 
     /// Output audio file path
     #[arg(short, long, default_value = "output.wav")]
@@ -57,6 +98,80 @@ pub struct GenerateArgs {
     #[arg(long)]
     pub frames_after_eos: Option<usize>,
 
+    /// Random seed for reproducible output (optional, random if not set)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Frames of previous audio (12.5 per second) carried into each following
+    /// sentence chunk for smoother prosody (0 = off)
+    #[arg(long, default_value = "0")]
+    pub continuity_frames: usize,
+
+    /// Speaking rate (0.25-4.0; 1.0 = natural pace, pitch is preserved)
+    #[arg(long, default_value = "1.0")]
+    pub speed: f32,
+
+    /// Candidates sampled per sentence chunk; the best one is kept
+    /// (EOS reached, plausible length, little silence or clipping)
+    #[arg(long, default_value = "1")]
+    pub candidates: usize,
+
+    /// Read text as written instead of expanding numbers, dates, currency,
+    /// units and abbreviations into words
+    #[arg(long)]
+    pub no_normalize: bool,
+
+    /// Read --text as SSML (<speak>, <break>, <p>, <s>, <prosody>, <say-as>,
+    /// <sub>, <audio>); unsupported elements are reported and skipped
+    #[arg(long)]
+    pub ssml: bool,
+
+    /// Pronunciation lexicon (TOML or JSON) mapping words or regex patterns
+    /// to respellings
+    #[arg(long)]
+    pub lexicon: Option<PathBuf>,
+
+    /// What to do with characters the model cannot voice
+    #[arg(long, value_enum, default_value = "transliterate")]
+    pub unsupported_chars: UnsupportedChars,
+
+    /// Turn off pauses inferred from punctuation ([pause:...] markers still apply)
+    #[arg(long)]
+    pub no_natural_pauses: bool,
+
+    /// Also pause at sentence ends, semicolons, colons and blank lines,
+    /// not only at commas and ellipses
+    #[arg(long)]
+    pub punctuation_pauses: bool,
+
+    /// Multiply every natural pause duration (0-10)
+    #[arg(long, default_value = "1.0")]
+    pub pause_scale: f32,
+
+    /// Pause after commas in milliseconds
+    #[arg(long)]
+    pub comma_pause_ms: Option<u32>,
+
+    /// Pause after semicolons in milliseconds
+    #[arg(long)]
+    pub semicolon_pause_ms: Option<u32>,
+
+    /// Pause after colons in milliseconds
+    #[arg(long)]
+    pub colon_pause_ms: Option<u32>,
+
+    /// Pause between sentences in milliseconds
+    #[arg(long)]
+    pub sentence_pause_ms: Option<u32>,
+
+    /// Pause at blank lines between paragraphs in milliseconds
+    #[arg(long)]
+    pub paragraph_pause_ms: Option<u32>,
+
+    /// Pause at ellipses in milliseconds
+    #[arg(long)]
+    pub ellipsis_pause_ms: Option<u32>,
+
     /// Stream raw PCM audio to stdout (for piping to audio players)
     #[arg(long)]
     pub stream: bool,
@@ -83,7 +198,7 @@ macro_rules! info {
     };
 }
 
-pub fn run(args: GenerateArgs) -> Result<()> {
+pub fn run(mut args: GenerateArgs) -> Result<()> {
     let quiet = args.quiet || args.stream;
 
     // Print banner
@@ -91,6 +206,29 @@ pub fn run(args: GenerateArgs) -> Result<()> {
         print_banner();
     }
 
+    let markdown = match &args.input_file {
+        Some(path) => {
+            args.text = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {:?}: {}", path, e))?;
+            !args.ssml
+                && path.extension().is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown")
+                })
+        }
+        None => false,
+    };
+
+    // Check SSML before loading the model so markup errors fail fast
+    if args.ssml {
+        check_ssml(&args.text, args.quiet)?;
+    }
+
+    let lexicon = args
+        .lexicon
+        .as_ref()
+        .map(|path| Lexicon::load(path).map(Arc::new))
+        .transpose()?;
+
     // Set up device
     let device = if args.use_metal {
         #[cfg(feature = "metal")]
@@ -140,6 +278,10 @@ pub fn run(args: GenerateArgs) -> Result<()> {
             &device,
         )?
     };
+    // [voice:...] markers accept the same specs as --voice
+    let model = model
+        .with_voice_resolver(SpecVoiceResolver)
+        .with_voice_preprocessing(args.voice_preprocessing.preprocessing());
 
     info!(
         quiet,
@@ -149,7 +291,11 @@ pub fn run(args: GenerateArgs) -> Result<()> {
     );
 
     // Resolve voice
-    let voice_display = args.voice.as_deref().unwrap_or("alba (default)");
+    let voice_display = if args.voice.is_empty() {
+        "alba (default)".to_string()
+    } else {
+        args.voice.join(", ")
+    };
     info!(
         quiet,
         "{} Using voice: {}",
@@ -157,24 +303,106 @@ pub fn run(args: GenerateArgs) -> Result<()> {
         voice_display.yellow()
     );
 
-    let voice_state = resolve_voice(&model, args.voice.as_deref())?;
+    let voice_state = resolve_voices(&model, &args.voice)?;
 
     info!(quiet, "  {} Voice ready", "✓".green());
 
+    let options = GenerationOptions {
+        temperature: args.temperature,
+        lsd_decode_steps: args.lsd_decode_steps,
+        eos_threshold: args.eos_threshold,
+        noise_clamp: args.noise_clamp,
+        frames_after_eos: args.frames_after_eos,
+        seed: args.seed,
+        continuity_frames: args.continuity_frames,
+        speed: args.speed,
+        candidates: args.candidates,
+        normalize_text: !args.no_normalize,
+        input_format: if args.ssml {
+            InputFormat::Ssml
+        } else if markdown {
+            InputFormat::Markdown
+        } else {
+            InputFormat::Text
+        },
+        ssml_audio: args.ssml,
+        lexicon,
+        pauses: pause_config(&args),
+        unsupported_chars: args.unsupported_chars.into(),
+        markdown: MarkdownOptions {
+            code_block_phrase: args.code_block_phrase.clone(),
+            ..MarkdownOptions::default()
+        },
+        ..model.generation_options()
+    };
+
     // Generate
     if args.stream {
-        run_streaming(&model, &args.text, &voice_state)
+        run_streaming(&model, &args.text, &voice_state, &options)
     } else {
-        run_to_file(&model, &args, &voice_state, quiet)
+        run_to_file(&model, &args, &voice_state, &options, quiet)
     }
 }
 
+/// Natural pause settings from the pause flags
+fn pause_config(args: &GenerateArgs) -> PauseConfig {
+    let mut pauses = if args.punctuation_pauses {
+        PauseConfig::punctuated()
+    } else {
+        PauseConfig::default()
+    };
+    pauses.enabled = !args.no_natural_pauses;
+    pauses.scale = args.pause_scale;
+    let overrides = [
+        (args.comma_pause_ms, &mut pauses.comma_ms),
+        (args.semicolon_pause_ms, &mut pauses.semicolon_ms),
+        (args.colon_pause_ms, &mut pauses.colon_ms),
+        (args.sentence_pause_ms, &mut pauses.sentence_end_ms),
+        (args.paragraph_pause_ms, &mut pauses.paragraph_ms),
+        (args.ellipsis_pause_ms, &mut pauses.ellipsis_ms),
+    ];
+    for (value, field) in overrides {
+        if let Some(ms) = value {
+            *field = ms;
+        }
+    }
+    pauses
+}
+
+/// Parse SSML input and print its warnings to stderr
+fn check_ssml(text: &str, quiet: bool) -> Result<()> {
+    let document = pocket_tts::ssml::parse_ssml(text)?;
+    if quiet {
+        return Ok(());
+    }
+    for warning in &document.warnings {
+        eprintln!("  {} SSML: {}", "!".yellow(), warning);
+    }
+    for item in &document.items {
+        if let SsmlItem::Audio { src, .. } = item
+            && !std::path::Path::new(src.strip_prefix("file://").unwrap_or(src)).is_file()
+        {
+            eprintln!(
+                "  {} SSML: audio file {} not found, speaking its fallback text",
+                "!".yellow(),
+                src
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Run streaming generation to stdout
-fn run_streaming(model: &TTSModel, text: &str, voice_state: &pocket_tts::ModelState) -> Result<()> {
+fn run_streaming(
+    model: &TTSModel,
+    text: &str,
+    voice_state: &pocket_tts::ModelState,
+    options: &GenerationOptions,
+) -> Result<()> {
     use std::io::Write;
     let mut stdout = std::io::stdout();
 
-    for chunk_res in model.generate_stream_long(text, voice_state) {
+    for chunk_res in model.generate_stream_long_with_options(text, voice_state, options) {
         let chunk = chunk_res?;
         // Convert tensor to 16-bit PCM
         let chunk = chunk.squeeze(0)?;
@@ -191,9 +419,11 @@ fn run_to_file(
     model: &TTSModel,
     args: &GenerateArgs,
     voice_state: &pocket_tts::ModelState,
+    options: &GenerationOptions,
     quiet: bool,
 ) -> Result<()> {
     use candle_core::Tensor;
+    use pocket_tts::GenerationEventKind;
 
     info!(
         quiet,
@@ -223,8 +453,31 @@ fn run_to_file(
     let mut audio_chunks = Vec::new();
     let mut total_samples = 0;
 
-    for chunk_res in model.generate_stream_long(&args.text, voice_state) {
-        let chunk = chunk_res?;
+    for event in model.generate_events_with_options(&args.text, voice_state, options) {
+        let event = event?;
+        if let GenerationEventKind::CandidateSelected {
+            index,
+            selected,
+            scores,
+        } = &event.kind
+        {
+            let score = &scores[*selected];
+            pb.println(format!(
+                "  {} Chunk {}: kept candidate {}/{} (score {:.2}, eos: {}, silence {:.0}%, clipping {:.1}%)",
+                "✓".green(),
+                index + 1,
+                selected + 1,
+                scores.len(),
+                score.score,
+                score.eos_reached,
+                score.silence_ratio * 100.0,
+                score.clipping_ratio * 100.0
+            ));
+            continue;
+        }
+        let Some(chunk) = event.kind.samples().cloned() else {
+            continue;
+        };
         let dims = chunk.dims();
         let samples = if dims.len() == 2 { dims[1] } else { dims[0] };
         total_samples += samples;
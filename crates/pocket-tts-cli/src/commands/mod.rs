@@ -1,3 +1,6 @@
+pub mod cache;
+pub mod codec;
 pub mod generate;
 pub mod serve;
+pub mod voice;
 pub mod wasm_demo;
@@ -5,8 +5,9 @@
 use anyhow::Result;
 use clap::{ArgAction, Parser, ValueEnum};
 use owo_colors::OwoColorize;
+use std::path::PathBuf;
 
-use crate::voice::PREDEFINED_VOICES;
+use crate::voice::{PREDEFINED_VOICES, VoicePreprocessArgs};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 #[value(rename_all = "kebab-case")]
@@ -40,6 +41,9 @@ pub struct ServeArgs {
     #[arg(long, default_value = "alba")]
     pub voice: String,
 
+    #[command(flatten)]
+    pub voice_preprocessing: VoicePreprocessArgs,
+
     /// Model variant
     #[arg(long, default_value = "b6369a24")]
     pub variant: String,
@@ -83,6 +87,21 @@ pub struct ServeArgs {
     /// Web UI mode to serve.
     #[arg(long, value_enum, default_value_t = UiMode::Standard)]
     pub ui: UiMode,
+
+    /// Pronunciation lexicon (TOML or JSON) loaded at startup; entries can be
+    /// changed at runtime through /lexicon
+    #[arg(long)]
+    pub lexicon: Option<PathBuf>,
+
+    /// Cache synthesized audio in this directory and answer repeated
+    /// /generate requests from it
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Size limit of the synthesis cache in MB; least recently used phrases
+    /// are evicted beyond it
+    #[arg(long, default_value_t = 512)]
+    pub cache_max_mb: u64,
 }
 
 pub async fn run(args: ServeArgs) -> Result<()> {
@@ -164,6 +183,12 @@ pub fn print_endpoints(host: &str, port: u16, ui_mode: UiMode) {
         format!("{}/v1/audio/speech", base).white(),
         "OpenAI-compatible".dimmed()
     );
+    println!(
+        "    {} {}  {}",
+        "GET/POST/DELETE".magenta(),
+        format!("{}/lexicon", base).white(),
+        "Pronunciation lexicon".dimmed()
+    );
     println!();
     println!(
         "  {} Active web UI mode: {}",
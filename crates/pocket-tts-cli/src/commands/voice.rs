@@ -0,0 +1,442 @@
+//! Voice command implementation
+//!
+//! Provides `pocket-tts voice blend` for mixing cloned voices into a new
+//! voice prompt, `pocket-tts voice check` for grading reference clips, and
+//! `add`/`list`/`show`/`remove`/`rename`/`export` for managing the named
+//! voices of the [`VoiceLibrary`].
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use owo_colors::OwoColorize;
+use pocket_tts::weights::download_if_necessary;
+use pocket_tts::{TTSModel, VoiceCheckStatus, VoicePrompt, VoicePromptFormat, VoicePromptMetadata};
+use std::path::{Path, PathBuf};
+
+use crate::voice::{PREDEFINED_VOICES, VoicePreprocessArgs, stock_voice_url, voice_clip_paths};
+use crate::voice_library::{VoiceLibrary, validate_name};
+
+#[derive(Parser, Debug)]
+pub struct VoiceArgs {
+    #[command(subcommand)]
+    pub action: VoiceAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum VoiceAction {
+    /// Blend several voices into a new voice prompt
+    ///
+    /// `voice blend a.wav:0.7 b.wav:0.3 -o brand.safetensors`
+    Blend(BlendArgs),
+    /// Check whether a clip is good reference audio for cloning
+    ///
+    /// Reports duration, sample rate, channels, peak and clipping, estimated
+    /// SNR, silence and loudness with pass/warn/fail grades. Exits with an
+    /// error when a check fails.
+    Check(CheckArgs),
+    /// Add a voice to the library under a name
+    ///
+    /// `voice add ceo --voice ceo.wav` makes `--voice ceo` work everywhere.
+    Add(AddArgs),
+    /// List the voices in the library
+    List,
+    /// Show the details of a library voice
+    Show(NameArgs),
+    /// Delete a voice from the library
+    Remove(NameArgs),
+    /// Rename a library voice
+    Rename(RenameArgs),
+    /// Copy a library voice's prompt to a .safetensors file
+    Export(ExportArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct CheckArgs {
+    /// Reference clip (WAV)
+    pub clip: PathBuf,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct AddArgs {
+    /// Library name (letters, digits, '-' and '_')
+    pub name: String,
+
+    /// Reference voice: WAV file, .safetensors voice prompt, stock voice
+    /// name or hf:// URL. Repeat with WAV clips of one speaker to clone
+    /// from all of them.
+    #[arg(short, long, required = true)]
+    pub voice: Vec<String>,
+
+    /// Free-form description shown by `voice list`
+    #[arg(short, long, default_value = "")]
+    pub description: String,
+
+    /// Replace an existing voice with the same name
+    #[arg(long)]
+    pub force: bool,
+
+    #[command(flatten)]
+    pub voice_preprocessing: VoicePreprocessArgs,
+
+    /// Model variant used to encode WAV audio (default: b6369a24)
+    #[arg(long, default_value = "b6369a24")]
+    pub variant: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct NameArgs {
+    /// Library name
+    pub name: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct RenameArgs {
+    /// Current name
+    pub old: String,
+    /// New name
+    pub new: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct ExportArgs {
+    /// Library name
+    pub name: String,
+
+    /// Output voice prompt (.safetensors)
+    #[arg(short, long)]
+    pub output: PathBuf,
+}
+
+#[derive(Parser, Debug)]
+pub struct BlendArgs {
+    /// Voices as PATH[:WEIGHT] (WAV audio or v1 .safetensors prompts;
+    /// weight defaults to 1 and weights are normalized)
+    #[arg(required = true, value_parser = parse_blend_input)]
+    pub voices: Vec<BlendInput>,
+
+    /// Output voice prompt (.safetensors)
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    #[command(flatten)]
+    pub voice_preprocessing: VoicePreprocessArgs,
+
+    /// Model variant (default: b6369a24)
+    #[arg(long, default_value = "b6369a24")]
+    pub variant: String,
+
+    /// Suppress all output except errors
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+/// One `PATH[:WEIGHT]` argument of `voice blend`
+#[derive(Debug, Clone)]
+pub struct BlendInput {
+    pub path: PathBuf,
+    pub weight: f32,
+}
+
+/// Parse `PATH[:WEIGHT]`; a suffix that is not a number stays part of the
+/// path, so `C:\voices\a.wav` works
+fn parse_blend_input(spec: &str) -> Result<BlendInput, String> {
+    let (path, weight) = match spec.rsplit_once(':') {
+        Some((path, weight)) if !path.is_empty() => match weight.parse::<f32>() {
+            Ok(weight) => (path, weight),
+            Err(_) => (spec, 1.0),
+        },
+        _ => (spec, 1.0),
+    };
+    if !weight.is_finite() || weight < 0.0 {
+        return Err(format!(
+            "weight must be a non-negative number, got {}",
+            weight
+        ));
+    }
+    Ok(BlendInput {
+        path: PathBuf::from(path),
+        weight,
+    })
+}
+
+pub fn run(args: VoiceArgs) -> Result<()> {
+    match args.action {
+        VoiceAction::Blend(args) => blend(args),
+        VoiceAction::Check(args) => check(args),
+        VoiceAction::Add(args) => add(args),
+        VoiceAction::List => list(),
+        VoiceAction::Show(args) => show(args),
+        VoiceAction::Remove(args) => remove(args),
+        VoiceAction::Rename(args) => rename(args),
+        VoiceAction::Export(args) => export(args),
+    }
+}
+
+fn blend(args: BlendArgs) -> Result<()> {
+    for input in &args.voices {
+        if !input.path.exists() {
+            anyhow::bail!("Voice file {:?} not found", input.path);
+        }
+    }
+
+    let model = TTSModel::load(&args.variant)?
+        .with_voice_preprocessing(args.voice_preprocessing.preprocessing());
+    let mut voices = Vec::with_capacity(args.voices.len());
+    for input in &args.voices {
+        let conditioning = load_conditioning(&model, &input.path)
+            .with_context(|| format!("Failed to load voice {:?}", input.path))?;
+        voices.push((conditioning, input.weight));
+    }
+
+    let blended = pocket_tts::blend_voices(&voices)?;
+    let frames = blended.dim(1)?;
+    let metadata = VoicePromptMetadata {
+        duration_secs: Some((frames as f64 / model.mimi.frame_rate) as f32),
+        ..VoicePromptMetadata::new(model.variant.clone())
+    };
+    VoicePrompt::from_conditioning(blended, metadata).save(&args.output)?;
+
+    if !args.quiet {
+        println!(
+            "  {} Blended {} voices into {} frames",
+            "✓".green(),
+            voices.len(),
+            frames
+        );
+        println!("    Output: {}", args.output.display().cyan());
+    }
+    Ok(())
+}
+
+/// Conditioning tensor of a WAV file or v1 voice prompt
+fn load_conditioning(model: &TTSModel, path: &Path) -> Result<candle_core::Tensor> {
+    let is_safetensors = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("safetensors"));
+    let prompt = if is_safetensors {
+        let prompt = VoicePrompt::load(path)?;
+        model.check_voice_prompt(&prompt)?;
+        prompt
+    } else {
+        model.create_voice_prompt(path, VoicePromptFormat::AudioPrompt)?
+    };
+    prompt.conditioning().cloned().ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} prompts store a KV cache and cannot be blended; use the source audio \
+             or an audio_prompt (v1) file",
+            prompt.format
+        )
+    })
+}
+
+fn check(args: CheckArgs) -> Result<()> {
+    let (audio, sample_rate) = pocket_tts::audio::read_wav(&args.clip)
+        .with_context(|| format!("Failed to read {:?}", args.clip))?;
+    let analysis = pocket_tts::analyze_voice_prompt(&audio, sample_rate)?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&analysis)?);
+    } else {
+        println!("  {} {}", "Clip:".bold(), args.clip.display());
+        println!("  Duration:    {:.1}s", analysis.duration_secs);
+        println!("  Sample rate: {} Hz", analysis.sample_rate);
+        println!("  Channels:    {}", analysis.channels);
+        println!(
+            "  Peak:        {:.1} dBFS ({:.2}% clipped)",
+            analysis.peak_db, analysis.clipping_percent
+        );
+        println!("  SNR:         {:.0} dB (estimated)", analysis.snr_db);
+        println!("  Silence:     {:.0}%", analysis.silence_ratio * 100.0);
+        println!("  Loudness:    {:.1} dBFS", analysis.loudness_db);
+        println!();
+        for check in &analysis.checks {
+            let mark = match check.status {
+                VoiceCheckStatus::Pass => "✓".green().to_string(),
+                VoiceCheckStatus::Warn => "!".yellow().to_string(),
+                VoiceCheckStatus::Fail => "✗".red().to_string(),
+            };
+            println!("  {} {:<12} {}", mark, check.name, check.message);
+        }
+    }
+
+    let failed = analysis
+        .checks
+        .iter()
+        .filter(|check| check.status == VoiceCheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{:?} failed {} check(s)", args.clip, failed);
+    }
+    Ok(())
+}
+
+fn add(args: AddArgs) -> Result<()> {
+    // Fail before loading the model or downloading anything
+    validate_name(&args.name)?;
+    let library = VoiceLibrary::open_default()?;
+    if !args.force && library.get(&args.name)?.is_some() {
+        anyhow::bail!(
+            "Voice '{}' already exists; remove it first or pass --force",
+            args.name
+        );
+    }
+
+    let (prompt, source) = match args.voice.as_slice() {
+        [spec] => single_voice_prompt(&args, spec)?,
+        specs => {
+            let paths = voice_clip_paths(specs)?;
+            let prompt = encoding_model(&args)?
+                .create_voice_prompt_from_files(&paths, VoicePromptFormat::AudioPrompt)
+                .context("Failed to encode voice clips")?;
+            let sources: Vec<String> = paths.iter().map(|path| canonical(path)).collect();
+            (prompt, sources.join(", "))
+        }
+    };
+
+    let entry = library.add(&args.name, &args.description, &source, &prompt, args.force)?;
+    println!(
+        "  {} Added voice '{}' ({})",
+        "✓".green(),
+        entry.name.bold(),
+        prompt.format
+    );
+    println!("    Library: {}", library.dir().display().cyan());
+    Ok(())
+}
+
+/// Voice prompt and library source of one `voice add --voice` spec
+fn single_voice_prompt(args: &AddArgs, spec: &str) -> Result<(VoicePrompt, String)> {
+    let spec = spec.trim();
+    let url = if PREDEFINED_VOICES.contains(&spec) {
+        stock_voice_url(spec)
+    } else {
+        spec.to_string()
+    };
+    let is_remote = url.starts_with("hf://");
+    if !is_remote && !Path::new(&url).exists() {
+        anyhow::bail!("Voice file {:?} not found", url);
+    }
+    let path = download_if_necessary(&url)
+        .with_context(|| format!("Failed to download voice from '{}'", url))?;
+    let source = if is_remote {
+        spec.to_string()
+    } else {
+        canonical(&path)
+    };
+
+    let is_safetensors = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("safetensors"));
+    let prompt = if is_safetensors {
+        VoicePrompt::load(&path)?
+    } else {
+        encoding_model(args)?
+            .create_voice_prompt(&path, VoicePromptFormat::AudioPrompt)
+            .with_context(|| format!("Failed to encode voice {:?}", path))?
+    };
+    Ok((prompt, source))
+}
+
+/// Model that encodes WAV audio for `voice add`
+fn encoding_model(args: &AddArgs) -> Result<TTSModel> {
+    Ok(TTSModel::load(&args.variant)?
+        .with_voice_preprocessing(args.voice_preprocessing.preprocessing()))
+}
+
+fn canonical(path: &Path) -> String {
+    std::fs::canonicalize(path)
+        .unwrap_or(path.to_path_buf())
+        .display()
+        .to_string()
+}
+
+fn list() -> Result<()> {
+    let library = VoiceLibrary::open_default()?;
+    let voices = library.list()?;
+    if voices.is_empty() {
+        println!(
+            "  No voices in {}. Add one with `pocket-tts voice add NAME --voice clip.wav`.",
+            library.dir().display()
+        );
+        return Ok(());
+    }
+    for voice in voices {
+        println!(
+            "  {:<20} v{}  {:<10} {}",
+            voice.name.bold(),
+            voice.format_version,
+            voice.model_variant.as_deref().unwrap_or("-"),
+            voice.description
+        );
+    }
+    Ok(())
+}
+
+fn show(args: NameArgs) -> Result<()> {
+    let library = VoiceLibrary::open_default()?;
+    let entry = library
+        .get(&args.name)?
+        .with_context(|| format!("Voice '{}' is not in the library", args.name))?;
+    let path = library.prompt_path(&entry);
+    let prompt = VoicePrompt::load(&path)?;
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+
+    println!("  {} {}", "Voice:".bold(), entry.name);
+    println!("  Description:   {}", entry.description);
+    println!("  Source:        {}", entry.source);
+    println!("  Format:        {}", prompt.format);
+    println!("  Model variant: {}", optional(entry.model_variant));
+    println!(
+        "  Duration:      {}",
+        optional(prompt.metadata.duration_secs.map(|s| format!("{:.1}s", s)))
+    );
+    println!(
+        "  Sample rate:   {}",
+        optional(
+            prompt
+                .metadata
+                .source_sample_rate
+                .map(|rate| format!("{} Hz", rate))
+        )
+    );
+    println!("  Created by:    {}", optional(prompt.metadata.created_by));
+    println!("  File:          {}", path.display());
+    Ok(())
+}
+
+fn remove(args: NameArgs) -> Result<()> {
+    let entry = VoiceLibrary::open_default()?.remove(&args.name)?;
+    println!("  {} Removed voice '{}'", "✓".green(), entry.name);
+    Ok(())
+}
+
+fn rename(args: RenameArgs) -> Result<()> {
+    let entry = VoiceLibrary::open_default()?.rename(&args.old, &args.new)?;
+    println!(
+        "  {} Renamed voice '{}' to '{}'",
+        "✓".green(),
+        args.old,
+        entry.name
+    );
+    Ok(())
+}
+
+fn export(args: ExportArgs) -> Result<()> {
+    let library = VoiceLibrary::open_default()?;
+    let entry = library
+        .get(&args.name)?
+        .with_context(|| format!("Voice '{}' is not in the library", args.name))?;
+    let path = library.prompt_path(&entry);
+    std::fs::copy(&path, &args.output)
+        .with_context(|| format!("Failed to copy {:?} to {:?}", path, args.output))?;
+    println!(
+        "  {} Exported voice '{}' to {}",
+        "✓".green(),
+        entry.name,
+        args.output.display().cyan()
+    );
+    Ok(())
+}
@@ -36,6 +36,7 @@ pub async fn run(args: WasmDemoArgs) -> Result<()> {
         host: args.host,
         port: args.port,
         voice: "alba".to_string(),
+        voice_preprocessing: Default::default(),
         variant: "b6369a24".to_string(),
         temperature: 0.7,
         lsd_decode_steps: 1,
@@ -47,6 +48,9 @@ pub async fn run(args: WasmDemoArgs) -> Result<()> {
         omp_threads: None,
         mkl_threads: None,
         ui: UiMode::WasmExperimental,
+        lexicon: None,
+        cache_dir: None,
+        cache_max_mb: 512,
     };
 
     crate::commands::serve::run(serve_args).await
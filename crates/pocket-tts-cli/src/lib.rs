@@ -1,3 +1,5 @@
 pub mod commands;
 pub mod server;
+pub mod synthesis_cache;
 pub mod voice;
+pub mod voice_library;
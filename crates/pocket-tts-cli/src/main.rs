@@ -31,6 +31,25 @@ enum Commands {
     /// Supports voice cloning using predefined voices or custom audio files.
     Generate(commands::generate::GenerateArgs),
 
+    /// Compress audio with the Mimi neural codec
+    ///
+    /// `codec encode in.wav out.mimi` stores compact Mimi latents;
+    /// `codec decode out.mimi out.wav` turns them back into audio.
+    Codec(commands::codec::CodecArgs),
+
+    /// Inspect or prune the synthesis cache
+    ///
+    /// `cache stats --dir DIR` reports cached phrases;
+    /// `cache prune --dir DIR --max-mb 100` evicts least recently used ones.
+    Cache(commands::cache::CacheArgs),
+
+    /// Create, check, combine and manage voices
+    ///
+    /// `voice check clip.wav` grades a reference clip; `voice add ceo --voice
+    /// ceo.wav` stores a named voice; `voice blend a.wav:0.7 b.wav:0.3 -o
+    /// brand.safetensors` mixes cloned voices into a new one.
+    Voice(commands::voice::VoiceArgs),
+
     /// Start the HTTP API server
     ///
     /// Runs a web server providing TTS generation via REST API.
@@ -52,6 +71,9 @@ async fn main() -> Result<()> {
             // Generate is CPU-bound, run synchronously
             commands::generate::run(cmd_args)
         }
+        Commands::Codec(cmd_args) => commands::codec::run(cmd_args),
+        Commands::Cache(cmd_args) => commands::cache::run(cmd_args),
+        Commands::Voice(cmd_args) => commands::voice::run(cmd_args),
         Commands::Serve(cmd_args) => commands::serve::run(cmd_args).await,
         Commands::WasmDemo(cmd_args) => commands::wasm_demo::run(cmd_args).await,
     }
@@ -1,7 +1,8 @@
 //! HTTP request handlers
 
 use crate::server::state::{AppState, VoiceStateCache};
-use crate::voice::{resolve_voice, voice_cache_key};
+use crate::synthesis_cache::SynthesisCache;
+use crate::voice::{cloned_voice_key, resolve_voice, uploaded_voice_analysis, voice_cache_key};
 #[cfg(feature = "web-ui")]
 use axum::extract::Path;
 #[cfg(feature = "web-ui")]
@@ -13,9 +14,14 @@ use axum::{
     http::{HeaderMap, StatusCode, header},
     response::{IntoResponse, Response},
 };
+use pocket_tts::{
+    GenerationHandle, GenerationOptions, InputFormat, Lexicon, PauseConfig, TextAnalysis,
+    UnsupportedCharPolicy, VoiceClipJoin, VoiceResolver,
+};
 #[cfg(feature = "web-ui")]
 use rust_embed::Embed;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio_stream::StreamExt as _;
 
 type SharedVoiceState = std::sync::Arc<pocket_tts::ModelState>;
@@ -156,6 +162,110 @@ pub struct GenerateRequest {
     lsd_steps: Option<usize>,
     eos_threshold: Option<f32>,
     noise_clamp: Option<f32>,
+    seed: Option<u64>,
+    continuity_frames: Option<usize>,
+    speed: Option<f32>,
+    candidates: Option<usize>,
+    normalize_text: Option<bool>,
+    input_format: Option<InputFormat>,
+    pauses: Option<PauseConfig>,
+    unsupported_chars: Option<UnsupportedCharPolicy>,
+}
+
+impl GenerateRequest {
+    /// Per-request generation options, starting from the model's defaults
+    fn generation_options(&self, model: &pocket_tts::TTSModel) -> GenerationOptions {
+        let mut options = model.generation_options();
+        if let Some(t) = self.temperature {
+            options.temperature = t;
+        }
+        if let Some(s) = self.lsd_steps {
+            options.lsd_decode_steps = s;
+        }
+        if let Some(e) = self.eos_threshold {
+            options.eos_threshold = e;
+        }
+        if let Some(nc) = self.noise_clamp {
+            options.noise_clamp = Some(nc);
+        }
+        if let Some(frames) = self.continuity_frames {
+            options.continuity_frames = frames;
+        }
+        if let Some(speed) = self.speed {
+            options.speed = speed;
+        }
+        if let Some(candidates) = self.candidates {
+            options.candidates = candidates;
+        }
+        if let Some(normalize) = self.normalize_text {
+            options.normalize_text = normalize;
+        }
+        if let Some(format) = self.input_format {
+            options.input_format = format;
+        }
+        if let Some(pauses) = &self.pauses {
+            options.pauses = pauses.clone();
+        }
+        if let Some(policy) = self.unsupported_chars {
+            options.unsupported_chars = policy;
+        }
+        options.seed = self.seed;
+        options
+    }
+
+    /// 400 response for out-of-range options or malformed SSML, checked before
+    /// queueing any work
+    fn invalid_options_response(&self, model: &pocket_tts::TTSModel) -> Option<Response> {
+        let options = self.generation_options(model);
+        let e = options
+            .validate()
+            .and_then(|()| match options.input_format {
+                InputFormat::Ssml => pocket_tts::ssml::parse_ssml(&self.text).map(|_| ()),
+                InputFormat::Text | InputFormat::Markdown => Ok(()),
+            })
+            .err()?;
+        Some(
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+                .into_response(),
+        )
+    }
+}
+
+impl GenerateRequest {
+    /// Tokenizer coverage report for the request text as it will be generated
+    fn analyze(
+        &self,
+        model: &pocket_tts::TTSModel,
+        lexicon: std::sync::Arc<Lexicon>,
+    ) -> anyhow::Result<TextAnalysis> {
+        let options = GenerationOptions {
+            lexicon: Some(lexicon),
+            ..self.generation_options(model)
+        };
+        model.analyze_text(&self.text, &options)
+    }
+
+    /// Warnings to send with the audio; fails when the `error` policy finds
+    /// characters the model cannot voice
+    fn text_warnings(
+        &self,
+        model: &pocket_tts::TTSModel,
+        lexicon: std::sync::Arc<Lexicon>,
+    ) -> anyhow::Result<Vec<String>> {
+        let analysis = self.analyze(model, lexicon)?;
+        if analysis.policy == UnsupportedCharPolicy::Error && !analysis.unsupported.is_empty() {
+            anyhow::bail!(
+                "Text contains characters the model cannot voice: {}",
+                analysis.warnings().join("; ")
+            );
+        }
+        Ok(analysis.warnings())
+    }
 }
 
 #[derive(Serialize)]
@@ -163,17 +273,92 @@ struct ErrorResponse {
     error: String,
 }
 
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error })).into_response()
+}
+
+/// Response header listing tokenizer warnings for the request text, separated
+/// by `; `
+pub const TEXT_WARNINGS_HEADER: &str = "x-text-warnings";
+
+/// Response header listing quality warnings for a voice uploaded as audio,
+/// separated by `; `
+pub const VOICE_WARNINGS_HEADER: &str = "x-voice-warnings";
+
+/// `Cache-Status` (RFC 9211) of a `/generate` response served from the
+/// synthesis cache
+const CACHE_HIT: &str = "pocket-tts; hit";
+/// `Cache-Status` of a generated response that was added to the cache
+const CACHE_STORED: &str = "pocket-tts; fwd=uri-miss; stored";
+/// `Cache-Status` of a generated response the cache failed to store
+const CACHE_MISS: &str = "pocket-tts; fwd=uri-miss";
+
+/// Complete WAV response with the optional warnings and cache headers
+fn wav_response(
+    wav_bytes: Vec<u8>,
+    warnings: &[String],
+    voice_warnings: &[String],
+    cache_status: Option<&str>,
+) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, "audio/wav".parse().unwrap());
+    headers.insert(
+        header::CONTENT_DISPOSITION,
+        "attachment; filename=\"pocket-tts-output.wav\""
+            .parse()
+            .unwrap(),
+    );
+    insert_warnings(&mut headers, TEXT_WARNINGS_HEADER, warnings);
+    insert_warnings(&mut headers, VOICE_WARNINGS_HEADER, voice_warnings);
+    if let Some(status) = cache_status {
+        headers.insert("cache-status", status.parse().unwrap());
+    }
+    (StatusCode::OK, headers, Body::from(wav_bytes)).into_response()
+}
+
+/// Add a warnings header unless there are no warnings
+fn insert_warnings(headers: &mut HeaderMap, name: &'static str, warnings: &[String]) {
+    if warnings.is_empty() {
+        return;
+    }
+    match warnings.join("; ").parse() {
+        Ok(value) => {
+            headers.insert(name, value);
+        }
+        Err(e) => tracing::warn!("{} not sent: {}", name, e),
+    }
+}
+
+/// Quality warnings for the request voice if it was uploaded as audio
+fn voice_warnings(voice_spec: Option<&str>) -> Vec<String> {
+    let Some(analysis) = voice_spec.and_then(uploaded_voice_analysis) else {
+        return Vec::new();
+    };
+    let warnings = analysis.warnings();
+    if !warnings.is_empty() {
+        tracing::warn!("Uploaded voice: {}", warnings.join("; "));
+    }
+    warnings
+}
+
 fn resolve_voice_cached(
     model: &pocket_tts::TTSModel,
     default_voice: &SharedVoiceState,
     voice_cache: &VoiceCache,
     voice_spec: Option<&str>,
 ) -> anyhow::Result<SharedVoiceState> {
-    let Some(spec) = voice_spec else {
-        return Ok(default_voice.clone());
-    };
+    match voice_spec {
+        Some(spec) => resolve_voice_spec_cached(model, voice_cache, spec),
+        None => Ok(default_voice.clone()),
+    }
+}
 
-    let key = voice_cache_key(spec);
+fn resolve_voice_spec_cached(
+    model: &pocket_tts::TTSModel,
+    voice_cache: &VoiceCache,
+    spec: &str,
+) -> anyhow::Result<SharedVoiceState> {
+    let key = voice_cache_key(spec, model.voice_preprocessing.as_ref());
 
     {
         let mut cache = voice_cache
@@ -192,10 +377,85 @@ fn resolve_voice_cached(
     Ok(resolved)
 }
 
+/// Resolves inline `[voice:...]` markers through the server's voice cache
+pub struct CachedVoiceResolver {
+    pub voice_cache: VoiceCache,
+}
+
+impl VoiceResolver for CachedVoiceResolver {
+    fn resolve(
+        &self,
+        model: &pocket_tts::TTSModel,
+        spec: &str,
+    ) -> anyhow::Result<SharedVoiceState> {
+        resolve_voice_spec_cached(model, &self.voice_cache, spec)
+    }
+}
+
+/// Cancels a generation when dropped
+///
+/// Axum drops the handler future when the client disconnects, so holding one
+/// of these in the handler stops the blocking generation task promptly.
+struct CancelOnDrop(GenerationHandle);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
 pub async fn generate(
     State(state): State<AppState>,
     Json(payload): Json<GenerateRequest>,
 ) -> Response {
+    generate_wav(state, payload, None).await
+}
+
+/// Complete WAV for `payload`, spoken by `clips` when given and otherwise by
+/// the request voice
+async fn generate_wav(
+    state: AppState,
+    payload: GenerateRequest,
+    clips: Option<UploadedClips>,
+) -> Response {
+    if let Some(response) = payload.invalid_options_response(&state.model) {
+        return response;
+    }
+    let lexicon = state.lexicon();
+    let warnings = match payload.text_warnings(&state.model, lexicon.clone()) {
+        Ok(warnings) => warnings,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    let voice_warnings = match &clips {
+        Some(clips) => clips.warnings.clone(),
+        None => voice_warnings(payload.voice.as_deref()),
+    };
+
+    // Repeated phrases are answered from disk without taking the model lock
+    let cached = state.synthesis_cache.clone().map(|cache| {
+        let voice_key = match &clips {
+            Some(clips) => clips.key.clone(),
+            None => payload.voice.as_deref().map_or_else(
+                || state.default_voice_key.clone(),
+                |spec| voice_cache_key(spec, state.model.voice_preprocessing.as_ref()),
+            ),
+        };
+        let options = GenerationOptions {
+            lexicon: Some(lexicon.clone()),
+            ..payload.generation_options(&state.model)
+        };
+        let key = SynthesisCache::key(&payload.text, &voice_key, &state.variant, &options);
+        (cache, key)
+    });
+    if let Some((cache, key)) = cached.clone()
+        && let Ok(Some(wav_bytes)) = tokio::task::spawn_blocking(move || cache.get(&key)).await
+    {
+        return wav_response(wav_bytes, &warnings, &voice_warnings, Some(CACHE_HIT));
+    }
+
+    let handle = GenerationHandle::new();
+    let _cancel_on_drop = CancelOnDrop(handle.clone());
+
     // Acquire lock for sequential processing
     let _guard = state.lock.lock().await;
 
@@ -208,30 +468,29 @@ pub async fn generate(
     // Run generation in blocking thread
     let result = tokio::task::spawn_blocking(move || {
         // Resolve voice (use default if not specified)
-        let voice_state =
-            resolve_voice_cached(&model, &default_voice, &voice_cache, voice_spec.as_deref())?;
+        let voice_state = match clips {
+            Some(clips) => resolve_clips_cached(&model, &voice_cache, clips)?,
+            None => {
+                resolve_voice_cached(&model, &default_voice, &voice_cache, voice_spec.as_deref())?
+            }
+        };
 
         // Override model params if provided in request
-        let mut model_cloned = (*model).clone();
-        if let Some(t) = payload.temperature {
-            model_cloned.temp = t;
-        }
-        if let Some(s) = payload.lsd_steps {
-            model_cloned.lsd_decode_steps = s;
-        }
-        if let Some(e) = payload.eos_threshold {
-            model_cloned.eos_threshold = e;
-        }
-        if let Some(nc) = payload.noise_clamp {
-            model_cloned.noise_clamp = Some(nc);
+        let options = GenerationOptions {
+            lexicon: Some(lexicon),
+            ..payload.generation_options(&model)
         }
+        .with_handle(handle);
 
         // Generate audio
         tracing::info!("Starting generation for text length: {} chars", text.len());
         let mut audio_chunks = Vec::new();
-        for chunk in model_cloned.generate_stream_long(&text, &voice_state) {
+        for chunk in model.generate_stream_long_with_options(&text, &voice_state, &options) {
             audio_chunks.push(chunk?);
         }
+        if options.is_cancelled() {
+            anyhow::bail!("Generation cancelled");
+        }
         if audio_chunks.is_empty() {
             anyhow::bail!("No audio generated");
         }
@@ -241,22 +500,25 @@ pub async fn generate(
         // Encode as WAV
         let mut buffer = std::io::Cursor::new(Vec::new());
         pocket_tts::audio::write_wav_to_writer(&mut buffer, &audio, model.sample_rate as u32)?;
+        let wav_bytes = buffer.into_inner();
+
+        // Still on the blocking thread, so the disk write and eviction do
+        // not stall the runtime
+        let cache_status = cached.map(|(cache, key)| match cache.put(&key, &wav_bytes) {
+            Ok(()) => CACHE_STORED,
+            Err(e) => {
+                tracing::warn!("Synthesis cache store failed: {:#}", e);
+                CACHE_MISS
+            }
+        });
 
-        Ok::<Vec<u8>, anyhow::Error>(buffer.into_inner())
+        Ok::<_, anyhow::Error>((wav_bytes, cache_status))
     })
     .await;
 
     match result {
-        Ok(Ok(wav_bytes)) => {
-            let mut headers = HeaderMap::new();
-            headers.insert(header::CONTENT_TYPE, "audio/wav".parse().unwrap());
-            headers.insert(
-                header::CONTENT_DISPOSITION,
-                "attachment; filename=\"pocket-tts-output.wav\""
-                    .parse()
-                    .unwrap(),
-            );
-            (StatusCode::OK, headers, Body::from(wav_bytes)).into_response()
+        Ok(Ok((wav_bytes, cache_status))) => {
+            wav_response(wav_bytes, &warnings, &voice_warnings, cache_status)
         }
         Ok(Err(e)) => (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -283,6 +545,16 @@ pub async fn generate_stream(
     State(state): State<AppState>,
     Json(payload): Json<GenerateRequest>,
 ) -> Response {
+    if let Some(response) = payload.invalid_options_response(&state.model) {
+        return response;
+    }
+    let lexicon = state.lexicon();
+    let warnings = match payload.text_warnings(&state.model, lexicon.clone()) {
+        Ok(warnings) => warnings,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    let voice_warnings = voice_warnings(payload.voice.as_deref());
+
     let model = state.model.clone();
     let default_voice = state.default_voice_state.clone();
     let voice_cache = state.voice_cache.clone();
@@ -293,6 +565,17 @@ pub async fn generate_stream(
     // Channel for streaming chunks
     let (tx, rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, anyhow::Error>>(10);
 
+    // Stop generating as soon as the client goes away, even mid-sentence
+    let handle = GenerationHandle::new();
+    let watcher = {
+        let tx = tx.clone();
+        let handle = handle.clone();
+        tokio::spawn(async move {
+            tx.closed().await;
+            handle.cancel();
+        })
+    };
+
     // Spawn generation task
     tokio::spawn(async move {
         let _guard = lock.lock().await;
@@ -304,27 +587,19 @@ pub async fn generate_stream(
                 resolve_voice_cached(&model, &default_voice, &voice_cache, voice_spec.as_deref())?;
 
             // Override model params if provided in request
-            let mut model_cloned = (*model).clone();
-            if let Some(t) = payload.temperature {
-                model_cloned.temp = t;
-            }
-            if let Some(s) = payload.lsd_steps {
-                model_cloned.lsd_decode_steps = s;
-            }
-            if let Some(e) = payload.eos_threshold {
-                model_cloned.eos_threshold = e;
-            }
-            if let Some(nc) = payload.noise_clamp {
-                model_cloned.noise_clamp = Some(nc);
+            let options = GenerationOptions {
+                lexicon: Some(lexicon),
+                ..payload.generation_options(&model)
             }
+            .with_handle(handle);
 
             // Stream audio chunks
             tracing::info!(
                 "Starting streaming generation for text length: {} chars",
                 text.len()
             );
-            for (i, chunk_res) in model_cloned
-                .generate_stream_long(&text, &voice_state)
+            for (i, chunk_res) in model
+                .generate_stream_long_with_options(&text, &voice_state, &options)
                 .enumerate()
             {
                 if i > 0 && i % 20 == 0 {
@@ -353,6 +628,7 @@ pub async fn generate_stream(
         if let Err(e) = result.await {
             let _ = tx.send(Err(anyhow::anyhow!("Task error: {}", e))).await;
         }
+        watcher.abort();
     });
 
     // Convert channel to stream
@@ -366,35 +642,82 @@ pub async fn generate_stream(
         },
     );
 
-    Response::builder()
+    let mut response = Response::builder()
         .header(header::CONTENT_TYPE, "application/octet-stream")
         .body(Body::from_stream(body_stream))
-        .unwrap()
+        .unwrap();
+    insert_warnings(response.headers_mut(), TEXT_WARNINGS_HEADER, &warnings);
+    insert_warnings(
+        response.headers_mut(),
+        VOICE_WARNINGS_HEADER,
+        &voice_warnings,
+    );
+    response
+}
+
+// ============================================================================
+// Text analysis
+// ============================================================================
+
+#[derive(Serialize)]
+struct AnalyzeResponse {
+    #[serde(flatten)]
+    analysis: TextAnalysis,
+    warnings: Vec<String>,
+}
+
+/// `POST /analyze`: tokenizer coverage report for a `/generate` request body,
+/// without generating audio
+pub async fn analyze(
+    State(state): State<AppState>,
+    Json(payload): Json<GenerateRequest>,
+) -> Response {
+    if let Some(response) = payload.invalid_options_response(&state.model) {
+        return response;
+    }
+    match payload.analyze(&state.model, state.lexicon()) {
+        Ok(analysis) => Json(AnalyzeResponse {
+            warnings: analysis.warnings(),
+            analysis,
+        })
+        .into_response(),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    }
 }
 
 // ============================================================================
 // Python API compatibility (/tts with multipart form)
 // ============================================================================
 
+/// Largest `/tts` form accepted, leaving room for several voice clips
+pub const TTS_FORM_MAX_BYTES: usize = 64 * 1024 * 1024;
+
 pub async fn tts_form(State(state): State<AppState>, mut multipart: Multipart) -> Response {
     let mut text: Option<String> = None;
     let mut voice_url: Option<String> = None;
-    let mut voice_wav_bytes: Option<Vec<u8>> = None;
-
-    // Parse multipart form
-    while let Ok(Some(field)) = multipart.next_field().await {
+    let mut voice_wavs: Vec<Vec<u8>> = Vec::new();
+
+    // Parse multipart form; a malformed or oversized body is rejected with
+    // the status axum assigns (400 or 413)
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return error_response(e.status(), e.body_text()),
+        };
         let name = field.name().unwrap_or("").to_string();
-        match name.as_str() {
-            "text" => {
-                text = field.text().await.ok();
-            }
-            "voice_url" => {
-                voice_url = field.text().await.ok();
-            }
-            "voice_wav" => {
-                voice_wav_bytes = field.bytes().await.ok().map(|b| b.to_vec());
-            }
-            _ => {}
+        let result = match name.as_str() {
+            "text" => field.text().await.map(|value| text = Some(value)),
+            "voice_url" => field.text().await.map(|value| voice_url = Some(value)),
+            // Repeated for several clips of one speaker
+            "voice_wav" => field
+                .bytes()
+                .await
+                .map(|bytes| voice_wavs.push(bytes.to_vec())),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            return error_response(e.status(), e.body_text());
         }
     }
 
@@ -412,32 +735,133 @@ pub async fn tts_form(State(state): State<AppState>, mut multipart: Multipart) -
     };
 
     // Determine voice
-    let voice = if let Some(bytes) = voice_wav_bytes {
-        // Use uploaded WAV - encode as base64 for our resolver
-        use base64::{Engine as _, engine::general_purpose};
-        Some(format!(
-            "data:audio/wav;base64,{}",
-            general_purpose::STANDARD.encode(&bytes)
-        ))
-    } else {
-        voice_url
+    let mut clips = None;
+    let voice = match voice_wavs.as_slice() {
+        [] => voice_url,
+        [bytes] => {
+            // Use uploaded WAV - encode as base64 for our resolver
+            use base64::{Engine as _, engine::general_purpose};
+            Some(format!(
+                "data:audio/wav;base64,{}",
+                general_purpose::STANDARD.encode(bytes)
+            ))
+        }
+        _ => {
+            let model = state.model.clone();
+            match tokio::task::spawn_blocking(move || prepare_uploaded_clips(&model, &voice_wavs))
+                .await
+            {
+                Ok(Ok(prepared)) => clips = Some(prepared),
+                Ok(Err(e)) => return error_response(StatusCode::BAD_REQUEST, format!("{:#}", e)),
+                Err(e) => {
+                    return error_response(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Task error: {}", e),
+                    );
+                }
+            }
+            None
+        }
     };
 
-    // Delegate to JSON generate handler
-    generate(
-        State(state),
-        Json(GenerateRequest {
+    // Delegate to the JSON generate handler
+    generate_wav(
+        state,
+        GenerateRequest {
             text,
             voice,
             temperature: None,
             lsd_steps: None,
             eos_threshold: None,
             noise_clamp: None,
-        }),
+            seed: None,
+            continuity_frames: None,
+            speed: None,
+            candidates: None,
+            normalize_text: None,
+            input_format: None,
+            pauses: None,
+            unsupported_chars: None,
+        },
+        clips,
     )
     .await
 }
 
+/// Several uploaded `voice_wav` clips of one speaker, ready to encode
+struct UploadedClips {
+    /// Voice cache key, derived from the clip contents
+    key: String,
+    /// Prompt audio (`[1, 1, T]`) from `TTSModel::prepare_voice_clips`
+    audio: candle_core::Tensor,
+    /// Quality warnings of each clip
+    warnings: Vec<String>,
+}
+
+/// Decode and analyze each clip, then clean up and join them like repeated
+/// `--voice` clips in `generate`
+fn prepare_uploaded_clips(
+    model: &pocket_tts::TTSModel,
+    uploads: &[Vec<u8>],
+) -> anyhow::Result<UploadedClips> {
+    let mut hasher = Sha256::new();
+    let mut clips = Vec::with_capacity(uploads.len());
+    let mut warnings = Vec::new();
+    for (index, bytes) in uploads.iter().enumerate() {
+        let (audio, sample_rate) = pocket_tts::audio::read_wav_from_bytes(bytes)
+            .map_err(|e| anyhow::anyhow!("voice_wav file {} is not valid WAV: {}", index, e))?;
+        let analysis = pocket_tts::analyze_voice_prompt(&audio, sample_rate)?;
+        warnings.extend(
+            analysis
+                .warnings()
+                .into_iter()
+                .map(|warning| format!("voice_wav file {}: {}", index, warning)),
+        );
+        hasher.update(Sha256::digest(bytes));
+        clips.push((audio, sample_rate));
+    }
+    if !warnings.is_empty() {
+        tracing::warn!("Uploaded voice: {}", warnings.join("; "));
+    }
+
+    let key = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    Ok(UploadedClips {
+        key: cloned_voice_key(
+            &format!("clips:{}", key),
+            model.voice_preprocessing.as_ref(),
+        ),
+        audio: model.prepare_voice_clips(&clips, &VoiceClipJoin::default())?,
+        warnings,
+    })
+}
+
+/// Voice state of uploaded clips, encoded once per distinct upload
+fn resolve_clips_cached(
+    model: &pocket_tts::TTSModel,
+    voice_cache: &VoiceCache,
+    clips: UploadedClips,
+) -> anyhow::Result<SharedVoiceState> {
+    {
+        let mut cache = voice_cache
+            .lock()
+            .map_err(|_| anyhow::anyhow!("voice cache lock poisoned"))?;
+        if let Some(cached) = cache.get(&clips.key) {
+            return Ok(cached.clone());
+        }
+    }
+
+    let resolved = std::sync::Arc::new(model.get_voice_state_from_tensor(&clips.audio)?);
+    let mut cache = voice_cache
+        .lock()
+        .map_err(|_| anyhow::anyhow!("voice cache lock poisoned"))?;
+    cache.put(clips.key, resolved.clone());
+    Ok(resolved)
+}
+
 // ============================================================================
 // OpenAI compatibility
 // ============================================================================
@@ -449,6 +873,7 @@ pub struct OpenAIRequest {
     input: String,
     voice: Option<String>,
     response_format: Option<String>,
+    speed: Option<f32>,
 }
 
 pub async fn openai_speech(state: State<AppState>, Json(payload): Json<OpenAIRequest>) -> Response {
@@ -460,6 +885,67 @@ pub async fn openai_speech(state: State<AppState>, Json(payload): Json<OpenAIReq
         lsd_steps: None,
         eos_threshold: None,
         noise_clamp: None,
+        seed: None,
+        continuity_frames: None,
+        speed: payload.speed,
+        candidates: None,
+        normalize_text: None,
+        input_format: None,
+        pauses: None,
+        unsupported_chars: None,
     };
     generate(state, Json(req)).await
 }
+
+// ============================================================================
+// Pronunciation lexicon
+// ============================================================================
+
+/// Body of `POST /lexicon` and `DELETE /lexicon`: exactly one of `word` or
+/// `pattern`, plus `respelling` when adding
+#[derive(Deserialize)]
+pub struct LexiconEntryRequest {
+    word: Option<String>,
+    pattern: Option<String>,
+    respelling: Option<String>,
+}
+
+pub async fn get_lexicon(State(state): State<AppState>) -> Response {
+    Json(Lexicon::clone(&state.lexicon())).into_response()
+}
+
+pub async fn add_lexicon_entry(
+    State(state): State<AppState>,
+    Json(entry): Json<LexiconEntryRequest>,
+) -> Response {
+    let result =
+        state.update_lexicon(
+            |lexicon| match (&entry.word, &entry.pattern, &entry.respelling) {
+                (Some(word), None, Some(respelling)) => lexicon.insert_word(word, respelling),
+                (None, Some(pattern), Some(respelling)) => {
+                    lexicon.insert_pattern(pattern, respelling)
+                }
+                _ => anyhow::bail!("expected `respelling` and exactly one of `word` or `pattern`"),
+            },
+        );
+    match result {
+        Ok(()) => get_lexicon(State(state)).await,
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+pub async fn remove_lexicon_entry(
+    State(state): State<AppState>,
+    Json(entry): Json<LexiconEntryRequest>,
+) -> Response {
+    let result = state.update_lexicon(|lexicon| match (&entry.word, &entry.pattern) {
+        (Some(word), None) => Ok(lexicon.remove_word(word)),
+        (None, Some(pattern)) => Ok(lexicon.remove_pattern(pattern)),
+        _ => anyhow::bail!("expected exactly one of `word` or `pattern`"),
+    });
+    match result {
+        Ok(true) => get_lexicon(State(state)).await,
+        Ok(false) => error_response(StatusCode::NOT_FOUND, "no such lexicon entry".to_string()),
+        Err(e) => error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
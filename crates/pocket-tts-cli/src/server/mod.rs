@@ -3,9 +3,10 @@
 //! Axum-based server providing TTS generation endpoints.
 
 use anyhow::Result;
-use pocket_tts::TTSModel;
+use pocket_tts::{Lexicon, TTSModel};
 
 use crate::commands::serve::{ServeArgs, UiMode, print_endpoints};
+use crate::synthesis_cache::SynthesisCache;
 use crate::voice::{resolve_voice, voice_cache_key};
 
 pub mod handlers;
@@ -72,6 +73,8 @@ pub async fn start_server(args: ServeArgs) -> Result<()> {
         )?
     };
 
+    let model = model.with_voice_preprocessing(args.voice_preprocessing.preprocessing());
+
     println!("  ✓ Model loaded (sample rate: {}Hz)", model.sample_rate);
 
     // Pre-load default voice
@@ -86,13 +89,40 @@ pub async fn start_server(args: ServeArgs) -> Result<()> {
         args.ui,
         wasm_pkg_dir,
     );
+    let state = match &args.lexicon {
+        Some(path) => {
+            let lexicon = Lexicon::load(path)?;
+            println!("  ✓ Lexicon loaded: {} entries", lexicon.len());
+            state.with_lexicon(lexicon)
+        }
+        None => state,
+    };
+    let state = match &args.cache_dir {
+        Some(dir) => {
+            let cache = SynthesisCache::open(dir, args.cache_max_mb.saturating_mul(1024 * 1024))?;
+            println!(
+                "  ✓ Synthesis cache: {} (up to {} MB)",
+                dir.display(),
+                args.cache_max_mb
+            );
+            // Quantized weights sound different, so they get their own
+            // entries; cleaned-up voices are told apart by their voice key
+            let mut variant = args.variant.clone();
+            if args.quantized {
+                variant.push_str(":quantized");
+            }
+            let voice_key = voice_cache_key(&args.voice, state.model.voice_preprocessing.as_ref());
+            state.with_synthesis_cache(cache, &variant, &voice_key)
+        }
+        None => state,
+    };
     {
         let mut cache = state
             .voice_cache
             .lock()
             .map_err(|_| anyhow::anyhow!("voice cache lock poisoned"))?;
         cache.put(
-            voice_cache_key(&args.voice),
+            voice_cache_key(&args.voice, state.model.voice_preprocessing.as_ref()),
             state.default_voice_state.clone(),
         );
     }
@@ -103,7 +133,7 @@ pub async fn start_server(args: ServeArgs) -> Result<()> {
         .map(str::trim)
         .filter(|s| !s.is_empty())
     {
-        let key = voice_cache_key(voice);
+        let key = voice_cache_key(voice, state.model.voice_preprocessing.as_ref());
         let already_cached = {
             let mut cache = state
                 .voice_cache
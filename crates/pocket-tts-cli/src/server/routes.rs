@@ -4,6 +4,8 @@ use crate::server::handlers;
 use crate::server::state::AppState;
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
+    http::HeaderName,
     routing::{get, post},
 };
 use tower_http::cors::{Any, CorsLayer};
@@ -14,7 +16,11 @@ pub fn create_router(state: AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers([
+            HeaderName::from_static(handlers::TEXT_WARNINGS_HEADER),
+            HeaderName::from_static(handlers::VOICE_WARNINGS_HEADER),
+        ]);
 
     let router = Router::new()
         // Health check
@@ -22,10 +28,21 @@ pub fn create_router(state: AppState) -> Router {
         // Generation endpoints
         .route("/generate", post(handlers::generate))
         .route("/stream", post(handlers::generate_stream))
+        .route("/analyze", post(handlers::analyze))
         // Python API compatibility (multipart form)
-        .route("/tts", post(handlers::tts_form))
+        .route(
+            "/tts",
+            post(handlers::tts_form).layer(DefaultBodyLimit::max(handlers::TTS_FORM_MAX_BYTES)),
+        )
         // OpenAI compatibility
-        .route("/v1/audio/speech", post(handlers::openai_speech));
+        .route("/v1/audio/speech", post(handlers::openai_speech))
+        // Pronunciation lexicon
+        .route(
+            "/lexicon",
+            get(handlers::get_lexicon)
+                .post(handlers::add_lexicon_entry)
+                .delete(handlers::remove_lexicon_entry),
+        );
 
     #[cfg(feature = "web-ui")]
     let router = router.route("/wasm/pkg/*path", get(handlers::serve_wasm_pkg));
@@ -1,13 +1,15 @@
 //! Server state management
 
-use pocket_tts::{ModelState, TTSModel};
+use pocket_tts::{Lexicon, ModelState, TTSModel};
 use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
-use std::sync::Arc;
 use std::sync::Mutex as StdMutex;
+use std::sync::{Arc, PoisonError, RwLock};
 use tokio::sync::Mutex;
 
+use super::handlers::CachedVoiceResolver;
 use crate::commands::serve::UiMode;
+use crate::synthesis_cache::SynthesisCache;
 
 #[derive(Debug)]
 pub struct VoiceStateCache {
@@ -74,6 +76,14 @@ pub struct AppState {
     pub ui_mode: UiMode,
     /// Filesystem location of generated WASM JS/WASM assets.
     pub wasm_pkg_dir: PathBuf,
+    /// Pronunciation lexicon; requests take a snapshot, edits swap in a new one
+    pub lexicon: Arc<RwLock<Arc<Lexicon>>>,
+    /// On-disk cache of generated WAV files, if enabled
+    pub synthesis_cache: Option<Arc<SynthesisCache>>,
+    /// Model variant, part of synthesis cache keys
+    pub variant: String,
+    /// Voice cache key of the default voice, part of synthesis cache keys
+    pub default_voice_key: String,
 }
 
 impl AppState {
@@ -84,15 +94,66 @@ impl AppState {
         ui_mode: UiMode,
         wasm_pkg_dir: PathBuf,
     ) -> Self {
+        let voice_cache = Arc::new(StdMutex::new(VoiceStateCache::new(voice_cache_capacity)));
+        // [voice:...] markers in request text share the request voice cache
+        let model = model.with_voice_resolver(CachedVoiceResolver {
+            voice_cache: voice_cache.clone(),
+        });
         Self {
             model: Arc::new(model),
             default_voice_state: Arc::new(default_voice_state),
-            voice_cache: Arc::new(StdMutex::new(VoiceStateCache::new(voice_cache_capacity))),
+            voice_cache,
             lock: Arc::new(Mutex::new(())),
             ui_mode,
             wasm_pkg_dir,
+            lexicon: Arc::new(RwLock::new(Arc::new(Lexicon::new()))),
+            synthesis_cache: None,
+            variant: String::new(),
+            default_voice_key: String::new(),
         }
     }
+
+    /// Serve repeated `/generate` requests from `cache`; `variant` and
+    /// `default_voice_key` describe the loaded model and default voice
+    pub fn with_synthesis_cache(
+        mut self,
+        cache: SynthesisCache,
+        variant: &str,
+        default_voice_key: &str,
+    ) -> Self {
+        self.synthesis_cache = Some(Arc::new(cache));
+        self.variant = variant.to_string();
+        self.default_voice_key = default_voice_key.to_string();
+        self
+    }
+
+    /// Start with `lexicon` instead of an empty one
+    pub fn with_lexicon(self, lexicon: Lexicon) -> Self {
+        *self.lexicon.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(lexicon);
+        self
+    }
+
+    /// The current lexicon
+    pub fn lexicon(&self) -> Arc<Lexicon> {
+        self.lexicon
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Edit a copy of the lexicon and swap it in if `edit` succeeds
+    ///
+    /// Generations already running keep the snapshot they started with.
+    pub fn update_lexicon<T>(
+        &self,
+        edit: impl FnOnce(&mut Lexicon) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let mut current = self.lexicon.write().unwrap_or_else(PoisonError::into_inner);
+        let mut lexicon = Lexicon::clone(&current);
+        let result = edit(&mut lexicon)?;
+        *current = Arc::new(lexicon);
+        Ok(result)
+    }
 }
 
 #[cfg(test)]
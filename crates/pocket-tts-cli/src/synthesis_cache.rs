@@ -0,0 +1,372 @@
+//! Content-addressed on-disk cache of synthesized audio
+//!
+//! IVR prompts and notifications repeat the same phrases, so the server can
+//! keep finished WAV files on disk and return them without running the model.
+//! An entry is addressed by the SHA-256 of everything that shapes the audio:
+//! the whitespace-normalized text, the voice key from
+//! [`crate::voice::voice_cache_key`], the model variant and the generation
+//! options, seed included. Unseeded requests share one entry, so the first
+//! take is the one that gets repeated.
+//!
+//! Hits refresh the entry's modification time. When a store pushes the cache
+//! over its size limit, the least recently used entries are deleted.
+
+use anyhow::{Context, Result};
+use pocket_tts::{GenerationOptions, InputFormat};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Bumped whenever the key derivation or the stored format changes
+const KEY_VERSION: &str = "pocket-tts-synthesis-v2";
+
+/// File extension of cache entries
+const ENTRY_EXTENSION: &str = "wav";
+
+/// The generation options that shape the audio, as JSON with one named field
+/// each. Destructured in full so a new option cannot be left out of the key.
+fn options_key(options: &GenerationOptions) -> String {
+    let GenerationOptions {
+        temperature,
+        lsd_decode_steps,
+        eos_threshold,
+        noise_clamp,
+        frames_after_eos,
+        max_frames_per_chunk,
+        max_tokens_per_chunk,
+        seed,
+        continuity_frames,
+        handle: _,
+        speed,
+        candidates,
+        normalize_text,
+        input_format,
+        ssml_audio,
+        lexicon,
+        pauses,
+        markdown,
+        unsupported_chars,
+    } = options;
+    let input_format = match input_format {
+        InputFormat::Text => "text",
+        InputFormat::Ssml => "ssml",
+        InputFormat::Markdown => "markdown",
+    };
+    serde_json::json!({
+        "temperature": temperature,
+        "lsd_decode_steps": lsd_decode_steps,
+        "eos_threshold": eos_threshold,
+        "noise_clamp": noise_clamp,
+        "frames_after_eos": frames_after_eos,
+        "max_frames_per_chunk": max_frames_per_chunk,
+        "max_tokens_per_chunk": max_tokens_per_chunk,
+        "seed": seed,
+        "continuity_frames": continuity_frames,
+        "speed": speed,
+        "candidates": candidates,
+        "normalize_text": normalize_text,
+        "input_format": input_format,
+        "ssml_audio": ssml_audio,
+        "lexicon": lexicon.as_deref(),
+        "pauses": pauses,
+        "markdown": markdown,
+        "unsupported_chars": unsupported_chars,
+    })
+    .to_string()
+}
+
+/// WAV files addressed by [`SynthesisCache::key`]
+#[derive(Debug, Clone)]
+pub struct SynthesisCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+/// Size and age of the cache contents
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+    /// Last use of the least recently used entry
+    pub oldest: Option<SystemTime>,
+    /// Last use of the most recently used entry
+    pub newest: Option<SystemTime>,
+}
+
+/// Entries deleted by [`SynthesisCache::prune`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    pub removed: usize,
+    pub freed_bytes: u64,
+}
+
+struct Entry {
+    path: PathBuf,
+    bytes: u64,
+    used: SystemTime,
+}
+
+impl SynthesisCache {
+    /// Open (and create) the cache directory, keeping at most `max_bytes` of
+    /// audio
+    pub fn open<P: AsRef<Path>>(dir: P, max_bytes: u64) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create cache directory {:?}", dir))?;
+        Ok(Self { dir, max_bytes })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.max_bytes
+    }
+
+    /// Cache key for `text` spoken by the voice behind `voice_key` with
+    /// `options` on model `variant`
+    ///
+    /// Leading, trailing and repeated whitespace does not change the key.
+    /// The options' cancellation handle is ignored.
+    pub fn key(text: &str, voice_key: &str, variant: &str, options: &GenerationOptions) -> String {
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let options = options_key(options);
+
+        let mut hasher = Sha256::new();
+        for part in [KEY_VERSION, &text, voice_key, variant, &options] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// WAV bytes stored under `key`, marking the entry as recently used
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let path = self.entry_path(key);
+        let bytes = std::fs::read(&path).ok()?;
+        if let Err(e) = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()))
+        {
+            tracing::warn!("Failed to refresh cache entry {:?}: {}", path, e);
+        }
+        Some(bytes)
+    }
+
+    /// Store WAV bytes under `key`, then evict least recently used entries
+    /// until the cache fits its size limit again
+    pub fn put(&self, key: &str, wav: &[u8]) -> Result<()> {
+        let path = self.entry_path(key);
+        // Write then rename, so readers never see a partial file
+        let tmp = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        std::fs::write(&tmp, wav).with_context(|| format!("Failed to write {:?}", tmp))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {:?}", path))?;
+        self.prune(self.max_bytes, None)?;
+        Ok(())
+    }
+
+    /// Number, size and age of the stored entries
+    pub fn stats(&self) -> Result<CacheStats> {
+        let entries = self.entries()?;
+        Ok(CacheStats {
+            entries: entries.len(),
+            bytes: entries.iter().map(|e| e.bytes).sum(),
+            oldest: entries.first().map(|e| e.used),
+            newest: entries.last().map(|e| e.used),
+        })
+    }
+
+    /// Delete entries unused for longer than `max_age`, then least recently
+    /// used entries until at most `max_bytes` remain
+    pub fn prune(&self, max_bytes: u64, max_age: Option<Duration>) -> Result<PruneStats> {
+        let entries = self.entries()?;
+        let now = SystemTime::now();
+        let mut remaining: u64 = entries.iter().map(|e| e.bytes).sum();
+        let mut stats = PruneStats::default();
+        for entry in entries {
+            let expired = max_age.is_some_and(|max_age| {
+                now.duration_since(entry.used)
+                    .is_ok_and(|age| age > max_age)
+            });
+            if !expired && remaining <= max_bytes {
+                break;
+            }
+            match std::fs::remove_file(&entry.path) {
+                Ok(()) => {
+                    remaining -= entry.bytes;
+                    stats.removed += 1;
+                    stats.freed_bytes += entry.bytes;
+                }
+                // Another process already evicted it
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    remaining -= entry.bytes;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to remove {:?}", entry.path));
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(key).with_extension(ENTRY_EXTENSION)
+    }
+
+    /// Stored entries, least recently used first
+    fn entries(&self) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        let dir = std::fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read cache directory {:?}", self.dir))?;
+        for item in dir {
+            let path = item?.path();
+            if path.extension().is_none_or(|ext| ext != ENTRY_EXTENSION) {
+                continue;
+            }
+            let Ok(metadata) = std::fs::metadata(&path) else {
+                continue;
+            };
+            entries.push(Entry {
+                path,
+                bytes: metadata.len(),
+                used: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+        entries.sort_by_key(|e| e.used);
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str, max_bytes: u64) -> SynthesisCache {
+        let dir = std::env::temp_dir().join(format!(
+            "pocket_tts_synthesis_cache_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        SynthesisCache::open(dir, max_bytes).unwrap()
+    }
+
+    fn set_used(cache: &SynthesisCache, key: &str, secs_ago: u64) {
+        std::fs::File::options()
+            .write(true)
+            .open(cache.entry_path(key))
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(secs_ago))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_key_covers_text_voice_variant_and_options() {
+        let options = GenerationOptions::default();
+        let key = SynthesisCache::key("Press one  for sales.", "stock:alba", "b6369a24", &options);
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            key,
+            SynthesisCache::key(
+                " Press one for\nsales. ",
+                "stock:alba",
+                "b6369a24",
+                &options
+            )
+        );
+        assert_eq!(
+            key,
+            SynthesisCache::key(
+                "Press one for sales.",
+                "stock:alba",
+                "b6369a24",
+                &options
+                    .clone()
+                    .with_handle(pocket_tts::GenerationHandle::new())
+            )
+        );
+
+        let different = [
+            SynthesisCache::key("Press two for sales.", "stock:alba", "b6369a24", &options),
+            SynthesisCache::key("Press one for sales.", "stock:marius", "b6369a24", &options),
+            SynthesisCache::key("Press one for sales.", "stock:alba", "other", &options),
+            SynthesisCache::key(
+                "Press one for sales.",
+                "stock:alba",
+                "b6369a24",
+                &options.clone().with_seed(Some(7)),
+            ),
+            SynthesisCache::key(
+                "Press one for sales.",
+                "stock:alba",
+                "b6369a24",
+                &GenerationOptions {
+                    speed: 1.5,
+                    ..options.clone()
+                },
+            ),
+        ];
+        for other in different {
+            assert_ne!(key, other);
+        }
+    }
+
+    #[test]
+    fn test_put_get_and_lru_eviction() {
+        let cache = temp_cache("lru", 25);
+        assert_eq!(cache.get("a"), None);
+
+        cache.put("a", &[1; 10]).unwrap();
+        cache.put("b", &[2; 10]).unwrap();
+        set_used(&cache, "a", 20);
+        set_used(&cache, "b", 10);
+        // A hit makes "a" the most recently used entry
+        assert_eq!(cache.get("a"), Some(vec![1; 10]));
+
+        cache.put("c", &[3; 10]).unwrap();
+        assert_eq!(cache.get("b"), None);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.stats().unwrap().entries, 2);
+        assert_eq!(cache.stats().unwrap().bytes, 20);
+
+        let _ = std::fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn test_prune_by_age_and_size() {
+        let cache = temp_cache("prune", u64::MAX);
+        for key in ["a", "b", "c"] {
+            cache.put(key, &[0; 10]).unwrap();
+        }
+        set_used(&cache, "a", 3 * 3600);
+        set_used(&cache, "b", 2 * 3600);
+        set_used(&cache, "c", 60);
+
+        let pruned = cache
+            .prune(u64::MAX, Some(Duration::from_secs(2 * 3600 + 60)))
+            .unwrap();
+        assert_eq!(
+            pruned,
+            PruneStats {
+                removed: 1,
+                freed_bytes: 10
+            }
+        );
+
+        let pruned = cache.prune(10, None).unwrap();
+        assert_eq!(pruned.removed, 1);
+        assert!(cache.get("c").is_some());
+
+        cache.prune(0, None).unwrap();
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+
+        let _ = std::fs::remove_dir_all(cache.dir());
+    }
+}
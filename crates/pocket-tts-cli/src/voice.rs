@@ -2,15 +2,22 @@
 //!
 //! This module provides unified voice resolution logic supporting:
 //! - Predefined voice names (alba, marius, etc.)
+//! - Named voices from the voice library ([`crate::voice_library`])
 //! - Local file paths
 //! - HuggingFace URLs (hf://...)
 //! - Base64-encoded audio data
+//!
+//! It also holds the reference audio cleanup flags shared by every command
+//! that clones voices.
 
 use anyhow::{Context, Result};
-use pocket_tts::TTSModel;
 use pocket_tts::weights::download_if_necessary;
-use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use pocket_tts::{TTSModel, VoicePreprocessing, VoiceResolver};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::voice_library::VoiceLibrary;
 
 /// Predefined stock voices from kyutai/pocket-tts-without-voice-cloning
 pub const PREDEFINED_VOICES: &[&str] = &[
@@ -20,54 +27,195 @@ pub const PREDEFINED_VOICES: &[&str] = &[
 /// HuggingFace repo for stock voice embeddings
 const STOCK_VOICE_REPO: &str = "kyutai/pocket-tts-without-voice-cloning";
 
+/// Reference audio cleanup flags (see [`VoicePreprocessing`])
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct VoicePreprocessArgs {
+    /// Clean up WAV reference audio before cloning: trim and shorten silences,
+    /// remove DC and rumble, normalize loudness and keep the best 30 seconds
+    #[arg(long)]
+    pub preprocess_voice: bool,
+
+    /// High-pass cutoff in Hz for the cleanup; 0 turns the filter off
+    /// (default: 80)
+    #[arg(long, value_name = "HZ", requires = "preprocess_voice")]
+    pub voice_high_pass_hz: Option<f32>,
+
+    /// Shorten pauses in the reference audio to this many milliseconds; 0
+    /// keeps them (default: 300)
+    #[arg(long, value_name = "MS", requires = "preprocess_voice")]
+    pub voice_max_silence_ms: Option<u32>,
+
+    /// Frames this many dB below the loudest frame count as silence
+    /// (default: 40)
+    #[arg(long, value_name = "DB", requires = "preprocess_voice")]
+    pub voice_silence_threshold_db: Option<f32>,
+
+    /// RMS loudness of the voiced frames in dBFS (default: -20)
+    #[arg(
+        long,
+        value_name = "DB",
+        requires = "preprocess_voice",
+        allow_hyphen_values = true
+    )]
+    pub voice_loudness_db: Option<f32>,
+
+    /// Keep at most this many seconds of reference audio; 0 keeps it all
+    /// (default: 30)
+    #[arg(long, value_name = "SECS", requires = "preprocess_voice")]
+    pub voice_max_duration_secs: Option<f32>,
+
+    /// Keep the DC offset of the reference audio
+    #[arg(long, requires = "preprocess_voice")]
+    pub voice_keep_dc: bool,
+
+    /// Keep silence at the start and end of the reference audio
+    #[arg(long, requires = "preprocess_voice")]
+    pub voice_keep_edges: bool,
+
+    /// Keep the loudness of the reference audio
+    #[arg(
+        long,
+        requires = "preprocess_voice",
+        conflicts_with = "voice_loudness_db"
+    )]
+    pub voice_keep_loudness: bool,
+}
+
+impl VoicePreprocessArgs {
+    /// The cleanup settings, or `None` without `--preprocess-voice`
+    pub fn preprocessing(&self) -> Option<VoicePreprocessing> {
+        if !self.preprocess_voice {
+            return None;
+        }
+        let defaults = VoicePreprocessing::default();
+        let positive = |value: f32| (value > 0.0).then_some(value);
+        Some(VoicePreprocessing {
+            remove_dc: !self.voice_keep_dc,
+            high_pass_hz: self
+                .voice_high_pass_hz
+                .map_or(defaults.high_pass_hz, positive),
+            trim_silence: !self.voice_keep_edges,
+            max_silence_ms: self
+                .voice_max_silence_ms
+                .map_or(defaults.max_silence_ms, |ms| (ms > 0).then_some(ms)),
+            silence_threshold_db: self
+                .voice_silence_threshold_db
+                .unwrap_or(defaults.silence_threshold_db),
+            target_loudness_db: match self.voice_keep_loudness {
+                true => None,
+                false => self.voice_loudness_db.or(defaults.target_loudness_db),
+            },
+            max_duration_secs: self
+                .voice_max_duration_secs
+                .map_or(defaults.max_duration_secs, positive),
+        })
+    }
+}
+
+/// `hf://` URL of a stock voice's embeddings
+pub fn stock_voice_url(name: &str) -> String {
+    format!("hf://{}/embeddings/{}.safetensors", STOCK_VOICE_REPO, name)
+}
+
 /// Build a stable cache key for a voice specification.
 ///
-/// File keys include mtime/size so updates invalidate cached entries.
-pub fn voice_cache_key(spec: &str) -> String {
+/// File keys include mtime/size so updates invalidate cached entries. Specs
+/// cloned from audio also include the cleanup settings, since they change
+/// the voice.
+pub fn voice_cache_key(spec: &str, preprocessing: Option<&VoicePreprocessing>) -> String {
     let spec = spec.trim();
 
     if PREDEFINED_VOICES.contains(&spec) {
         return format!("stock:{spec}");
     }
+    if let Some(path) = library_voice_path(spec) {
+        return format!("library:{spec}:{}", file_key(&path));
+    }
     if spec.starts_with("hf://") {
-        return format!("hf:{spec}");
+        let key = format!("hf:{spec}");
+        return match is_wav(Path::new(spec)) {
+            true => cloned_voice_key(&key, preprocessing),
+            false => key,
+        };
     }
 
     let path = PathBuf::from(spec);
     if path.exists() {
-        let canonical = std::fs::canonicalize(&path).unwrap_or(path.clone());
-        let (mtime_secs, size) = std::fs::metadata(&canonical)
-            .ok()
-            .map(|m| {
-                let modified = m
-                    .modified()
-                    .ok()
-                    .and_then(|ts| ts.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
-                (modified, m.len())
-            })
-            .unwrap_or((0, 0));
-        return format!("file:{}:{mtime_secs}:{size}", canonical.display());
+        let key = format!("file:{}", file_key(&path));
+        return match is_wav(&path) {
+            true => cloned_voice_key(&key, preprocessing),
+            false => key,
+        };
     }
 
-    if is_base64_audio(spec) {
-        return format!("b64:{:016x}", hash_str(spec));
+    let key = match is_base64_audio(spec) {
+        true => format!("b64:{:016x}", hash_str(spec)),
+        false => format!("raw:{}:{:016x}", spec.len(), hash_str(spec)),
+    };
+    cloned_voice_key(&key, preprocessing)
+}
+
+/// `key` of a voice cloned from audio, extended with the cleanup settings
+/// applied to that audio
+pub fn cloned_voice_key(key: &str, preprocessing: Option<&VoicePreprocessing>) -> String {
+    match preprocessing.and_then(|p| serde_json::to_string(p).ok()) {
+        Some(json) => format!("{key}:pre:{:016x}", hash_str(&json)),
+        None => key.to_string(),
     }
+}
+
+/// Whether `path` names WAV audio
+fn is_wav(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("wav") || ext.eq_ignore_ascii_case("wave"))
+}
 
-    format!("raw:{}:{:016x}", spec.len(), hash_str(spec))
+/// Canonical path, modification time and size of a file
+fn file_key(path: &Path) -> String {
+    let canonical = std::fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    let (mtime_secs, size) = std::fs::metadata(&canonical)
+        .ok()
+        .map(|m| {
+            let modified = m
+                .modified()
+                .ok()
+                .and_then(|ts| ts.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            (modified, m.len())
+        })
+        .unwrap_or((0, 0));
+    format!("{}:{mtime_secs}:{size}", canonical.display())
 }
 
+/// Prompt file of the library voice called `name`, if there is one
+///
+/// Specs that are not valid library names never touch the library.
+fn library_voice_path(name: &str) -> Option<PathBuf> {
+    crate::voice_library::validate_name(name).ok()?;
+    let library = VoiceLibrary::open_default().ok()?;
+    match library.get(name) {
+        Ok(entry) => entry.map(|entry| library.prompt_path(&entry)),
+        Err(e) => {
+            tracing::warn!("Failed to read voice library {:?}: {}", library.dir(), e);
+            None
+        }
+    }
+}
+
+/// First 64 bits of the SHA-256 of `s`, stable across builds so keys can
+/// address the on-disk synthesis cache
 fn hash_str(s: &str) -> u64 {
-    let mut h = std::collections::hash_map::DefaultHasher::new();
-    s.hash(&mut h);
-    h.finish()
+    let digest = Sha256::digest(s.as_bytes());
+    u64::from_be_bytes(digest[..8].try_into().unwrap())
 }
 
 /// Resolve a voice specification to a ModelState
 ///
 /// Supports multiple input formats:
 /// - Predefined names: "alba", "marius", etc.
+/// - Library names added with `pocket-tts voice add`
 /// - Local paths: "/path/to/audio.wav" or "/path/to/embeddings.safetensors"
 /// - HF URLs: "hf://owner/repo/file.wav"
 /// - Base64 audio: "data:audio/wav;base64,..." or raw base64 string
@@ -81,6 +229,60 @@ pub fn resolve_voice(model: &TTSModel, voice_spec: Option<&str>) -> Result<pocke
     }
 }
 
+/// Resolve one or more voice specifications to a ModelState
+///
+/// Several specs clone a single voice from all of them (see
+/// [`TTSModel::get_voice_state_from_files`]); each must then be WAV audio.
+pub fn resolve_voices(model: &TTSModel, specs: &[String]) -> Result<pocket_tts::ModelState> {
+    match specs {
+        [] => resolve_voice(model, None),
+        [spec] => resolve_voice(model, Some(spec)),
+        specs => {
+            let paths = voice_clip_paths(specs)?;
+            model
+                .get_voice_state_from_files(&paths)
+                .context("Failed to clone voice from clips")
+        }
+    }
+}
+
+/// Local WAV files behind specs used as clips of one speaker (paths or
+/// hf:// URLs)
+pub fn voice_clip_paths(specs: &[String]) -> Result<Vec<PathBuf>> {
+    specs
+        .iter()
+        .map(|spec| {
+            let spec = spec.trim();
+            let path = if spec.starts_with("hf://") {
+                download_if_necessary(spec)
+                    .with_context(|| format!("Failed to download voice from '{}'", spec))?
+            } else {
+                PathBuf::from(spec)
+            };
+            if !path.exists() {
+                anyhow::bail!("Voice clip {:?} not found", spec);
+            }
+            if !is_wav(&path) {
+                anyhow::bail!(
+                    "Voice clip '{}' is not a WAV file; only audio clips can be combined",
+                    spec
+                );
+            }
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Resolves inline `[voice:...]` markers with the same specs as `--voice`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpecVoiceResolver;
+
+impl VoiceResolver for SpecVoiceResolver {
+    fn resolve(&self, model: &TTSModel, spec: &str) -> Result<Arc<pocket_tts::ModelState>> {
+        Ok(Arc::new(resolve_voice_spec(model, spec)?))
+    }
+}
+
 /// Resolve a specific voice specification
 fn resolve_voice_spec(model: &TTSModel, spec: &str) -> Result<pocket_tts::ModelState> {
     let spec = spec.trim();
@@ -90,18 +292,25 @@ fn resolve_voice_spec(model: &TTSModel, spec: &str) -> Result<pocket_tts::ModelS
         return resolve_predefined_voice(model, spec);
     }
 
-    // 2. Check if it's an hf:// URL
+    // 2. Check if it's a voice from the library
+    if let Some(path) = library_voice_path(spec) {
+        return model
+            .get_voice_state_from_prompt_file(&path)
+            .with_context(|| format!("Failed to load library voice '{}'", spec));
+    }
+
+    // 3. Check if it's an hf:// URL
     if spec.starts_with("hf://") {
         return resolve_hf_voice(model, spec);
     }
 
-    // 3. Check if it's a file path that exists
+    // 4. Check if it's a file path that exists
     let path = PathBuf::from(spec);
     if path.exists() {
         return resolve_file_voice(model, &path);
     }
 
-    // 4. Check if it's base64 encoded audio
+    // 5. Check if it's base64 encoded audio
     if is_base64_audio(spec) {
         return resolve_base64_voice(model, spec);
     }
@@ -110,6 +319,7 @@ fn resolve_voice_spec(model: &TTSModel, spec: &str) -> Result<pocket_tts::ModelS
     anyhow::bail!(
         "Voice '{}' not found. Expected one of:\n\
          - Predefined name: {}\n\
+         - Library voice: see `pocket-tts voice list`\n\
          - File path: /path/to/voice.wav or /path/to/embeddings.safetensors\n\
          - HuggingFace URL: hf://owner/repo/file.wav\n\
          - Base64 audio: data:audio/wav;base64,...",
@@ -120,7 +330,7 @@ fn resolve_voice_spec(model: &TTSModel, spec: &str) -> Result<pocket_tts::ModelS
 
 /// Resolve a predefined voice name to embeddings via HF Hub
 fn resolve_predefined_voice(model: &TTSModel, name: &str) -> Result<pocket_tts::ModelState> {
-    let hf_path = format!("hf://{}/embeddings/{}.safetensors", STOCK_VOICE_REPO, name);
+    let hf_path = stock_voice_url(name);
 
     let local_path = download_if_necessary(&hf_path)
         .with_context(|| format!("Failed to download stock voice '{}'", name))?;
@@ -188,8 +398,21 @@ fn is_base64_audio(spec: &str) -> bool {
     false
 }
 
-/// Resolve base64-encoded audio data
-fn resolve_base64_voice(model: &TTSModel, spec: &str) -> Result<pocket_tts::ModelState> {
+/// Quality report for a voice uploaded as base64 audio
+///
+/// `None` for every other kind of spec, and for uploads that cannot be
+/// decoded (resolving them reports the error).
+pub fn uploaded_voice_analysis(spec: &str) -> Option<pocket_tts::VoiceAnalysis> {
+    let spec = spec.trim();
+    if !is_base64_audio(spec) {
+        return None;
+    }
+    let bytes = decode_base64_audio(spec).ok()?;
+    pocket_tts::analyze_voice_prompt_bytes(&bytes).ok()
+}
+
+/// WAV bytes of a data URL or raw base64 string
+fn decode_base64_audio(spec: &str) -> Result<Vec<u8>> {
     // Strip data URL prefix if present
     let b64_str = if spec.starts_with("data:") {
         spec.split(',').nth(1).unwrap_or(spec)
@@ -198,26 +421,17 @@ fn resolve_base64_voice(model: &TTSModel, spec: &str) -> Result<pocket_tts::Mode
     };
 
     use base64::{Engine as _, engine::general_purpose};
-    let bytes = general_purpose::STANDARD
+    general_purpose::STANDARD
         .decode(b64_str)
-        .context("Failed to decode base64 audio")?;
-
-    // Decode WAV from bytes
-    let (audio, sample_rate) = pocket_tts::audio::read_wav_from_bytes(&bytes)
-        .context("Failed to parse WAV from base64 data")?;
-
-    // Resample if needed
-    let audio = if sample_rate != model.sample_rate as u32 {
-        pocket_tts::audio::resample(&audio, sample_rate, model.sample_rate as u32)?
-    } else {
-        audio
-    };
+        .context("Failed to decode base64 audio")
+}
 
-    // Add batch dimension: [C, T] -> [B, C, T]
-    let audio = audio.unsqueeze(0)?;
+/// Resolve base64-encoded audio data
+fn resolve_base64_voice(model: &TTSModel, spec: &str) -> Result<pocket_tts::ModelState> {
+    let bytes = decode_base64_audio(spec)?;
 
     model
-        .get_voice_state_from_tensor(&audio)
+        .get_voice_state_from_bytes(&bytes)
         .context("Failed to encode base64 audio for voice cloning")
 }
 
@@ -242,15 +456,91 @@ mod tests {
         assert!(!is_base64_audio("short"));
     }
 
+    #[test]
+    fn test_voice_preprocess_args() {
+        assert_eq!(VoicePreprocessArgs::default().preprocessing(), None);
+        let args = VoicePreprocessArgs {
+            preprocess_voice: true,
+            ..Default::default()
+        };
+        assert_eq!(args.preprocessing(), Some(VoicePreprocessing::default()));
+
+        let args = VoicePreprocessArgs {
+            preprocess_voice: true,
+            voice_high_pass_hz: Some(0.0),
+            voice_max_silence_ms: Some(500),
+            voice_loudness_db: Some(-16.0),
+            voice_max_duration_secs: Some(0.0),
+            voice_keep_edges: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            args.preprocessing(),
+            Some(VoicePreprocessing {
+                high_pass_hz: None,
+                max_silence_ms: Some(500),
+                target_loudness_db: Some(-16.0),
+                max_duration_secs: None,
+                trim_silence: false,
+                ..VoicePreprocessing::default()
+            })
+        );
+    }
+
     #[test]
     fn test_voice_cache_key_stock() {
-        assert_eq!(voice_cache_key("alba"), "stock:alba");
+        assert_eq!(voice_cache_key("alba", None), "stock:alba");
+        let cleanup = VoicePreprocessing::default();
+        assert_eq!(voice_cache_key("alba", Some(&cleanup)), "stock:alba");
+    }
+
+    #[test]
+    fn test_voice_cache_key_preprocessing() {
+        let cleanup = VoicePreprocessing::default();
+        let louder = VoicePreprocessing {
+            target_loudness_db: Some(-16.0),
+            ..VoicePreprocessing::default()
+        };
+        for spec in [
+            "data:audio/wav;base64,AAAA",
+            "hf://owner/repo/voice.wav",
+            "not-a-voice",
+        ] {
+            let plain = voice_cache_key(spec, None);
+            let cleaned = voice_cache_key(spec, Some(&cleanup));
+            assert_ne!(plain, cleaned, "{spec}");
+            assert_ne!(cleaned, voice_cache_key(spec, Some(&louder)), "{spec}");
+            assert_eq!(cleaned, voice_cache_key(spec, Some(&cleanup)), "{spec}");
+        }
+
+        let dir =
+            std::env::temp_dir().join(format!("pocket_tts_voice_cache_key_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let wav = dir.join("voice.wav");
+        let embeddings = dir.join("voice.safetensors");
+        std::fs::write(&wav, b"RIFF").unwrap();
+        std::fs::write(&embeddings, b"{}").unwrap();
+        let wav = wav.to_str().unwrap();
+        assert_ne!(
+            voice_cache_key(wav, None),
+            voice_cache_key(wav, Some(&cleanup))
+        );
+        let embeddings = embeddings.to_str().unwrap();
+        assert_eq!(
+            voice_cache_key(embeddings, None),
+            voice_cache_key(embeddings, Some(&cleanup))
+        );
+        assert_eq!(
+            voice_cache_key("hf://owner/repo/voice.safetensors", Some(&cleanup)),
+            "hf:hf://owner/repo/voice.safetensors"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
     }
 
     #[test]
     fn test_voice_cache_key_base64() {
-        let k1 = voice_cache_key("data:audio/wav;base64,AAAA");
-        let k2 = voice_cache_key("data:audio/wav;base64,AAAB");
+        let k1 = voice_cache_key("data:audio/wav;base64,AAAA", None);
+        let k2 = voice_cache_key("data:audio/wav;base64,AAAB", None);
         assert!(k1.starts_with("b64:"));
         assert!(k2.starts_with("b64:"));
         assert_ne!(k1, k2);
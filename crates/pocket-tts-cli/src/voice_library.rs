@@ -0,0 +1,308 @@
+//! Persistent library of named voices
+//!
+//! `pocket-tts voice add ceo --voice ceo.wav` encodes a reference clip once
+//! and stores the voice prompt under a name, so `--voice ceo` works in
+//! `generate` and `serve` without passing paths around. The library is a
+//! directory of `.safetensors` voice prompts next to an `index.json` that
+//! records each voice's description, source, prompt format and model variant.
+//!
+//! The directory is `$POCKET_TTS_VOICE_DIR` when set, otherwise
+//! `pocket-tts/voices` in the platform data directory (`$XDG_DATA_HOME`,
+//! `~/.local/share` or `%APPDATA%`).
+
+use anyhow::{Context, Result};
+use pocket_tts::VoicePrompt;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::voice::PREDEFINED_VOICES;
+
+/// Environment variable overriding the library directory
+pub const VOICE_DIR_ENV: &str = "POCKET_TTS_VOICE_DIR";
+
+/// Name of the index file inside the library directory
+const INDEX_FILE: &str = "index.json";
+
+/// Longest accepted voice name
+const MAX_NAME_LEN: usize = 64;
+
+/// One named voice in the library index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Where the voice came from (file path, `hf://` URL or stock name)
+    pub source: String,
+    /// Voice prompt format version, see [`pocket_tts::VoicePromptFormat`]
+    pub format_version: u32,
+    /// Model variant the prompt was encoded with, if known
+    #[serde(default)]
+    pub model_variant: Option<String>,
+    /// Voice prompt file, relative to the library directory
+    pub file: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    voices: Vec<VoiceEntry>,
+}
+
+/// Directory of named voice prompts
+#[derive(Debug, Clone)]
+pub struct VoiceLibrary {
+    dir: PathBuf,
+}
+
+impl VoiceLibrary {
+    /// Library at `dir`; nothing is created until a voice is added
+    pub fn open<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Library at [`Self::default_dir`]
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(Self::default_dir()?))
+    }
+
+    /// `$POCKET_TTS_VOICE_DIR`, or `pocket-tts/voices` in the user data
+    /// directory
+    pub fn default_dir() -> Result<PathBuf> {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        if let Some(dir) = var(VOICE_DIR_ENV) {
+            return Ok(PathBuf::from(dir));
+        }
+        let data_dir = var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("APPDATA").map(PathBuf::from))
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+            .with_context(|| {
+                format!(
+                    "Cannot locate the voice library: set {} or HOME",
+                    VOICE_DIR_ENV
+                )
+            })?;
+        Ok(data_dir.join("pocket-tts").join("voices"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// All voices, sorted by name
+    pub fn list(&self) -> Result<Vec<VoiceEntry>> {
+        let mut voices = self.read_index()?.voices;
+        voices.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(voices)
+    }
+
+    /// The voice called `name`, if any
+    pub fn get(&self, name: &str) -> Result<Option<VoiceEntry>> {
+        Ok(self
+            .read_index()?
+            .voices
+            .into_iter()
+            .find(|voice| voice.name == name))
+    }
+
+    /// Path of the voice prompt file of `entry`
+    pub fn prompt_path(&self, entry: &VoiceEntry) -> PathBuf {
+        self.dir.join(&entry.file)
+    }
+
+    /// Store `prompt` as `name`, replacing an existing voice only if
+    /// `overwrite` is set
+    pub fn add(
+        &self,
+        name: &str,
+        description: &str,
+        source: &str,
+        prompt: &VoicePrompt,
+        overwrite: bool,
+    ) -> Result<VoiceEntry> {
+        validate_name(name)?;
+        let mut index = self.read_index()?;
+        if let Some(position) = index.voices.iter().position(|v| v.name == name) {
+            if !overwrite {
+                anyhow::bail!(
+                    "Voice '{}' already exists; remove it first or pass --force",
+                    name
+                );
+            }
+            index.voices.remove(position);
+        }
+
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create voice library {:?}", self.dir))?;
+        let entry = VoiceEntry {
+            name: name.to_string(),
+            description: description.to_string(),
+            source: source.to_string(),
+            format_version: prompt.format.version(),
+            model_variant: prompt.metadata.model_variant.clone(),
+            file: format!("{}.safetensors", name),
+        };
+        prompt.save(self.prompt_path(&entry))?;
+        index.voices.push(entry.clone());
+        self.write_index(&index)?;
+        Ok(entry)
+    }
+
+    /// Delete the voice called `name` and its prompt file
+    pub fn remove(&self, name: &str) -> Result<VoiceEntry> {
+        let mut index = self.read_index()?;
+        let position = self.position(&index, name)?;
+        let entry = index.voices.remove(position);
+        self.write_index(&index)?;
+        let path = self.prompt_path(&entry);
+        if let Err(e) = std::fs::remove_file(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            return Err(e).with_context(|| format!("Failed to remove {:?}", path));
+        }
+        Ok(entry)
+    }
+
+    /// Rename the voice `old` to `new`, keeping its prompt
+    pub fn rename(&self, old: &str, new: &str) -> Result<VoiceEntry> {
+        validate_name(new)?;
+        let mut index = self.read_index()?;
+        let position = self.position(&index, old)?;
+        if old != new && index.voices.iter().any(|v| v.name == new) {
+            anyhow::bail!("Voice '{}' already exists", new);
+        }
+
+        let entry = &mut index.voices[position];
+        let old_path = self.dir.join(&entry.file);
+        entry.name = new.to_string();
+        entry.file = format!("{}.safetensors", new);
+        let new_path = self.dir.join(&entry.file);
+        std::fs::rename(&old_path, &new_path)
+            .with_context(|| format!("Failed to rename {:?} to {:?}", old_path, new_path))?;
+        let entry = entry.clone();
+        self.write_index(&index)?;
+        Ok(entry)
+    }
+
+    fn position(&self, index: &Index, name: &str) -> Result<usize> {
+        index
+            .voices
+            .iter()
+            .position(|v| v.name == name)
+            .with_context(|| format!("Voice '{}' is not in the library {:?}", name, self.dir))
+    }
+
+    fn read_index(&self) -> Result<Index> {
+        let path = self.dir.join(INDEX_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("Failed to parse voice library index {:?}", path)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Index::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        }
+    }
+
+    fn write_index(&self, index: &Index) -> Result<()> {
+        let path = self.dir.join(INDEX_FILE);
+        // Write then rename, so a crash never leaves a truncated index
+        let tmp = self
+            .dir
+            .join(format!("{}.{}.tmp", INDEX_FILE, std::process::id()));
+        std::fs::write(&tmp, serde_json::to_string_pretty(index)?)
+            .with_context(|| format!("Failed to write {:?}", tmp))?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {:?}", path))?;
+        Ok(())
+    }
+}
+
+/// Library names are short identifiers that cannot be mistaken for paths,
+/// URLs or stock voices
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if name.is_empty() || name.len() > MAX_NAME_LEN || !valid_chars {
+        anyhow::bail!(
+            "Invalid voice name '{}': use up to {} letters, digits, '-' or '_'",
+            name,
+            MAX_NAME_LEN
+        );
+    }
+    if PREDEFINED_VOICES.contains(&name) {
+        anyhow::bail!("'{}' is a predefined voice and cannot be redefined", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::{DType, Device, Tensor};
+    use pocket_tts::VoicePromptMetadata;
+
+    fn temp_library(name: &str) -> VoiceLibrary {
+        let dir = std::env::temp_dir().join(format!(
+            "pocket_tts_voice_library_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        VoiceLibrary::open(dir)
+    }
+
+    fn prompt() -> VoicePrompt {
+        let conditioning = Tensor::zeros((1, 3, 4), DType::F32, &Device::Cpu).unwrap();
+        VoicePrompt::from_conditioning(
+            conditioning,
+            VoicePromptMetadata::new(Some("b6369a24".to_string())),
+        )
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("ceo").is_ok());
+        assert!(validate_name("support-agent_2").is_ok());
+        for bad in ["", "alba", "../x", "a b", "voice.wav", "hf://x"] {
+            assert!(validate_name(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_add_rename_remove() {
+        let library = temp_library("crud");
+        assert!(library.list().unwrap().is_empty());
+
+        let entry = library
+            .add("ceo", "Quarterly updates", "ceo.wav", &prompt(), false)
+            .unwrap();
+        assert_eq!(entry.format_version, 1);
+        assert_eq!(entry.model_variant.as_deref(), Some("b6369a24"));
+        assert!(library.prompt_path(&entry).exists());
+        assert!(library.add("ceo", "", "x.wav", &prompt(), false).is_err());
+        library.add("ceo", "", "x.wav", &prompt(), true).unwrap();
+        library
+            .add("intern", "", "y.wav", &prompt(), false)
+            .unwrap();
+
+        assert!(library.rename("ceo", "intern").is_err());
+        let renamed = library.rename("ceo", "chief").unwrap();
+        assert!(library.prompt_path(&renamed).exists());
+        assert!(VoicePrompt::load(library.prompt_path(&renamed)).is_ok());
+        let names: Vec<_> = library
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|v| v.name)
+            .collect();
+        assert_eq!(names, ["chief", "intern"]);
+
+        let removed = library.remove("chief").unwrap();
+        assert!(!library.prompt_path(&removed).exists());
+        assert!(library.get("chief").unwrap().is_none());
+        assert!(library.remove("chief").is_err());
+
+        let _ = std::fs::remove_dir_all(library.dir());
+    }
+}
@@ -80,3 +80,109 @@ async fn test_api_base64_cloning() {
     let reader = hound::WavReader::new(cursor).unwrap();
     assert!(reader.duration() > 0);
 }
+
+/// WAV file bytes of a 220 Hz tone
+fn tone_wav(secs: f32) -> Vec<u8> {
+    let mut wav_bytes = Vec::new();
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 24000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::new(std::io::Cursor::new(&mut wav_bytes), spec).unwrap();
+    for i in 0..(secs * 24000.0) as usize {
+        let t = i as f32 / 24000.0;
+        let sample = 0.3 * (2.0 * std::f32::consts::PI * 220.0 * t).sin();
+        writer
+            .write_sample((sample * i16::MAX as f32) as i16)
+            .unwrap();
+    }
+    writer.finalize().unwrap();
+    wav_bytes
+}
+
+#[tokio::test]
+async fn test_api_warns_about_short_uploaded_voice() {
+    let Some(app) = create_test_app() else { return };
+
+    // One second of a tone is too little speech to clone from
+    let wav_bytes = tone_wav(1.0);
+
+    let body = json!({
+        "text": "Short voice test",
+        "voice": format!("data:audio/wav;base64,{}", general_purpose::STANDARD.encode(&wav_bytes))
+    });
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/generate")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let warnings = response
+        .headers()
+        .get("x-voice-warnings")
+        .expect("missing voice warnings")
+        .to_str()
+        .unwrap();
+    assert!(warnings.contains("fail: only"), "{warnings}");
+}
+
+#[tokio::test]
+async fn test_api_tts_form_joins_multiple_voice_clips() {
+    let Some(app) = create_test_app() else { return };
+
+    // Two 2 s clips are joined into more than 4 s of reference audio, so the
+    // speech check warns instead of failing
+    let boundary = "pocket-tts-test-boundary";
+    let mut body = Vec::new();
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"text\"\r\n\r\nTwo clips.\r\n"
+        )
+        .as_bytes(),
+    );
+    for index in 0..2 {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"voice_wav\"; \
+                 filename=\"clip{index}.wav\"\r\nContent-Type: audio/wav\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&tone_wav(2.0));
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/tts")
+                .header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let warnings = response
+        .headers()
+        .get("x-voice-warnings")
+        .expect("missing voice warnings")
+        .to_str()
+        .unwrap();
+    assert!(warnings.contains("warn: only 4."), "{warnings}");
+}
@@ -8,6 +8,235 @@ fn test_cli_help() {
     cmd.arg("--help").assert().success();
 }
 
+#[test]
+fn test_cli_codec_help() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    cmd.args(["codec", "encode", "--help"]).assert().success();
+}
+
+#[test]
+fn test_cli_generate_rejects_malformed_ssml() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    cmd.args(["generate", "--ssml", "--text", "<speak>Hello</p>"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_generate_rejects_missing_input_file() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    cmd.args(["generate", "--input-file", "does-not-exist.md"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_cli_voice_cleanup_flags_need_preprocess_voice() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    let output = cmd
+        .args(["generate", "--voice-high-pass-hz", "120"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).to_string();
+    assert!(stderr.contains("--preprocess-voice"), "{stderr}");
+}
+
+#[test]
+fn test_cli_cache_stats_and_prune() {
+    let dir = std::env::temp_dir().join(format!("pocket_tts_cli_cache_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("0123abcd.wav"), [0u8; 64]).unwrap();
+    let dir_arg = dir.to_str().unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    let output = cmd
+        .args(["cache", "stats", "--dir", dir_arg])
+        .assert()
+        .success();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).to_string();
+    assert!(stdout.contains("Entries:   1"), "{stdout}");
+
+    // Nothing selected to prune
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    cmd.args(["cache", "prune", "--dir", dir_arg])
+        .assert()
+        .failure();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    cmd.args(["cache", "prune", "--dir", dir_arg, "--all"])
+        .assert()
+        .success();
+    assert!(!dir.join("0123abcd.wav").exists());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cli_voice_blend_rejects_bad_inputs() {
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    let output = cmd
+        .args([
+            "voice",
+            "blend",
+            "missing.wav:0.7",
+            "-o",
+            "brand.safetensors",
+        ])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).to_string();
+    assert!(stderr.contains("not found"), "{stderr}");
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    let output = cmd
+        .args(["voice", "blend", "a.wav:-0.5", "-o", "brand.safetensors"])
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).to_string();
+    assert!(stderr.contains("non-negative"), "{stderr}");
+}
+
+#[test]
+fn test_cli_voice_check_reports_short_clip() {
+    let clip = std::env::temp_dir().join(format!("pocket_tts_check_{}.wav", std::process::id()));
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 24000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(&clip, spec).unwrap();
+    for i in 0..24000 {
+        let t = i as f32 / 24000.0;
+        let sample = 0.3 * (2.0 * std::f32::consts::PI * 220.0 * t).sin();
+        writer
+            .write_sample((sample * i16::MAX as f32) as i16)
+            .unwrap();
+    }
+    writer.finalize().unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    let output = cmd
+        .args(["voice", "check", clip.to_str().unwrap(), "--json"])
+        .assert()
+        .failure();
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout).to_string();
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(report["sample_rate"], 24000);
+    assert_eq!(report["channels"], 1);
+    assert!((report["duration_secs"].as_f64().unwrap() - 1.0).abs() < 0.01);
+    let speech = report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == "speech")
+        .unwrap();
+    assert_eq!(speech["status"], "fail");
+
+    let _ = std::fs::remove_file(&clip);
+}
+
+#[test]
+fn test_cli_voice_add_rejects_non_audio_clips() {
+    let dir = std::env::temp_dir().join(format!("pocket_tts_cli_clips_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let prompt = dir.join("prompt.safetensors");
+    let clip = dir.join("clip.wav");
+    std::fs::write(&prompt, b"").unwrap();
+    std::fs::write(&clip, b"").unwrap();
+
+    #[allow(deprecated)]
+    let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+    let output = cmd
+        .env("POCKET_TTS_VOICE_DIR", dir.join("library"))
+        .args(["voice", "add", "team", "--voice"])
+        .arg(&clip)
+        .arg("--voice")
+        .arg(&prompt)
+        .assert()
+        .failure();
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr).to_string();
+    assert!(
+        stderr.contains("only audio clips can be combined"),
+        "{stderr}"
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cli_voice_library_lifecycle() {
+    let dir = std::env::temp_dir().join(format!("pocket_tts_cli_voices_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let library = dir.join("library");
+    let prompt_path = dir.join("ceo_prompt.safetensors");
+    let conditioning = candle_core::Tensor::zeros(
+        (1, 3, 4),
+        candle_core::DType::F32,
+        &candle_core::Device::Cpu,
+    )
+    .unwrap();
+    pocket_tts::VoicePrompt::from_conditioning(conditioning, Default::default())
+        .save(&prompt_path)
+        .unwrap();
+
+    let voice = |args: &[&str]| {
+        #[allow(deprecated)]
+        let mut cmd = Command::cargo_bin("pocket-tts-cli").unwrap();
+        cmd.env("POCKET_TTS_VOICE_DIR", &library)
+            .arg("voice")
+            .args(args)
+            .assert()
+    };
+    let stdout = |assert: assert_cmd::assert::Assert| {
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    voice(&[
+        "add",
+        "ceo",
+        "--voice",
+        prompt_path.to_str().unwrap(),
+        "--description",
+        "Quarterly updates",
+    ])
+    .success();
+    voice(&["add", "ceo", "--voice", prompt_path.to_str().unwrap()]).failure();
+    voice(&["add", "alba", "--voice", prompt_path.to_str().unwrap()]).failure();
+
+    let listed = stdout(voice(&["list"]).success());
+    assert!(
+        listed.contains("ceo") && listed.contains("Quarterly updates"),
+        "{listed}"
+    );
+
+    voice(&["rename", "ceo", "chief"]).success();
+    let shown = stdout(voice(&["show", "chief"]).success());
+    assert!(shown.contains("audio_prompt (v1)"), "{shown}");
+
+    let exported = dir.join("exported.safetensors");
+    voice(&["export", "chief", "-o", exported.to_str().unwrap()]).success();
+    assert!(exported.exists());
+
+    voice(&["remove", "chief"]).success();
+    voice(&["show", "chief"]).failure();
+    assert!(!stdout(voice(&["list"]).success()).contains("chief"));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
 #[test]
 fn test_cli_generate_basic() {
     let output_file = "test_cli_gen.wav";
@@ -109,6 +109,173 @@ async fn test_api_full_flow() {
     assert_eq!(response.headers().get("content-type").unwrap(), "audio/wav");
 }
 
+#[tokio::test]
+async fn test_api_rejects_out_of_range_speed() {
+    let Some(app) = create_test_app() else { return };
+
+    let body = json!({
+        "model": "pocket-tts",
+        "input": "Too fast",
+        "speed": 10.0
+    });
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/audio/speech")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_api_analyze_and_reject_unsupported_chars() {
+    let Some(app) = create_test_app() else { return };
+
+    let send = |uri: &str, body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(send("/analyze", json!({"text": "Party time 🎉!"})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let analysis: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(analysis["policy"], "transliterate");
+    assert_eq!(analysis["unsupported"][0]["ch"], "🎉");
+    assert!(!analysis["chunks"].as_array().unwrap().is_empty());
+    assert!(!analysis["warnings"].as_array().unwrap().is_empty());
+
+    let response = app
+        .oneshot(send(
+            "/generate",
+            json!({"text": "Party time 🎉!", "unsupported_chars": "error"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_api_generate_serves_repeats_from_synthesis_cache() {
+    use pocket_tts_cli::synthesis_cache::SynthesisCache;
+
+    let Some(state) = create_test_state() else {
+        return;
+    };
+    let dir = std::env::temp_dir().join(format!("pocket_tts_server_cache_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = SynthesisCache::open(&dir, 64 * 1024 * 1024).unwrap();
+    let app = routes::create_router(state.with_synthesis_cache(cache, "b6369a24", "stock:alba"));
+
+    let mut bodies = Vec::new();
+    for expected in ["pocket-tts; fwd=uri-miss; stored", "pocket-tts; hit"] {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/generate")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(
+                        json!({"text": "Press one.", "seed": 1}).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("cache-status").unwrap(), expected);
+        bodies.push(
+            axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap(),
+        );
+    }
+    assert_eq!(bodies[0], bodies[1]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_api_lexicon_add_list_remove() {
+    let Some(app) = create_test_app() else { return };
+
+    let send = |method: &str, body: serde_json::Value| {
+        Request::builder()
+            .method(method)
+            .uri("/lexicon")
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(send(
+            "POST",
+            json!({"word": "nginx", "respelling": "engine x"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(send("POST", json!({"pattern": "(", "respelling": "x"})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/lexicon")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let listed: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        listed,
+        json!({"words": {"nginx": "engine x"}, "patterns": []})
+    );
+
+    let response = app
+        .clone()
+        .oneshot(send("DELETE", json!({"word": "nginx"})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(send("DELETE", json!({"word": "nginx"})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
 #[cfg(feature = "web-ui")]
 #[tokio::test]
 async fn test_web_interface() {
@@ -80,6 +80,7 @@ interface WasmChunkStats {
 interface WasmStreamLike {
   next_chunk_min_samples(minSamples: number): Float32Array | null | undefined;
   last_chunk_stats(): WasmChunkStats;
+  cancel(): void;
 }
 
 interface WasmModelLike {
@@ -346,6 +347,7 @@ const handleStartStream = async (
   }
 
   if (stopRequested || streamToken !== activeStreamToken) {
+    stream.cancel();
     throw new Error("abort");
   }
 
@@ -1,6 +1,11 @@
+#![allow(clippy::missing_safety_doc)]
+
 use candle_core::Device;
 use num_enum::FromPrimitive;
-use pocket_tts::{ModelState, TTSModel};
+use pocket_tts::{
+    GenerationHandle, GenerationOptions, ModelState, TTSModel, VoicePreprocessing,
+    VoicePromptFormat,
+};
 use std::collections::HashMap;
 use std::ffi::CStr;
 use std::os::raw::c_char;
@@ -21,6 +26,65 @@ pub struct AudioBuffer {
     length: usize,
 }
 
+/// Reference audio cleanup settings (see `pocket_tts::VoicePreprocessing`)
+///
+/// Start from `pocket_tts_voice_preprocessing_default`. A zero or negative
+/// `high_pass_hz` or `max_duration_secs`, a zero `max_silence_ms` and a NaN
+/// `target_loudness_db` turn that step off.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct VoicePreprocessingSettings {
+    remove_dc: bool,
+    high_pass_hz: f32,
+    trim_silence: bool,
+    max_silence_ms: u32,
+    silence_threshold_db: f32,
+    target_loudness_db: f32,
+    max_duration_secs: f32,
+}
+
+impl From<VoicePreprocessing> for VoicePreprocessingSettings {
+    fn from(p: VoicePreprocessing) -> Self {
+        Self {
+            remove_dc: p.remove_dc,
+            high_pass_hz: p.high_pass_hz.unwrap_or(0.0),
+            trim_silence: p.trim_silence,
+            max_silence_ms: p.max_silence_ms.unwrap_or(0),
+            silence_threshold_db: p.silence_threshold_db,
+            target_loudness_db: p.target_loudness_db.unwrap_or(f32::NAN),
+            max_duration_secs: p.max_duration_secs.unwrap_or(0.0),
+        }
+    }
+}
+
+impl From<VoicePreprocessingSettings> for VoicePreprocessing {
+    fn from(s: VoicePreprocessingSettings) -> Self {
+        let positive = |value: f32| (value > 0.0).then_some(value);
+        Self {
+            remove_dc: s.remove_dc,
+            high_pass_hz: positive(s.high_pass_hz),
+            trim_silence: s.trim_silence,
+            max_silence_ms: (s.max_silence_ms > 0).then_some(s.max_silence_ms),
+            silence_threshold_db: s.silence_threshold_db,
+            target_loudness_db: (!s.target_loudness_db.is_nan()).then_some(s.target_loudness_db),
+            max_duration_secs: positive(s.max_duration_secs),
+        }
+    }
+}
+
+/// A copy of `model` (sharing its weights) that cleans up reference audio
+/// with `settings`, or no cleanup when `settings` is null
+///
+/// The copy keeps the settings local to one call, so concurrent calls on the
+/// same model handle never write to it.
+unsafe fn with_preprocessing(
+    model: &TTSModel,
+    settings: *const VoicePreprocessingSettings,
+) -> TTSModel {
+    let preprocessing = unsafe { settings.as_ref() }.map(|s| VoicePreprocessing::from(*s));
+    model.clone().with_voice_preprocessing(preprocessing)
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
 pub enum StreamControlCode {
@@ -100,7 +164,7 @@ pub unsafe extern "C" fn pocket_tts_load_from_files(
         Ok(model) => Box::into_raw(Box::new(model)),
         Err(err) => {
             eprintln!("Failed to load model: {:?}", err);
-            return ptr::null_mut();
+            ptr::null_mut()
         }
     }
 }
@@ -114,6 +178,123 @@ pub unsafe extern "C" fn pocket_tts_generate_stream(
     on_finished: StreamFinishedCallback,
     on_error: StreamErrorCallback,
     user_data: *mut std::ffi::c_void,
+) {
+    unsafe {
+        generate_stream(
+            model,
+            text,
+            voice,
+            |options| options,
+            on_chunk,
+            on_finished,
+            on_error,
+            user_data,
+        )
+    }
+}
+
+/// Stream speech from text using a voice, with a fixed sampling seed
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn pocket_tts_generate_stream_with_seed(
+    model: *mut TTSModel,
+    text: *const c_char,
+    voice: *mut ModelState,
+    seed: u64,
+    on_chunk: StreamChunkCallback,
+    on_finished: StreamFinishedCallback,
+    on_error: StreamErrorCallback,
+    user_data: *mut std::ffi::c_void,
+) {
+    unsafe {
+        generate_stream(
+            model,
+            text,
+            voice,
+            |options| options.with_seed(Some(seed)),
+            on_chunk,
+            on_finished,
+            on_error,
+            user_data,
+        )
+    }
+}
+
+/// Stream speech from text using a voice, stopping once `handle` is cancelled
+///
+/// Cancellation is checked before every frame; the stream then ends with
+/// `on_finished`. The handle stays owned by the caller.
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn pocket_tts_generate_stream_cancellable(
+    model: *mut TTSModel,
+    text: *const c_char,
+    voice: *mut ModelState,
+    handle: *const GenerationHandle,
+    on_chunk: StreamChunkCallback,
+    on_finished: StreamFinishedCallback,
+    on_error: StreamErrorCallback,
+    user_data: *mut std::ffi::c_void,
+) {
+    let handle = if handle.is_null() {
+        None
+    } else {
+        Some(unsafe { (*handle).clone() })
+    };
+    unsafe {
+        generate_stream(
+            model,
+            text,
+            voice,
+            |options| match handle {
+                Some(handle) => options.with_handle(handle),
+                None => options,
+            },
+            on_chunk,
+            on_finished,
+            on_error,
+            user_data,
+        )
+    }
+}
+
+/// Stream speech from text using a voice at the given speaking rate (1.0 = natural)
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn pocket_tts_generate_stream_with_speed(
+    model: *mut TTSModel,
+    text: *const c_char,
+    voice: *mut ModelState,
+    speed: f32,
+    on_chunk: StreamChunkCallback,
+    on_finished: StreamFinishedCallback,
+    on_error: StreamErrorCallback,
+    user_data: *mut std::ffi::c_void,
+) {
+    unsafe {
+        generate_stream(
+            model,
+            text,
+            voice,
+            |options| GenerationOptions { speed, ..options },
+            on_chunk,
+            on_finished,
+            on_error,
+            user_data,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn generate_stream(
+    model: *mut TTSModel,
+    text: *const c_char,
+    voice: *mut ModelState,
+    configure: impl FnOnce(GenerationOptions) -> GenerationOptions,
+    on_chunk: StreamChunkCallback,
+    on_finished: StreamFinishedCallback,
+    on_error: StreamErrorCallback,
+    user_data: *mut std::ffi::c_void,
 ) {
     if model.is_null() || text.is_null() || voice.is_null() {
         unsafe {
@@ -136,7 +317,9 @@ pub unsafe extern "C" fn pocket_tts_generate_stream(
         }
     };
 
-    for chunk_result in model.generate_stream(text_str, voice) {
+    let options = configure(model.generation_options());
+
+    for chunk_result in model.generate_stream_with_options(text_str, voice, &options) {
         let audio_tensor = match chunk_result {
             Ok(tensor) => tensor,
             Err(err) => {
@@ -159,7 +342,7 @@ pub unsafe extern "C" fn pocket_tts_generate_stream(
             }
         };
 
-        let stream_control_code = StreamControlCode::from(unsafe {
+        let stream_control_code = unsafe {
             let audio_data_len = audio_data.len();
             let mut boxed_slice = audio_data.into_boxed_slice();
             let data_ptr = boxed_slice.as_mut_ptr();
@@ -169,7 +352,7 @@ pub unsafe extern "C" fn pocket_tts_generate_stream(
                 length: audio_data_len,
             }));
             on_chunk(buffer, user_data)
-        });
+        };
 
         match stream_control_code {
             StreamControlCode::Proceed => {}
@@ -199,6 +382,42 @@ pub unsafe extern "C" fn pocket_tts_generate(
     model: *mut TTSModel,
     text: *const c_char,
     voice: *mut ModelState,
+) -> *mut AudioBuffer {
+    unsafe { generate(model, text, voice, |options| options) }
+}
+
+/// Generate speech from text using a voice, with a fixed sampling seed
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pocket_tts_generate_with_seed(
+    model: *mut TTSModel,
+    text: *const c_char,
+    voice: *mut ModelState,
+    seed: u64,
+) -> *mut AudioBuffer {
+    unsafe { generate(model, text, voice, |options| options.with_seed(Some(seed))) }
+}
+
+/// Generate speech from text using a voice at the given speaking rate (1.0 = natural)
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pocket_tts_generate_with_speed(
+    model: *mut TTSModel,
+    text: *const c_char,
+    voice: *mut ModelState,
+    speed: f32,
+) -> *mut AudioBuffer {
+    unsafe {
+        generate(model, text, voice, |options| GenerationOptions {
+            speed,
+            ..options
+        })
+    }
+}
+
+unsafe fn generate(
+    model: *mut TTSModel,
+    text: *const c_char,
+    voice: *mut ModelState,
+    configure: impl FnOnce(GenerationOptions) -> GenerationOptions,
 ) -> *mut AudioBuffer {
     if model.is_null() || text.is_null() || voice.is_null() {
         return ptr::null_mut();
@@ -217,7 +436,8 @@ pub unsafe extern "C" fn pocket_tts_generate(
         }
     };
 
-    let audio_tensor = match model.generate(text_str, voice) {
+    let options = configure(model.generation_options());
+    let audio_tensor = match model.generate_with_options(text_str, voice, &options) {
         Ok(audio) => audio,
         Err(err) => {
             eprintln!("Failed to generate audio tensor {:?}", err);
@@ -238,7 +458,7 @@ pub unsafe extern "C" fn pocket_tts_generate(
         }
         Err(err) => {
             eprintln!("Error converting audio tensor to vector: {:?}", err);
-            return ptr::null_mut();
+            ptr::null_mut()
         }
     }
 }
@@ -266,12 +486,14 @@ pub unsafe extern "C" fn pocket_tts_get_voice_state_from_wav(
         Ok(state) => Box::into_raw(Box::new(state)),
         Err(err) => {
             eprintln!("Error getting voice state: {:?}", err);
-            return ptr::null_mut();
+            ptr::null_mut()
         }
     }
 }
 
-/// Create a voice state from an safetensors file path
+/// Create a voice state from a voice prompt safetensors file path
+///
+/// The prompt format (v1, v2 or v3) is detected from the file.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pocket_tts_get_voice_state_from_safetensors(
     model: *mut TTSModel,
@@ -294,12 +516,15 @@ pub unsafe extern "C" fn pocket_tts_get_voice_state_from_safetensors(
         Ok(state) => Box::into_raw(Box::new(state)),
         Err(err) => {
             eprintln!("Failed to get voice from safetensors: {:?}", err);
-            return ptr::null_mut();
+            ptr::null_mut()
         }
     }
 }
 
 /// Create a voice state from an safetensors v2 file path
+///
+/// Same as `pocket_tts_get_voice_state_from_safetensors`, kept for existing
+/// callers.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pocket_tts_get_voice_state_from_safetensors_v2(
     model: *mut TTSModel,
@@ -318,16 +543,19 @@ pub unsafe extern "C" fn pocket_tts_get_voice_state_from_safetensors_v2(
         }
     };
 
-    match model.get_voice_state_from_prompt_file_v2(path_str) {
+    match model.get_voice_state_from_prompt_file(path_str) {
         Ok(state) => Box::into_raw(Box::new(state)),
         Err(err) => {
             eprintln!("Failed to get voice from safetensors: {:?}", err);
-            return ptr::null_mut();
+            ptr::null_mut()
         }
     }
 }
 
 /// Create a voice state from an safetensors v3 file path
+///
+/// Same as `pocket_tts_get_voice_state_from_safetensors`, kept for existing
+/// callers.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pocket_tts_get_voice_state_from_safetensors_v3(
     model: *mut TTSModel,
@@ -346,11 +574,11 @@ pub unsafe extern "C" fn pocket_tts_get_voice_state_from_safetensors_v3(
         }
     };
 
-    match model.get_voice_state_from_prompt_file_v3(path_str) {
+    match model.get_voice_state_from_prompt_file(path_str) {
         Ok(state) => Box::into_raw(Box::new(state)),
         Err(err) => {
             eprintln!("Failed to get voice from safetensors: {:?}", err);
-            return ptr::null_mut();
+            ptr::null_mut()
         }
     }
 }
@@ -483,6 +711,119 @@ pub unsafe extern "C" fn pocket_tts_free_voice_state(state: *mut ModelState) {
     }
 }
 
+/// Create a generation handle used to cancel a running stream
+#[unsafe(no_mangle)]
+pub extern "C" fn pocket_tts_generation_handle_new() -> *mut GenerationHandle {
+    Box::into_raw(Box::new(GenerationHandle::new()))
+}
+
+/// Cancel every generation using this handle; safe to call from any thread
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pocket_tts_generation_handle_cancel(handle: *const GenerationHandle) {
+    if !handle.is_null() {
+        unsafe { &*handle }.cancel();
+    }
+}
+
+/// Free a generation handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pocket_tts_generation_handle_free(handle: *mut GenerationHandle) {
+    if !handle.is_null() {
+        unsafe {
+            let _ = Box::from_raw(handle);
+        }
+    }
+}
+
+/// Write the default reference audio cleanup settings to `settings`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pocket_tts_voice_preprocessing_default(
+    settings: *mut VoicePreprocessingSettings,
+) {
+    if !settings.is_null() {
+        unsafe { settings.write(VoicePreprocessing::default().into()) };
+    }
+}
+
+/// Create a voice state from an audio file path, cleaning up the audio with
+/// `settings` first (no cleanup when null)
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pocket_tts_get_voice_state_from_wav_preprocessed(
+    model: *mut TTSModel,
+    path: *const c_char,
+    settings: *const VoicePreprocessingSettings,
+) -> *mut ModelState {
+    if model.is_null() || path.is_null() {
+        return ptr::null_mut();
+    }
+
+    let model = unsafe { with_preprocessing(&*model, settings) };
+    let path_str = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(s) => s,
+        Err(err) => {
+            eprintln!("Bad path: {:?}", err);
+            return ptr::null_mut();
+        }
+    };
+
+    match model.get_voice_state(path_str) {
+        Ok(state) => Box::into_raw(Box::new(state)),
+        Err(err) => {
+            eprintln!("Error getting voice state: {:?}", err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Create a voice saftensors of format `version` (1, 2 or 3) from wav,
+/// cleaning up the audio with `settings` first (no cleanup when null)
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pocket_tts_create_safetensors_from_wav_preprocessed(
+    model: *mut TTSModel,
+    wav_path: *const c_char,
+    safetensors_path: *const c_char,
+    version: u32,
+    settings: *const VoicePreprocessingSettings,
+) {
+    if model.is_null() || wav_path.is_null() || safetensors_path.is_null() {
+        eprintln!("Null in parameters");
+        return;
+    }
+    let format = match version {
+        1 => VoicePromptFormat::AudioPrompt,
+        2 => VoicePromptFormat::KvCacheCurrentEnd,
+        3 => VoicePromptFormat::KvCacheOffset,
+        _ => {
+            eprintln!("Unknown voice prompt version: {}", version);
+            return;
+        }
+    };
+
+    let model = unsafe { with_preprocessing(&*model, settings) };
+    let wav_path_str = match unsafe { CStr::from_ptr(wav_path) }.to_str() {
+        Ok(s) => s,
+        Err(err) => {
+            eprintln!("Bad wav_path: {:?}", err);
+            return;
+        }
+    };
+    let safetensors_path_str = match unsafe { CStr::from_ptr(safetensors_path) }.to_str() {
+        Ok(s) => s,
+        Err(err) => {
+            eprintln!("Bad safetensors_path: {:?}", err);
+            return;
+        }
+    };
+
+    match model
+        .create_voice_prompt(wav_path_str, format)
+        .and_then(|prompt| prompt.save(safetensors_path_str))
+    {
+        Ok(_) => {}
+        Err(err) => eprintln!("Failed to save safetensors: {:?}", err),
+    }
+}
+
 /// Get the sample rate of the model
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pocket_tts_sample_rate(model: *const TTSModel) -> u32 {
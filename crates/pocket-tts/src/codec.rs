@@ -0,0 +1,446 @@
+//! Mimi neural audio codec container
+//!
+//! Stores Mimi encoder latents in a small self-describing `.mimi` file so
+//! arbitrary audio can be compressed with the shipped model and decoded later
+//! with [`crate::TTSModel::decode_audio`].
+//!
+//! Layout (little endian):
+//!
+//! | Field         | Type  | Notes                                   |
+//! |---------------|-------|-----------------------------------------|
+//! | magic         | 4 B   | `MIMI`                                  |
+//! | version       | u8    | currently 1                             |
+//! | precision     | u8    | 0 = f32, 1 = i16, 2 = i8                |
+//! | reserved      | u16   | 0                                       |
+//! | sample_rate   | u32   | Hz                                      |
+//! | frame_rate    | f32   | latent frames per second                |
+//! | latent_dim    | u32   | values per frame                        |
+//! | num_frames    | u32   |                                         |
+//! | num_samples   | u64   | original length, used to trim padding   |
+//!
+//! Frames follow in order. Integer precisions store a per-frame f32 scale
+//! before the frame's quantized values.
+
+use anyhow::{Result, bail};
+use candle_core::{Device, Tensor};
+use std::fmt;
+use std::str::FromStr;
+
+/// File magic of the `.mimi` container
+pub const MAGIC: &[u8; 4] = b"MIMI";
+/// Current container version
+pub const VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 32;
+
+/// How latent values are stored in the container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LatentPrecision {
+    /// Lossless 32-bit floats
+    F32,
+    /// 16-bit integers with a per-frame scale
+    I16,
+    /// 8-bit integers with a per-frame scale (smallest)
+    #[default]
+    I8,
+}
+
+impl LatentPrecision {
+    fn id(self) -> u8 {
+        match self {
+            Self::F32 => 0,
+            Self::I16 => 1,
+            Self::I8 => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Self::F32),
+            1 => Ok(Self::I16),
+            2 => Ok(Self::I8),
+            _ => bail!("Unknown latent precision id {}", id),
+        }
+    }
+
+    /// Bits per stored latent value
+    pub fn bits(self) -> usize {
+        match self {
+            Self::F32 => 32,
+            Self::I16 => 16,
+            Self::I8 => 8,
+        }
+    }
+
+    /// Largest quantized magnitude for integer precisions
+    fn max_level(self) -> f32 {
+        match self {
+            Self::F32 => 1.0,
+            Self::I16 => i16::MAX as f32,
+            Self::I8 => i8::MAX as f32,
+        }
+    }
+}
+
+impl fmt::Display for LatentPrecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::F32 => write!(f, "f32"),
+            Self::I16 => write!(f, "i16"),
+            Self::I8 => write!(f, "i8"),
+        }
+    }
+}
+
+impl FromStr for LatentPrecision {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "f32" => Ok(Self::F32),
+            "i16" => Ok(Self::I16),
+            "i8" => Ok(Self::I8),
+            _ => bail!("Unknown latent precision '{}' (expected f32, i16 or i8)", s),
+        }
+    }
+}
+
+/// Audio encoded as Mimi latents
+#[derive(Debug, Clone)]
+pub struct EncodedAudio {
+    /// Sample rate the latents decode to
+    pub sample_rate: u32,
+    /// Latent frames per second
+    pub frame_rate: f32,
+    /// Length of the original audio in samples
+    pub num_samples: u64,
+    /// Encoder latents `[frames, latent_dim]`
+    pub latents: Tensor,
+}
+
+impl EncodedAudio {
+    /// Number of latent frames
+    pub fn num_frames(&self) -> usize {
+        self.latents.dims()[0]
+    }
+
+    /// Values per latent frame
+    pub fn latent_dim(&self) -> usize {
+        self.latents.dims()[1]
+    }
+
+    /// Duration of the original audio in seconds
+    pub fn duration_secs(&self) -> f64 {
+        self.num_samples as f64 / self.sample_rate as f64
+    }
+
+    /// Payload bitrate in bits per second when stored with `precision`
+    pub fn bitrate(&self, precision: LatentPrecision) -> f64 {
+        let scale_bits = if precision == LatentPrecision::F32 {
+            0
+        } else {
+            32
+        };
+        (self.latent_dim() * precision.bits() + scale_bits) as f64 * self.frame_rate as f64
+    }
+
+    /// Check that a model decoding `sample_rate` audio from `latent_dim`
+    /// values per frame can decode these latents
+    pub fn check_decodable(&self, sample_rate: u32, latent_dim: usize) -> Result<()> {
+        if self.sample_rate != sample_rate {
+            bail!(
+                "Encoded audio is {}Hz but the model decodes {}Hz",
+                self.sample_rate,
+                sample_rate
+            );
+        }
+        if self.latent_dim() != latent_dim {
+            bail!(
+                "Encoded latents have {} channels but Mimi expects {}",
+                self.latent_dim(),
+                latent_dim
+            );
+        }
+        if self.num_frames() == 0 {
+            bail!("No frames to decode");
+        }
+        Ok(())
+    }
+
+    /// Serialize to the `.mimi` container format
+    pub fn to_bytes(&self, precision: LatentPrecision) -> Result<Vec<u8>> {
+        let frames = self.latents.to_vec2::<f32>()?;
+        let mut out = Vec::with_capacity(
+            HEADER_LEN + frames.len() * (4 + self.latent_dim() * precision.bits() / 8),
+        );
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(precision.id());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&self.sample_rate.to_le_bytes());
+        out.extend_from_slice(&self.frame_rate.to_le_bytes());
+        out.extend_from_slice(&(self.latent_dim() as u32).to_le_bytes());
+        out.extend_from_slice(&(frames.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.num_samples.to_le_bytes());
+
+        for frame in &frames {
+            if precision == LatentPrecision::F32 {
+                for v in frame {
+                    out.extend_from_slice(&v.to_le_bytes());
+                }
+                continue;
+            }
+            let peak = frame.iter().fold(0f32, |m, v| m.max(v.abs()));
+            let scale = if peak > 0.0 {
+                peak / precision.max_level()
+            } else {
+                1.0
+            };
+            out.extend_from_slice(&scale.to_le_bytes());
+            for v in frame {
+                let q = (v / scale).round();
+                match precision {
+                    LatentPrecision::I16 => out.extend_from_slice(&(q as i16).to_le_bytes()),
+                    LatentPrecision::I8 => out.push(q as i8 as u8),
+                    LatentPrecision::F32 => unreachable!(),
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Parse the `.mimi` container format
+    pub fn from_bytes(bytes: &[u8], device: &Device) -> Result<Self> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.take(4)? != MAGIC {
+            bail!("Not a Mimi codec file (bad magic)");
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            bail!("Unsupported Mimi codec file version {}", version);
+        }
+        let precision = LatentPrecision::from_id(reader.u8()?)?;
+        reader.take(2)?;
+        let sample_rate = reader.u32()?;
+        let frame_rate = f32::from_le_bytes(reader.array()?);
+        let latent_dim = reader.u32()? as usize;
+        let num_frames = reader.u32()? as usize;
+        let num_samples = u64::from_le_bytes(reader.array()?);
+
+        // The header is untrusted: size the payload before allocating for it
+        let frame_len = match precision {
+            LatentPrecision::F32 => latent_dim.checked_mul(4),
+            _ => latent_dim
+                .checked_mul(precision.bits() / 8)
+                .and_then(|len| len.checked_add(4)),
+        };
+        let payload_len = frame_len.and_then(|len| len.checked_mul(num_frames));
+        match payload_len {
+            Some(len) if len > reader.remaining() => bail!("Truncated Mimi codec file"),
+            Some(len) if len < reader.remaining() => {
+                bail!("Trailing data after Mimi codec frames")
+            }
+            Some(_) => {}
+            None => bail!(
+                "Invalid Mimi codec header: {} frames of {} values",
+                num_frames,
+                latent_dim
+            ),
+        }
+
+        let mut values = Vec::with_capacity(num_frames * latent_dim);
+        for _ in 0..num_frames {
+            if precision == LatentPrecision::F32 {
+                for _ in 0..latent_dim {
+                    values.push(f32::from_le_bytes(reader.array()?));
+                }
+                continue;
+            }
+            let scale = f32::from_le_bytes(reader.array()?);
+            for _ in 0..latent_dim {
+                let q = match precision {
+                    LatentPrecision::I16 => i16::from_le_bytes(reader.array()?) as f32,
+                    LatentPrecision::I8 => reader.u8()? as i8 as f32,
+                    LatentPrecision::F32 => unreachable!(),
+                };
+                values.push(q * scale);
+            }
+        }
+
+        let latents = Tensor::from_vec(values, (num_frames, latent_dim), device)?;
+        Ok(Self {
+            sample_rate,
+            frame_rate,
+            num_samples,
+            latents,
+        })
+    }
+
+    /// Write to a `.mimi` file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+        precision: LatentPrecision,
+    ) -> Result<()> {
+        std::fs::write(path, self.to_bytes(precision)?)?;
+        Ok(())
+    }
+
+    /// Read a `.mimi` file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read<P: AsRef<std::path::Path>>(path: P, device: &Device) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?, device)
+    }
+}
+
+/// Cursor over a byte slice that fails on truncated input
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < n {
+            bail!("Truncated Mimi codec file");
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> EncodedAudio {
+        let values: Vec<f32> = (0..12).map(|i| (i as f32 - 5.5) / 4.0).collect();
+        EncodedAudio {
+            sample_rate: 24000,
+            frame_rate: 12.5,
+            num_samples: 5000,
+            latents: Tensor::from_vec(values, (3, 4), &Device::Cpu).unwrap(),
+        }
+    }
+
+    fn max_error(a: &Tensor, b: &Tensor) -> f32 {
+        (a - b)
+            .unwrap()
+            .abs()
+            .unwrap()
+            .max_all()
+            .unwrap()
+            .to_scalar::<f32>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_f32_is_lossless() {
+        let encoded = sample();
+        let bytes = encoded.to_bytes(LatentPrecision::F32).unwrap();
+        assert_eq!(bytes.len(), HEADER_LEN + 3 * 4 * 4);
+
+        let decoded = EncodedAudio::from_bytes(&bytes, &Device::Cpu).unwrap();
+        assert_eq!(decoded.sample_rate, 24000);
+        assert_eq!(decoded.frame_rate, 12.5);
+        assert_eq!(decoded.num_samples, 5000);
+        assert_eq!(decoded.latent_dim(), 4);
+        assert_eq!(max_error(&decoded.latents, &encoded.latents), 0.0);
+    }
+
+    #[test]
+    fn test_roundtrip_integer_precisions() {
+        let encoded = sample();
+        for (precision, tolerance) in [(LatentPrecision::I16, 1e-4), (LatentPrecision::I8, 1e-2)] {
+            let bytes = encoded.to_bytes(precision).unwrap();
+            assert_eq!(bytes.len(), HEADER_LEN + 3 * (4 + 4 * precision.bits() / 8));
+            let decoded = EncodedAudio::from_bytes(&bytes, &Device::Cpu).unwrap();
+            assert!(max_error(&decoded.latents, &encoded.latents) < tolerance);
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let bytes = sample().to_bytes(LatentPrecision::I8).unwrap();
+        assert!(EncodedAudio::from_bytes(&bytes[..bytes.len() - 1], &Device::Cpu).is_err());
+        assert!(EncodedAudio::from_bytes(b"RIFF0000", &Device::Cpu).is_err());
+
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert!(EncodedAudio::from_bytes(&extra, &Device::Cpu).is_err());
+    }
+
+    #[test]
+    fn test_rejects_lying_header() {
+        let bytes = sample().to_bytes(LatentPrecision::F32).unwrap();
+        let with_header = |latent_dim: u32, num_frames: u32| {
+            let mut bytes = bytes.clone();
+            bytes[16..20].copy_from_slice(&latent_dim.to_le_bytes());
+            bytes[20..24].copy_from_slice(&num_frames.to_le_bytes());
+            bytes
+        };
+
+        // A bare header claiming a huge payload must not allocate for it
+        let huge = with_header(u32::MAX, u32::MAX);
+        assert!(EncodedAudio::from_bytes(&huge[..HEADER_LEN], &Device::Cpu).is_err());
+        assert!(EncodedAudio::from_bytes(&huge, &Device::Cpu).is_err());
+        // More frames than the payload holds
+        assert!(EncodedAudio::from_bytes(&with_header(4, 4), &Device::Cpu).is_err());
+        // Same payload size, other shape, still parses
+        let reshaped = EncodedAudio::from_bytes(&with_header(6, 2), &Device::Cpu).unwrap();
+        assert_eq!(reshaped.latents.dims(), &[2, 6]);
+    }
+
+    #[test]
+    fn test_check_decodable() {
+        let encoded = sample();
+        assert!(encoded.check_decodable(24000, 4).is_ok());
+        assert!(encoded.check_decodable(16000, 4).is_err());
+        assert!(encoded.check_decodable(24000, 32).is_err());
+    }
+
+    #[test]
+    fn test_precision_parsing() {
+        assert_eq!(
+            "I8".parse::<LatentPrecision>().unwrap(),
+            LatentPrecision::I8
+        );
+        assert_eq!(
+            "f32".parse::<LatentPrecision>().unwrap(),
+            LatentPrecision::F32
+        );
+        assert!("f16".parse::<LatentPrecision>().is_err());
+        assert_eq!(LatentPrecision::I16.to_string(), "i16");
+    }
+
+    #[test]
+    fn test_bitrate() {
+        let encoded = sample();
+        assert_eq!(encoded.bitrate(LatentPrecision::F32), 4.0 * 32.0 * 12.5);
+        assert_eq!(
+            encoded.bitrate(LatentPrecision::I8),
+            (4.0 * 8.0 + 32.0) * 12.5
+        );
+    }
+}
@@ -5,6 +5,7 @@ use candle_nn::{Embedding, Module, VarBuilder};
 use tokenizers::Tokenizer;
 
 use anyhow::Result;
+use std::ops::Range;
 use std::path::Path;
 
 use std::sync::Arc;
@@ -311,11 +312,78 @@ impl LUTConditioner {
             .map_err(|e| anyhow::anyhow!("Failed to encode text: {:?}", e))?;
         Ok(encoding.get_ids().len())
     }
+
+    /// Tokenize `text` and report the tokens the vocabulary does not cover
+    pub fn coverage(&self, text: &str) -> Result<TokenCoverage> {
+        token_coverage(&self.tokenizer, text)
+    }
+}
+
+/// Token count of a text and the byte ranges it could only encode as
+/// `<unk>` or byte-fallback (`<0xE2>`) tokens
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenCoverage {
+    /// Total number of tokens
+    pub tokens: usize,
+    /// Byte range of each `<unk>` token in the text
+    pub unknown: Vec<Range<usize>>,
+    /// Byte range of the character behind each byte-fallback token
+    pub byte_fallback: Vec<Range<usize>>,
+}
+
+impl TokenCoverage {
+    /// Whether every token is a regular vocabulary piece
+    pub fn is_covered(&self) -> bool {
+        self.unknown.is_empty() && self.byte_fallback.is_empty()
+    }
+
+    /// Byte ranges of the unknown and byte-fallback tokens, in text order
+    pub fn unsupported_spans(&self) -> Vec<Range<usize>> {
+        let mut spans: Vec<_> = self
+            .unknown
+            .iter()
+            .chain(&self.byte_fallback)
+            .cloned()
+            .collect();
+        spans.sort_by_key(|span| span.start);
+        spans.dedup();
+        spans
+    }
+}
+
+fn token_coverage(tokenizer: &Tokenizer, text: &str) -> Result<TokenCoverage> {
+    let encoding = tokenizer
+        .encode(text, true)
+        .map_err(|e| anyhow::anyhow!("Failed to encode text: {:?}", e))?;
+    let unk_id = tokenizer.token_to_id("<unk>");
+    let mut coverage = TokenCoverage {
+        tokens: encoding.get_ids().len(),
+        ..TokenCoverage::default()
+    };
+    for (&id, &(start, end)) in encoding.get_ids().iter().zip(encoding.get_offsets()) {
+        if Some(id) == unk_id {
+            coverage.unknown.push(start..end);
+        } else if tokenizer
+            .id_to_token(id)
+            .is_some_and(|piece| is_byte_piece(&piece))
+        {
+            coverage.byte_fallback.push(start..end);
+        }
+    }
+    Ok(coverage)
+}
+
+/// Whether `piece` is a SentencePiece byte-fallback piece such as `<0xE2>`
+fn is_byte_piece(piece: &str) -> bool {
+    piece
+        .strip_prefix("<0x")
+        .and_then(|hex| hex.strip_suffix('>'))
+        .is_some_and(|hex| hex.len() == 2 && hex.chars().all(|c| c.is_ascii_hexdigit()))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::LUTConditioner;
+    use super::{LUTConditioner, token_coverage};
 
     fn encode_varint(mut value: u64) -> Vec<u8> {
         let mut out = Vec::new();
@@ -387,4 +455,29 @@ mod tests {
         let err = LUTConditioner::parse_sentencepiece_vocab(&[]).expect_err("expected empty error");
         assert!(err.to_string().contains("No vocabulary found"));
     }
+
+    #[test]
+    fn test_token_coverage_reports_unknown_and_byte_fallback() {
+        use tokenizers::Tokenizer;
+        use tokenizers::models::unigram::Unigram;
+        use tokenizers::pre_tokenizers::metaspace::{Metaspace, PrependScheme};
+
+        let vocab = ["<unk>", "▁", "▁hi", "!", "<0xE2>", "<0x80>", "<0x94>"]
+            .iter()
+            .map(|piece| (piece.to_string(), -1.0))
+            .collect();
+        let unigram = Unigram::from(vocab, Some(0), true).unwrap();
+        let mut tokenizer = Tokenizer::new(unigram);
+        tokenizer.with_pre_tokenizer(Some(Metaspace::new('▁', PrependScheme::Always, false)));
+
+        assert!(token_coverage(&tokenizer, "hi hi!").unwrap().is_covered());
+
+        // "—" is E2 80 94 in UTF-8; "x" has no piece at all
+        let text = "hi— x hi";
+        let coverage = token_coverage(&tokenizer, text).unwrap();
+        assert_eq!(coverage.byte_fallback, vec![2..5, 2..5, 2..5]);
+        assert_eq!(coverage.unknown, vec![6..7]);
+        assert_eq!(coverage.unsupported_spans(), vec![2..5, 6..7]);
+        assert_eq!(&text[2..5], "—");
+    }
 }
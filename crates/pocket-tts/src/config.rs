@@ -107,6 +107,18 @@ pub struct Config {
     pub weights_path_without_voice_cloning: Option<String>,
 }
 
+impl Config {
+    /// Variant signature taken from the `tts_<variant>.safetensors` weights
+    /// file name
+    pub fn variant(&self) -> Option<String> {
+        let path = self.weights_path.as_deref()?;
+        let path = path.split('@').next()?;
+        let file = path.rsplit('/').next()?;
+        let variant = file.strip_prefix("tts_")?.strip_suffix(".safetensors")?;
+        (!variant.is_empty()).then(|| variant.to_string())
+    }
+}
+
 /// Load configuration from a YAML file
 pub fn load_config<P: AsRef<Path>>(path: P) -> anyhow::Result<Config> {
     let contents = std::fs::read_to_string(path)?;
@@ -165,4 +177,13 @@ mod tests {
             eprintln!("Config file not found at {:?}, skipping test", path);
         }
     }
+
+    #[test]
+    fn test_variant_from_weights_path() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("config")
+            .join("b6369a24.yaml");
+        let config = load_config(&path).expect("Failed to load config");
+        assert_eq!(config.variant().as_deref(), Some("b6369a24"));
+    }
 }
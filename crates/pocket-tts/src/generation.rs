@@ -0,0 +1,654 @@
+//! Per-call generation options
+//!
+//! `GenerationOptions` carries every knob that affects a single generation
+//! call, so callers no longer need to clone and mutate `TTSModel` to change
+//! settings for one request. A `GenerationHandle` attached to the options
+//! lets the caller cancel a running generation and observe its progress.
+
+use crate::config::defaults;
+use crate::lexicon::Lexicon;
+use crate::markdown::MarkdownOptions;
+use crate::pause::PauseConfig;
+use crate::text_coverage::UnsupportedCharPolicy;
+use candle_core::Tensor;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Default maximum number of tokens per text chunk (matches Python's splitter)
+pub const MAX_TOKENS_PER_CHUNK: usize = 50;
+
+/// Slowest accepted speaking rate (matches OpenAI's `speed` range)
+pub const MIN_SPEED: f32 = 0.25;
+/// Fastest accepted speaking rate (matches OpenAI's `speed` range)
+pub const MAX_SPEED: f32 = 4.0;
+
+/// Most best-of-N candidates accepted per sentence chunk
+pub const MAX_CANDIDATES: usize = 16;
+
+/// Most continuity frames accepted (4 seconds of audio), keeping the prompt
+/// re-encoded before every chunk short
+pub const MAX_CONTINUITY_FRAMES: usize = 50;
+
+/// Frame RMS below which audio counts as silence (about -40 dBFS)
+const SILENCE_RMS: f32 = 0.01;
+/// Sample magnitude at which audio counts as clipped
+const CLIP_LEVEL: f32 = 0.99;
+/// Fraction of the frame budget below which a chunk looks truncated
+const MIN_DURATION_RATIO: f32 = 0.15;
+
+/// Settings for a single generate/stream call
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationOptions {
+    /// Sampling temperature (0.0 = deterministic)
+    pub temperature: f32,
+    /// Number of LSD decode steps
+    pub lsd_decode_steps: usize,
+    /// End-of-sequence threshold (more negative = longer audio)
+    pub eos_threshold: f32,
+    /// Optional clamp for sampled noise (truncated normal)
+    pub noise_clamp: Option<f32>,
+    /// Frames to generate after EOS. If `None`, estimated from the chunk text.
+    pub frames_after_eos: Option<usize>,
+    /// Maximum frames per text chunk. If `None`, estimated from the word count.
+    pub max_frames_per_chunk: Option<usize>,
+    /// Maximum tokens per text chunk when splitting long text
+    pub max_tokens_per_chunk: usize,
+    /// Seed for the sampling RNG. If `None`, a fresh entropy-seeded RNG is used.
+    pub seed: Option<u64>,
+    /// Frames of previously generated audio (12.5 per second) used to prompt
+    /// each following sentence chunk, carrying pitch and pace across chunk
+    /// boundaries. `0` disables continuity and every chunk starts fresh.
+    pub continuity_frames: usize,
+    /// Cancellation and progress handle checked on every generated frame
+    pub handle: Option<GenerationHandle>,
+    /// Speaking rate multiplier; `1.0` is the model's natural pace and
+    /// 0.5–2.0 sounds best. Pitch is preserved.
+    pub speed: f32,
+    /// Candidates sampled per sentence chunk; the best one by
+    /// [`CandidateScore`] is kept. `1` streams the only candidate directly,
+    /// larger values buffer each chunk until all candidates are done.
+    pub candidates: usize,
+    /// Expand numbers, dates, currency, units and abbreviations into words
+    /// (see [`crate::text_normalize`]). Applies to the long-form entry points,
+    /// `generate_stream_long*` and `generate_events*`.
+    pub normalize_text: bool,
+    /// How the long-form entry points read their text: plain text with
+    /// `[pause:...]` markers, an SSML subset (see [`crate::ssml`]) or
+    /// Markdown (see [`crate::markdown`])
+    pub input_format: InputFormat,
+    /// Let SSML `<audio src>` load WAV files from the local filesystem. Off
+    /// by default so untrusted SSML cannot read files; the element's fallback
+    /// text is spoken instead.
+    pub ssml_audio: bool,
+    /// Pronunciation respellings applied to the text before tokenization
+    pub lexicon: Option<Arc<Lexicon>>,
+    /// Natural pauses inserted at punctuation by the long-form entry points
+    pub pauses: PauseConfig,
+    /// Heading pauses and code block handling for Markdown input
+    pub markdown: MarkdownOptions,
+    /// What generation does with characters the tokenizer cannot encode
+    /// (see [`crate::text_coverage`])
+    pub unsupported_chars: UnsupportedCharPolicy,
+}
+
+/// Text format accepted by the long-form entry points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputFormat {
+    /// Plain text with optional `[pause:500ms]` markers
+    #[default]
+    Text,
+    /// SSML subset parsed by [`crate::ssml::parse_ssml`]
+    Ssml,
+    /// Markdown read by [`crate::markdown::parse_markdown`]
+    Markdown,
+}
+
+impl Default for GenerationOptions {
+    fn default() -> Self {
+        Self {
+            temperature: defaults::TEMPERATURE,
+            lsd_decode_steps: defaults::LSD_DECODE_STEPS,
+            eos_threshold: defaults::EOS_THRESHOLD,
+            noise_clamp: defaults::NOISE_CLAMP,
+            frames_after_eos: None,
+            max_frames_per_chunk: None,
+            max_tokens_per_chunk: MAX_TOKENS_PER_CHUNK,
+            seed: None,
+            continuity_frames: 0,
+            handle: None,
+            speed: 1.0,
+            candidates: 1,
+            normalize_text: true,
+            input_format: InputFormat::Text,
+            ssml_audio: false,
+            lexicon: None,
+            pauses: PauseConfig::default(),
+            markdown: MarkdownOptions::default(),
+            unsupported_chars: UnsupportedCharPolicy::default(),
+        }
+    }
+}
+
+impl GenerationOptions {
+    /// Return a copy of these options with the given seed
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Return a copy of these options with the given handle attached
+    pub fn with_handle(mut self, handle: GenerationHandle) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Check that the options describe a valid generation
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&self.speed) {
+            anyhow::bail!(
+                "speed must be between {} and {}, got {}",
+                MIN_SPEED,
+                MAX_SPEED,
+                self.speed
+            );
+        }
+        if !(1..=MAX_CANDIDATES).contains(&self.candidates) {
+            anyhow::bail!(
+                "candidates must be between 1 and {}, got {}",
+                MAX_CANDIDATES,
+                self.candidates
+            );
+        }
+        if self.continuity_frames > MAX_CONTINUITY_FRAMES {
+            anyhow::bail!(
+                "continuity frames must be at most {}, got {}",
+                MAX_CONTINUITY_FRAMES,
+                self.continuity_frames
+            );
+        }
+        self.pauses.validate()
+    }
+
+    /// Apply the lexicon (if any) to `text`
+    pub(crate) fn apply_lexicon(&self, text: &str) -> String {
+        match &self.lexicon {
+            Some(lexicon) => lexicon.apply(text),
+            None => text.to_string(),
+        }
+    }
+
+    /// Whether the attached handle (if any) has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(GenerationHandle::is_cancelled)
+    }
+
+    /// Forward a progress update to the attached handle (if any)
+    pub(crate) fn report_progress(&self, progress: GenerationProgress) {
+        if let Some(handle) = &self.handle {
+            handle.report(progress);
+        }
+    }
+}
+
+/// Resamples the FlowLM latent sequence in time to change speaking rate
+///
+/// Output frame `k` sits at latent position `k * speed`; positions between two
+/// latents are linearly interpolated before Mimi decoding, so timing changes
+/// while pitch does not. At `speed == 1.0` every latent passes through as is.
+pub(crate) struct LatentRateResampler {
+    speed: f64,
+    next_pos: f64,
+    index: usize,
+    prev: Option<Tensor>,
+}
+
+impl LatentRateResampler {
+    pub(crate) fn new(speed: f32) -> Self {
+        Self {
+            speed: speed as f64,
+            next_pos: 0.0,
+            index: 0,
+            prev: None,
+        }
+    }
+
+    /// Feed the next latent and return the frames to decode (possibly none)
+    pub(crate) fn push(&mut self, latent: &Tensor) -> candle_core::Result<Vec<Tensor>> {
+        let pos = self.index as f64;
+        let mut frames = Vec::new();
+        while self.next_pos <= pos + 1e-6 {
+            // Distance back from the current latent, in [0, 1)
+            let back = pos - self.next_pos;
+            let frame = match &self.prev {
+                Some(prev) if back > 1e-6 => ((prev * back)? + (latent * (1.0 - back))?)?,
+                _ => latent.clone(),
+            };
+            frames.push(frame);
+            self.next_pos += self.speed;
+        }
+        self.prev = Some(latent.clone());
+        self.index += 1;
+        Ok(frames)
+    }
+}
+
+/// Heuristic quality of one best-of-N candidate chunk
+///
+/// `score` starts at 0 and subtracts 1 when EOS was never reached, the
+/// silence ratio, 10 times the clipping ratio, and up to 1 when the chunk used
+/// less than 15% of its frame budget before EOS. The highest score wins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandidateScore {
+    /// Whether the candidate reached EOS before its frame budget ran out
+    pub eos_reached: bool,
+    /// Frames until EOS (or all frames) divided by the chunk's frame budget
+    pub duration_ratio: f32,
+    /// Fraction of frames before EOS that are near-silent
+    pub silence_ratio: f32,
+    /// Fraction of samples at or beyond full scale
+    pub clipping_ratio: f32,
+    /// Combined score; higher is better
+    pub score: f32,
+}
+
+/// Statistics gathered frame by frame while a candidate is generated
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CandidateStats {
+    eos_step: Option<usize>,
+    speech_steps: usize,
+    audio_frames: usize,
+    silent_frames: usize,
+    samples: usize,
+    clipped: usize,
+}
+
+impl CandidateStats {
+    /// Record one generated frame
+    pub(crate) fn push(
+        &mut self,
+        step: usize,
+        eos_detected: bool,
+        audio: &Tensor,
+    ) -> candle_core::Result<()> {
+        if eos_detected && self.eos_step.is_none() {
+            self.eos_step = Some(step);
+        }
+        let before_eos = self.eos_step.is_none_or(|eos| step <= eos);
+        if before_eos {
+            self.speech_steps += 1;
+        }
+
+        let n = audio.elem_count();
+        if n == 0 {
+            return Ok(());
+        }
+        if before_eos {
+            self.audio_frames += 1;
+            let rms = audio.sqr()?.mean_all()?.sqrt()?.to_scalar::<f32>()?;
+            if rms < SILENCE_RMS {
+                self.silent_frames += 1;
+            }
+        }
+        self.samples += n;
+        self.clipped += audio
+            .abs()?
+            .ge(CLIP_LEVEL)?
+            .to_dtype(candle_core::DType::F32)?
+            .sum_all()?
+            .to_scalar::<f32>()? as usize;
+        Ok(())
+    }
+
+    /// Score the candidate against its frame budget (`max_gen_len`)
+    pub(crate) fn score(&self, max_gen_len: usize) -> CandidateScore {
+        let ratio = |num: usize, den: usize| {
+            if den == 0 {
+                0.0
+            } else {
+                num as f32 / den as f32
+            }
+        };
+        let eos_reached = self.eos_step.is_some();
+        let duration_ratio = ratio(self.speech_steps, max_gen_len);
+        let silence_ratio = ratio(self.silent_frames, self.audio_frames);
+        let clipping_ratio = ratio(self.clipped, self.samples);
+
+        let mut score = -silence_ratio - 10.0 * clipping_ratio;
+        if !eos_reached {
+            score -= 1.0;
+        }
+        if duration_ratio < MIN_DURATION_RATIO {
+            score -= 1.0 - duration_ratio / MIN_DURATION_RATIO;
+        }
+        CandidateScore {
+            eos_reached,
+            duration_ratio,
+            silence_ratio,
+            clipping_ratio,
+            score,
+        }
+    }
+}
+
+/// Index of the best-scoring candidate (the first one on ties)
+pub(crate) fn best_candidate(scores: &[CandidateScore]) -> usize {
+    scores.iter().enumerate().fold(0, |best, (i, s)| {
+        if s.score > scores[best].score {
+            i
+        } else {
+            best
+        }
+    })
+}
+
+/// Progress of a running generation, reported once per generated frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationProgress {
+    /// Index of the sentence chunk being generated within the call
+    pub chunk_index: usize,
+    /// Number of sentence chunks in the call
+    pub num_chunks: usize,
+    /// Frame step within the current chunk
+    pub step: usize,
+    /// Upper bound on frames for the current chunk (`max_frames_per_chunk` or its estimate)
+    pub max_gen_len: usize,
+}
+
+/// Event yielded by [`crate::TTSModel::generate_events`]
+#[derive(Debug, Clone)]
+pub struct GenerationEvent {
+    /// Offset, in output samples, at which this event happens
+    pub sample_offset: usize,
+    pub kind: GenerationEventKind,
+}
+
+/// What happened at a [`GenerationEvent`]
+#[derive(Debug, Clone)]
+pub enum GenerationEventKind {
+    /// A sentence chunk is about to be generated
+    ChunkStarted { index: usize, text: String },
+    /// One decoded Mimi frame `[1, channels, samples]`
+    Audio { samples: Tensor, frame_step: usize },
+    /// Silence `[1, channels, samples]` inserted for a pause
+    Pause { ms: u32, samples: Tensor },
+    /// Pre-recorded audio `[1, channels, samples]` from an SSML `<audio>` element
+    AudioClip { src: String, samples: Tensor },
+    /// EOS was first detected at this frame step of the current chunk
+    EosDetected { step: usize },
+    /// Best-of-N picked `selected` among the candidates of chunk `index`
+    CandidateSelected {
+        index: usize,
+        selected: usize,
+        scores: Vec<CandidateScore>,
+    },
+    /// The sentence chunk has finished generating
+    ChunkFinished { index: usize },
+    /// A `[voice:...]` marker switched the speaker for the following chunks
+    VoiceChanged { voice: String },
+}
+
+impl GenerationEventKind {
+    /// Number of output samples this event contributes
+    pub fn num_samples(&self) -> usize {
+        match self {
+            Self::Audio { samples, .. }
+            | Self::Pause { samples, .. }
+            | Self::AudioClip { samples, .. } => samples.dims().last().copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Audio carried by this event, if any
+    pub fn samples(&self) -> Option<&Tensor> {
+        match self {
+            Self::Audio { samples, .. }
+            | Self::Pause { samples, .. }
+            | Self::AudioClip { samples, .. } => Some(samples),
+            _ => None,
+        }
+    }
+}
+
+/// Callback invoked with generation progress
+pub type ProgressCallback = Arc<dyn Fn(&GenerationProgress) + Send + Sync>;
+
+/// Shared handle used to cancel a generation and observe its progress
+///
+/// Clones share the same cancellation flag, so a handle can be kept by the
+/// caller while a clone travels with the `GenerationOptions`. Streams stop
+/// yielding frames once the handle is cancelled.
+#[derive(Clone, Default)]
+pub struct GenerationHandle {
+    cancelled: Arc<AtomicBool>,
+    progress: Option<ProgressCallback>,
+}
+
+impl GenerationHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach a progress callback, called from the generating thread
+    pub fn with_progress(
+        mut self,
+        callback: impl Fn(&GenerationProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Request cancellation; generation stops before the next frame
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn report(&self, progress: GenerationProgress) {
+        if let Some(callback) = &self.progress {
+            callback(&progress);
+        }
+    }
+}
+
+impl fmt::Debug for GenerationHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerationHandle")
+            .field("cancelled", &self.is_cancelled())
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+/// Handles are equal when they share the same cancellation flag
+impl PartialEq for GenerationHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_matches_config_defaults() {
+        let opts = GenerationOptions::default();
+        assert_eq!(opts.temperature, defaults::TEMPERATURE);
+        assert_eq!(opts.lsd_decode_steps, defaults::LSD_DECODE_STEPS);
+        assert_eq!(opts.eos_threshold, defaults::EOS_THRESHOLD);
+        assert_eq!(opts.noise_clamp, None);
+        assert_eq!(opts.max_tokens_per_chunk, MAX_TOKENS_PER_CHUNK);
+        assert_eq!(opts.seed, None);
+        assert_eq!(opts.continuity_frames, 0);
+        assert_eq!(opts.candidates, 1);
+        assert!(opts.normalize_text);
+    }
+
+    #[test]
+    fn test_with_seed() {
+        let opts = GenerationOptions::default().with_seed(Some(3));
+        assert_eq!(opts.seed, Some(3));
+    }
+
+    #[test]
+    fn test_handle_cancel_is_shared_between_clones() {
+        let handle = GenerationHandle::new();
+        let opts = GenerationOptions::default().with_handle(handle.clone());
+        assert!(!opts.is_cancelled());
+        handle.cancel();
+        assert!(opts.clone().is_cancelled());
+        assert_eq!(opts.handle, Some(handle));
+    }
+
+    #[test]
+    fn test_validate_speed_range() {
+        let mut opts = GenerationOptions::default();
+        assert!(opts.validate().is_ok());
+        opts.speed = 0.1;
+        assert!(opts.validate().is_err());
+        opts.speed = f32::NAN;
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_candidates_range() {
+        let mut opts = GenerationOptions {
+            candidates: 0,
+            ..Default::default()
+        };
+        assert!(opts.validate().is_err());
+        opts.candidates = MAX_CANDIDATES;
+        assert!(opts.validate().is_ok());
+        opts.candidates = MAX_CANDIDATES + 1;
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_continuity_frames_range() {
+        let mut opts = GenerationOptions {
+            continuity_frames: MAX_CONTINUITY_FRAMES,
+            ..Default::default()
+        };
+        assert!(opts.validate().is_ok());
+        opts.continuity_frames = MAX_CONTINUITY_FRAMES + 1;
+        assert!(opts.validate().is_err());
+        opts.continuity_frames = usize::MAX;
+        assert!(opts.validate().is_err());
+    }
+
+    fn frame(value: f32) -> Tensor {
+        Tensor::full(value, (1, 1, 1920), &candle_core::Device::Cpu).unwrap()
+    }
+
+    #[test]
+    fn test_candidate_stats_scores() {
+        let mut clean = CandidateStats::default();
+        for step in 0..10 {
+            clean.push(step, step == 7, &frame(0.3)).unwrap();
+        }
+        // Trailing quiet frames after EOS do not count as dead air
+        clean.push(10, false, &frame(0.0)).unwrap();
+        let clean = clean.score(20);
+        assert!(clean.eos_reached);
+        assert_eq!(clean.duration_ratio, 0.4);
+        assert_eq!(clean.silence_ratio, 0.0);
+        assert_eq!(clean.clipping_ratio, 0.0);
+        assert_eq!(clean.score, 0.0);
+
+        let mut runaway = CandidateStats::default();
+        for step in 0..20 {
+            let value = if step % 2 == 0 { 0.0 } else { 1.0 };
+            runaway.push(step, false, &frame(value)).unwrap();
+        }
+        let runaway = runaway.score(20);
+        assert!(!runaway.eos_reached);
+        assert_eq!(runaway.silence_ratio, 0.5);
+        assert_eq!(runaway.clipping_ratio, 0.5);
+
+        let mut truncated = CandidateStats::default();
+        truncated.push(0, true, &frame(0.3)).unwrap();
+        let truncated = truncated.score(20);
+        assert!(truncated.score < clean.score);
+
+        assert_eq!(best_candidate(&[runaway, clean, truncated]), 1);
+    }
+
+    fn resample_counts(speed: f32, n: usize) -> Vec<usize> {
+        let device = candle_core::Device::Cpu;
+        let mut resampler = LatentRateResampler::new(speed);
+        (0..n)
+            .map(|i| {
+                let latent = Tensor::new(&[[i as f32]], &device).unwrap();
+                resampler.push(&latent).unwrap().len()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resampler_frame_counts() {
+        assert_eq!(resample_counts(1.0, 4), vec![1, 1, 1, 1]);
+        assert_eq!(resample_counts(2.0, 4), vec![1, 0, 1, 0]);
+        assert_eq!(resample_counts(0.5, 4), vec![1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn test_resampler_interpolates_between_latents() {
+        let device = candle_core::Device::Cpu;
+        let mut resampler = LatentRateResampler::new(0.5);
+        let mut values = Vec::new();
+        for i in 0..3 {
+            let latent = Tensor::new(&[[i as f32 * 2.0]], &device).unwrap();
+            for frame in resampler.push(&latent).unwrap() {
+                values.push(frame.flatten_all().unwrap().to_vec1::<f32>().unwrap()[0]);
+            }
+        }
+        assert_eq!(values, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_event_num_samples() {
+        let samples = Tensor::zeros(
+            (1, 1, 1920),
+            candle_core::DType::F32,
+            &candle_core::Device::Cpu,
+        )
+        .unwrap();
+        let audio = GenerationEventKind::Audio {
+            samples: samples.clone(),
+            frame_step: 0,
+        };
+        let pause = GenerationEventKind::Pause { ms: 80, samples };
+        let eos = GenerationEventKind::EosDetected { step: 3 };
+        assert_eq!(audio.num_samples(), 1920);
+        assert_eq!(pause.num_samples(), 1920);
+        assert_eq!(eos.num_samples(), 0);
+        assert!(eos.samples().is_none());
+    }
+
+    #[test]
+    fn test_handle_reports_progress() {
+        use std::sync::Mutex;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let handle =
+            GenerationHandle::new().with_progress(move |p| sink.lock().unwrap().push(p.step));
+        let opts = GenerationOptions::default().with_handle(handle);
+        for step in 0..3 {
+            opts.report_progress(GenerationProgress {
+                chunk_index: 0,
+                num_chunks: 1,
+                step,
+                max_gen_len: 3,
+            });
+        }
+        assert_eq!(*seen.lock().unwrap(), vec![0, 1, 2]);
+    }
+}
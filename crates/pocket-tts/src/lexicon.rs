@@ -0,0 +1,398 @@
+//! User pronunciation lexicon
+//!
+//! Maps words and regex patterns to respellings the model pronounces
+//! correctly, e.g. `nginx` -> `engine x`. Lexicons are plain TOML or JSON:
+//!
+//! ```toml
+//! [words]
+//! nginx = "engine x"
+//! Nguyen = "win"
+//! SQL = "sequel"
+//!
+//! [[patterns]]
+//! pattern = 'v(\d+)\.(\d+)'
+//! respelling = "version $1 point $2"
+//! ```
+//!
+//! Word rules:
+//! - Only whole words match: "SQL" does not touch "MySQL".
+//! - An entry written in lowercase, or in lowercase with a capital first
+//!   letter, matches any casing, and the respelling follows the casing of the
+//!   match: "NGINX" gets an all-caps respelling, "Nginx" a capitalized one.
+//!   Any other entry with uppercase letters only matches that exact casing, so
+//!   "US" can differ from "us". Casing is Unicode-aware: "Über" matches "über".
+//! - Longer entries win over shorter ones that start at the same place.
+//!
+//! Patterns run first, in file order, with `$1`-style capture references in
+//! the respelling. Words are replaced in a single pass afterwards, so one
+//! respelling is never rewritten by another word entry.
+
+use anyhow::{Result, bail};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A regex entry of a [`Lexicon`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternEntry {
+    /// Regular expression matched against the text
+    pub pattern: String,
+    /// Replacement; `$1`, `${name}` refer to capture groups
+    pub respelling: String,
+}
+
+/// On-disk and wire shape of a lexicon
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LexiconFile {
+    #[serde(default)]
+    words: BTreeMap<String, String>,
+    #[serde(default)]
+    patterns: Vec<PatternEntry>,
+}
+
+/// Word and pattern respellings applied to text before tokenization
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "LexiconFile", into = "LexiconFile")]
+pub struct Lexicon {
+    words: BTreeMap<String, String>,
+    patterns: Vec<PatternEntry>,
+    /// Alternation of every word entry, one capture group per entry
+    word_regex: Option<Regex>,
+    /// Word entries in the order of their capture groups
+    word_order: Vec<String>,
+    pattern_regexes: Vec<Regex>,
+}
+
+impl PartialEq for Lexicon {
+    fn eq(&self, other: &Self) -> bool {
+        self.words == other.words && self.patterns == other.patterns
+    }
+}
+
+impl TryFrom<LexiconFile> for Lexicon {
+    type Error = anyhow::Error;
+
+    fn try_from(file: LexiconFile) -> Result<Self> {
+        let mut lexicon = Self {
+            words: file.words,
+            patterns: file.patterns,
+            ..Self::default()
+        };
+        for word in lexicon.words.keys() {
+            check_word(word)?;
+        }
+        lexicon.compile()?;
+        Ok(lexicon)
+    }
+}
+
+impl From<Lexicon> for LexiconFile {
+    fn from(lexicon: Lexicon) -> Self {
+        Self {
+            words: lexicon.words,
+            patterns: lexicon.patterns,
+        }
+    }
+}
+
+impl Lexicon {
+    /// Empty lexicon
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a lexicon from TOML
+    pub fn from_toml(s: &str) -> Result<Self> {
+        Ok(toml::from_str(s)?)
+    }
+
+    /// Parse a lexicon from JSON
+    pub fn from_json(s: &str) -> Result<Self> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Load a lexicon file; `.json` files are read as JSON, anything else as TOML
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let lexicon = if is_json {
+            Self::from_json(&contents)
+        } else {
+            Self::from_toml(&contents)
+        };
+        lexicon.map_err(|e| anyhow::anyhow!("Failed to parse lexicon {:?}: {}", path, e))
+    }
+
+    /// Word entries, keyed by the word as written
+    pub fn words(&self) -> &BTreeMap<String, String> {
+        &self.words
+    }
+
+    /// Pattern entries in the order they are applied
+    pub fn patterns(&self) -> &[PatternEntry] {
+        &self.patterns
+    }
+
+    /// Number of word and pattern entries
+    pub fn len(&self) -> usize {
+        self.words.len() + self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Add or replace a word entry
+    pub fn insert_word(&mut self, word: &str, respelling: &str) -> Result<()> {
+        let word = word.trim();
+        check_word(word)?;
+        self.words.insert(word.to_string(), respelling.to_string());
+        self.compile()
+    }
+
+    /// Remove a word entry, returning whether it existed
+    pub fn remove_word(&mut self, word: &str) -> bool {
+        let removed = self.words.remove(word.trim()).is_some();
+        if removed {
+            self.compile()
+                .expect("removing a word keeps the lexicon valid");
+        }
+        removed
+    }
+
+    /// Add a pattern entry, or replace the respelling of an identical pattern
+    pub fn insert_pattern(&mut self, pattern: &str, respelling: &str) -> Result<()> {
+        let regex = Regex::new(pattern)?;
+        let entry = PatternEntry {
+            pattern: pattern.to_string(),
+            respelling: respelling.to_string(),
+        };
+        match self.patterns.iter().position(|p| p.pattern == pattern) {
+            Some(i) => {
+                self.patterns[i] = entry;
+                self.pattern_regexes[i] = regex;
+            }
+            None => {
+                self.patterns.push(entry);
+                self.pattern_regexes.push(regex);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a pattern entry, returning whether it existed
+    pub fn remove_pattern(&mut self, pattern: &str) -> bool {
+        match self.patterns.iter().position(|p| p.pattern == pattern) {
+            Some(i) => {
+                self.patterns.remove(i);
+                self.pattern_regexes.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace every lexicon match in `text` with its respelling
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for (regex, entry) in self.pattern_regexes.iter().zip(&self.patterns) {
+            text = regex
+                .replace_all(&text, entry.respelling.as_str())
+                .into_owned();
+        }
+        let Some(word_regex) = &self.word_regex else {
+            return text;
+        };
+        word_regex
+            .replace_all(&text, |caps: &Captures| {
+                let (index, matched) = caps
+                    .iter()
+                    .skip(1)
+                    .enumerate()
+                    .find_map(|(i, m)| Some((i, m?.as_str())))
+                    .expect("one word group matches");
+                let word = &self.word_order[index];
+                let respelling = &self.words[word];
+                if is_case_sensitive(word) {
+                    respelling.clone()
+                } else {
+                    match_case(matched, respelling)
+                }
+            })
+            .into_owned()
+    }
+
+    /// Rebuild the regexes after the entries changed
+    fn compile(&mut self) -> Result<()> {
+        self.pattern_regexes = self
+            .patterns
+            .iter()
+            .map(|p| Regex::new(&p.pattern))
+            .collect::<Result<_, _>>()?;
+
+        // Longest first so "New York" wins over "New"; exact-case entries
+        // before case-insensitive ones of the same length
+        let mut order: Vec<String> = self.words.keys().cloned().collect();
+        order.sort_by_key(|w| (std::cmp::Reverse(w.chars().count()), !is_case_sensitive(w)));
+        self.word_regex = if order.is_empty() {
+            None
+        } else {
+            let alternatives: Vec<String> = order.iter().map(|w| word_pattern(w)).collect();
+            Some(Regex::new(&alternatives.join("|"))?)
+        };
+        self.word_order = order;
+        Ok(())
+    }
+}
+
+fn check_word(word: &str) -> Result<()> {
+    if word.trim().is_empty() {
+        bail!("lexicon words must not be empty");
+    }
+    Ok(())
+}
+
+/// Entries other than lowercase or capitalized words ("nginx", "Über") only
+/// match their exact casing
+fn is_case_sensitive(word: &str) -> bool {
+    let has_lowercase = word.chars().any(char::is_lowercase);
+    let has_uppercase = word.chars().any(char::is_uppercase);
+    has_uppercase && (!has_lowercase || word.chars().skip(1).any(char::is_uppercase))
+}
+
+/// Capture group matching `word` as a whole word
+fn word_pattern(word: &str) -> String {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let start = if word.starts_with(is_word_char) {
+        r"\b"
+    } else {
+        ""
+    };
+    let end = if word.ends_with(is_word_char) {
+        r"\b"
+    } else {
+        ""
+    };
+    let flags = if is_case_sensitive(word) { "" } else { "(?i)" };
+    format!("({}{}{}{})", flags, start, regex::escape(word), end)
+}
+
+/// Give the respelling the casing of the matched text: all caps, capitalized
+/// or lowercase
+fn match_case(matched: &str, respelling: &str) -> String {
+    let letters = matched.chars().filter(|c| c.is_alphabetic()).count();
+    if letters > 1 && !matched.chars().any(char::is_lowercase) {
+        return respelling.to_uppercase();
+    }
+    let mut chars = respelling.chars();
+    match (matched.chars().next(), chars.next()) {
+        (Some(m), Some(first)) if m.is_uppercase() => first.to_uppercase().chain(chars).collect(),
+        (Some(m), Some(first)) if m.is_lowercase() => first.to_lowercase().chain(chars).collect(),
+        _ => respelling.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOML: &str = r#"
+[words]
+nginx = "engine x"
+SQL = "sequel"
+"New York" = "noo york"
+new = "knew"
+"C++" = "see plus plus"
+
+[[patterns]]
+pattern = 'v(\d+)\.(\d+)'
+respelling = "version $1 point $2"
+"#;
+
+    #[test]
+    fn test_word_boundaries_and_case() {
+        let lexicon = Lexicon::from_toml(TOML).unwrap();
+        assert_eq!(
+            lexicon.apply("Nginx and NGINX behind nginx-proxy."),
+            "Engine x and ENGINE X behind engine x-proxy."
+        );
+        assert_eq!(
+            lexicon.apply("SQL, not sql or MySQL."),
+            "sequel, not sql or MySQL."
+        );
+        assert_eq!(
+            lexicon.apply("New York is new, C++ too."),
+            "noo york is knew, see plus plus too."
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_case() {
+        let lexicon = Lexicon::from_toml(
+            r#"
+[words]
+"Über" = "ueber"
+"ünter" = "unter"
+"ÉTÉ" = "summer"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            lexicon.apply("über, Über and ÜBER"),
+            "ueber, Ueber and UEBER"
+        );
+        assert_eq!(
+            lexicon.apply("ünter, Ünter and ÜNTER"),
+            "unter, Unter and UNTER"
+        );
+        assert_eq!(lexicon.apply("ÉTÉ but été"), "summer but été");
+    }
+
+    #[test]
+    fn test_patterns_run_before_words() {
+        let lexicon = Lexicon::from_toml(TOML).unwrap();
+        assert_eq!(
+            lexicon.apply("Upgrade nginx to v1.25 now."),
+            "Upgrade engine x to version 1 point 25 now."
+        );
+    }
+
+    #[test]
+    fn test_json_matches_toml() {
+        let json = r#"{"words": {"nginx": "engine x"}, "patterns": []}"#;
+        let lexicon = Lexicon::from_json(json).unwrap();
+        assert_eq!(lexicon.apply("nginx"), "engine x");
+        assert_eq!(
+            serde_json::to_value(&lexicon).unwrap(),
+            serde_json::json!({"words": {"nginx": "engine x"}, "patterns": []})
+        );
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut lexicon = Lexicon::new();
+        assert!(lexicon.is_empty());
+        lexicon.insert_word("kyutai", "kyoo tie").unwrap();
+        lexicon.insert_pattern(r"#(\d+)", "issue $1").unwrap();
+        assert_eq!(lexicon.len(), 2);
+        assert_eq!(lexicon.apply("Kyutai #42"), "Kyoo tie issue 42");
+
+        assert!(lexicon.remove_word("kyutai"));
+        assert!(!lexicon.remove_word("kyutai"));
+        assert!(lexicon.remove_pattern(r"#(\d+)"));
+        assert_eq!(lexicon.apply("Kyutai #42"), "Kyutai #42");
+    }
+
+    #[test]
+    fn test_invalid_entries_are_rejected() {
+        let mut lexicon = Lexicon::new();
+        assert!(lexicon.insert_pattern("(unclosed", "x").is_err());
+        assert!(lexicon.insert_word("  ", "x").is_err());
+        assert!(Lexicon::from_toml("[[patterns]]\npattern = '('\nrespelling = ''").is_err());
+        assert!(lexicon.is_empty());
+    }
+}
@@ -1,18 +1,48 @@
 pub mod audio;
+pub mod codec;
 pub mod conditioners;
 pub mod config;
+pub mod generation;
+pub mod lexicon;
+pub mod markdown;
 pub mod models;
 pub mod modules;
 pub mod pause;
 pub mod quantize;
+pub mod sentence;
+pub mod ssml;
+pub mod text_coverage;
+pub mod text_normalize;
 pub mod tts_model;
+pub mod voice;
+pub mod voice_analysis;
+pub mod voice_blend;
+pub mod voice_clips;
+pub mod voice_preprocess;
+pub mod voice_prompt;
 pub mod voice_state;
 pub mod weights;
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
-pub use pause::{ParsedText, PauseMarker, parse_text_with_pauses};
+pub use codec::{EncodedAudio, LatentPrecision};
+pub use generation::{
+    CandidateScore, GenerationEvent, GenerationEventKind, GenerationHandle, GenerationOptions,
+    GenerationProgress, InputFormat,
+};
+pub use lexicon::Lexicon;
+pub use pause::{ParsedText, PauseConfig, PauseMarker, parse_text_with_pauses};
 pub use quantize::{QuantizeConfig, QuantizedTensor};
+pub use sentence::{DefaultSentenceSplitter, SentenceSplitter};
+pub use text_coverage::{TextAnalysis, UnsupportedCharPolicy};
 pub use tts_model::TTSModel;
+pub use voice::{VoiceMarker, VoiceResolver, parse_voice_markers, strip_voice_markers};
+pub use voice_analysis::{
+    VoiceAnalysis, VoiceCheck, VoiceCheckStatus, analyze_voice_prompt, analyze_voice_prompt_bytes,
+};
+pub use voice_blend::blend_voices;
+pub use voice_clips::VoiceClipJoin;
+pub use voice_preprocess::VoicePreprocessing;
+pub use voice_prompt::{VoicePrompt, VoicePromptFormat, VoicePromptMetadata};
 pub use voice_state::ModelState;
@@ -0,0 +1,591 @@
+//! Markdown input for long-form narration
+//!
+//! Turns a Markdown document into speakable text instead of reading out its
+//! syntax:
+//! - Headings become their own sentence with a longer pause around them
+//! - List items and table rows become separate sentences
+//! - Links and images keep their text; URLs are dropped
+//! - Emphasis, inline code backticks, HTML tags and escapes are removed
+//! - Fenced and indented code blocks are skipped, or replaced by
+//!   [`MarkdownOptions::code_block_phrase`]
+//! - Front matter, HTML comments and link reference definitions are skipped
+//!
+//! Paragraphs are separated by blank lines in the output text, so the
+//! paragraph pause of [`crate::PauseConfig`] applies between them.
+
+use crate::ssml::decode_entities;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+/// Default Markdown reading settings
+pub mod defaults {
+    /// Pause before and after a heading in milliseconds
+    pub const HEADING_PAUSE_MS: u32 = 700;
+}
+
+static ATX_HEADING_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}#{1,6}(?:[ \t]+(.*?))?(?:[ \t]+#+)?[ \t]*$").unwrap());
+
+static SETEXT_UNDERLINE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}(?:=+|-+)[ \t]*$").unwrap());
+
+static LIST_ITEM_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[ \t]*(?:[-*+]|\d{1,9}[.)])(?:[ \t]+(?:\[[ xX]\][ \t]+)?(.*))?$").unwrap()
+});
+
+static REFERENCE_DEFINITION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ {0,3}\[([^\]]+)\]:[ \t]*(.*)$").unwrap());
+
+static CODE_SPAN_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(`+)([^`]|[^`].*?[^`])(`+)").unwrap());
+
+static IMAGE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!\[([^\]]*)\](?:\([^)]*\)|\[[^\]]*\])").unwrap());
+
+static LINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]^][^\]]*)\](?:\([^)]*\)|\[[^\]]*\])").unwrap());
+
+static FOOTNOTE_REFERENCE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[\^[^\]]+\]").unwrap());
+
+static AUTOLINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"<(?:[A-Za-z][A-Za-z0-9+.-]*:[^<>\s]*|[^<>\s@]+@[^<>\s]+)>|https?://[^\s<>]*[^\s<>.,;:!?)\]'"]"#,
+    )
+    .unwrap()
+});
+
+static HTML_TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"</?[A-Za-z][^<>]*>|<!--.*?-->").unwrap());
+
+/// Emphasis markers, strongest first; `_` only counts outside words. Like
+/// CommonMark flanking rules, the text just inside the markers is neither
+/// whitespace nor the marker itself; see [`is_emphasis`] for digits
+static EMPHASIS_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        r"\*\*\*([^\s*](?:.*?[^\s*])?)\*\*\*",
+        r"\*\*([^\s*](?:.*?[^\s*])?)\*\*",
+        r"\*([^\s*](?:[^*]*?[^\s*])?)\*",
+        r"\b___([^\s_](?:.*?[^\s_])?)___\b",
+        r"\b__([^\s_](?:.*?[^\s_])?)__\b",
+        r"\b_([^\s_](?:[^_]*?[^\s_])?)_\b",
+        r"~~([^\s~](?:.*?[^\s~])?)~~",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+static WHITESPACE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+/// Space left before punctuation by a removed URL or tag
+static SPACE_BEFORE_PUNCTUATION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" ([,.;:!?])( |$)").unwrap());
+
+/// Characters Markdown lets you escape with a backslash
+const ESCAPABLE: &str = "\\`*_{}[]()#+-.!|<>~\"'";
+
+/// Escaped characters are parked as Unicode noncharacters (one per entry of
+/// [`ESCAPABLE`]) while the inline markup is removed. Noncharacters are never
+/// text, so any in the input are dropped first and cannot be confused with a
+/// parked escape
+const ESCAPE_OFFSET: u32 = 0xFDD0;
+
+/// How Markdown input is read
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkdownOptions {
+    /// Pause before and after each heading in milliseconds
+    pub heading_pause_ms: u32,
+    /// Sentence spoken in place of each code block; `None` skips code blocks
+    pub code_block_phrase: Option<String>,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            heading_pause_ms: defaults::HEADING_PAUSE_MS,
+            code_block_phrase: None,
+        }
+    }
+}
+
+/// A piece of a Markdown document, in reading order
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkdownItem {
+    /// Plain text; blank lines separate paragraphs
+    Text(String),
+    /// Silence in milliseconds
+    Break(u32),
+}
+
+/// Convert Markdown into speakable text and heading pauses
+///
+/// ```
+/// use pocket_tts::markdown::{MarkdownItem, MarkdownOptions, parse_markdown};
+///
+/// let items = parse_markdown(
+///     "# Release notes\n\n- Faster *startup*\n- See [the docs](https://example.com)",
+///     &MarkdownOptions::default(),
+/// );
+/// assert_eq!(
+///     items,
+///     vec![
+///         MarkdownItem::Text("Release notes.".to_string()),
+///         MarkdownItem::Break(700),
+///         MarkdownItem::Text("Faster startup.\nSee the docs.".to_string()),
+///     ]
+/// );
+/// ```
+pub fn parse_markdown(markdown: &str, options: &MarkdownOptions) -> Vec<MarkdownItem> {
+    let markdown = markdown.replace("\r\n", "\n");
+    let mut lines: Vec<&str> = markdown.lines().collect();
+    skip_front_matter(&mut lines);
+
+    let mut reader = Reader::new(options);
+    let mut i = 0;
+    while i < lines.len() {
+        let line = strip_blockquote(lines[i]);
+        i += 1;
+
+        if let Some(fence) = code_fence(line) {
+            while i < lines.len() {
+                let closing = strip_blockquote(lines[i]).trim();
+                i += 1;
+                if closing.len() >= fence.len() && closing.chars().all(|c| fence.starts_with(c)) {
+                    break;
+                }
+            }
+            reader.code_block();
+        } else if line.trim().is_empty() {
+            reader.end_paragraph();
+        } else if line.trim_start().starts_with("<!--") {
+            let mut comment = line.to_string();
+            while !comment.contains("-->") && i < lines.len() {
+                comment = lines[i].to_string();
+                i += 1;
+            }
+            reader.end_paragraph();
+        } else if let Some(caps) = ATX_HEADING_REGEX.captures(line) {
+            reader.heading(caps.get(1).map_or("", |m| m.as_str()));
+        } else if reader.has_paragraph() && SETEXT_UNDERLINE_REGEX.is_match(line) {
+            let text = std::mem::take(&mut reader.paragraph).join(" ");
+            reader.heading(&text);
+        } else if is_thematic_break(line) {
+            reader.end_paragraph();
+        } else if is_indented_code(line) && !reader.has_paragraph() && !reader.in_list {
+            while i < lines.len() {
+                let next = strip_blockquote(lines[i]);
+                if !next.trim().is_empty() && !is_indented_code(next) {
+                    break;
+                }
+                i += 1;
+            }
+            reader.code_block();
+        } else if let Some(caps) = LIST_ITEM_REGEX.captures(line) {
+            reader.end_paragraph();
+            reader.in_list = true;
+            reader.list_item = true;
+            reader
+                .paragraph
+                .push(caps.get(1).map_or("", |m| m.as_str()).to_string());
+        } else if let Some(row) = table_row(line) {
+            reader.end_paragraph();
+            if let Some(row) = row {
+                reader.list_item = true;
+                reader.paragraph.push(row);
+                reader.end_paragraph();
+            }
+        } else if let Some(caps) = REFERENCE_DEFINITION_REGEX.captures(line) {
+            // Footnote definitions are read as a paragraph, link targets skipped
+            reader.end_paragraph();
+            if caps[1].starts_with('^') {
+                reader.paragraph.push(caps[2].to_string());
+            }
+        } else {
+            if !reader.has_paragraph() && !line.starts_with([' ', '\t']) {
+                reader.in_list = false;
+            }
+            reader.paragraph.push(line.trim().to_string());
+        }
+    }
+    reader.finish()
+}
+
+/// Accumulates paragraphs into text items
+struct Reader<'a> {
+    options: &'a MarkdownOptions,
+    items: Vec<MarkdownItem>,
+    /// Text since the last heading
+    text: String,
+    /// Lines of the paragraph being read
+    paragraph: Vec<String>,
+    /// The current paragraph is a list item or table row
+    list_item: bool,
+    /// The previous sentence in `text` was a list item or table row
+    last_was_list_item: bool,
+    /// Indented lines continue a list instead of starting a code block
+    in_list: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(options: &'a MarkdownOptions) -> Self {
+        Self {
+            options,
+            items: Vec::new(),
+            text: String::new(),
+            paragraph: Vec::new(),
+            list_item: false,
+            last_was_list_item: false,
+            in_list: false,
+        }
+    }
+
+    fn has_paragraph(&self) -> bool {
+        !self.paragraph.is_empty()
+    }
+
+    fn end_paragraph(&mut self) {
+        let list_item = std::mem::take(&mut self.list_item);
+        let lines = std::mem::take(&mut self.paragraph);
+        if let Some(sentence) = sentence(&lines.join(" ")) {
+            self.push_sentence(sentence, list_item);
+        }
+    }
+
+    /// Append a sentence; consecutive list items stay in one paragraph
+    fn push_sentence(&mut self, sentence: String, list_item: bool) {
+        if !self.text.is_empty() {
+            self.text.push_str(if list_item && self.last_was_list_item {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        self.text.push_str(&sentence);
+        self.last_was_list_item = list_item;
+    }
+
+    fn code_block(&mut self) {
+        self.end_paragraph();
+        if let Some(phrase) = self.options.code_block_phrase.as_deref().and_then(sentence) {
+            self.push_sentence(phrase, false);
+        }
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.end_paragraph();
+        let Some(heading) = sentence(text) else {
+            return;
+        };
+        let had_content = !self.items.is_empty() || !self.text.is_empty();
+        self.flush_text();
+        if had_content {
+            self.pause(self.options.heading_pause_ms);
+        }
+        self.items.push(MarkdownItem::Text(heading));
+        self.pause(self.options.heading_pause_ms);
+        self.in_list = false;
+    }
+
+    fn pause(&mut self, ms: u32) {
+        if ms == 0 {
+            return;
+        }
+        match self.items.last_mut() {
+            Some(MarkdownItem::Break(previous)) => *previous = (*previous).max(ms),
+            _ => self.items.push(MarkdownItem::Break(ms)),
+        }
+    }
+
+    fn flush_text(&mut self) {
+        if !self.text.is_empty() {
+            self.items
+                .push(MarkdownItem::Text(std::mem::take(&mut self.text)));
+        }
+        self.last_was_list_item = false;
+    }
+
+    fn finish(mut self) -> Vec<MarkdownItem> {
+        self.end_paragraph();
+        self.flush_text();
+        if let Some(MarkdownItem::Break(_)) = self.items.last() {
+            self.items.pop();
+        }
+        self.items
+    }
+}
+
+/// Drop a leading `---` ... `---` (or `...`) YAML front matter block
+fn skip_front_matter(lines: &mut Vec<&str>) {
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return;
+    }
+    if let Some(end) = lines
+        .iter()
+        .skip(1)
+        .position(|l| matches!(l.trim_end(), "---" | "..."))
+    {
+        lines.drain(..end + 2);
+    }
+}
+
+fn strip_blockquote(mut line: &str) -> &str {
+    while let Some(rest) = line.trim_start().strip_prefix('>') {
+        line = rest.strip_prefix(' ').unwrap_or(rest);
+    }
+    line
+}
+
+/// The opening fence of a fenced code block, e.g. "```" or "~~~~"
+fn code_fence(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+    (len >= 3).then(|| &trimmed[..len])
+}
+
+fn is_indented_code(line: &str) -> bool {
+    line.starts_with("    ") || line.starts_with('\t')
+}
+
+/// `---`, `***` or `___`, optionally spaced
+fn is_thematic_break(line: &str) -> bool {
+    let marks: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    marks.len() >= 3 && matches!(marks[0], '-' | '*' | '_') && marks.iter().all(|&c| c == marks[0])
+}
+
+/// A table row: `Some(Some(cells))` for content, `Some(None)` for the
+/// `|---|:---:|` delimiter row, `None` for non-table lines
+fn table_row(line: &str) -> Option<Option<String>> {
+    let trimmed = line.trim();
+    if !trimmed.starts_with('|') {
+        return None;
+    }
+    if trimmed
+        .chars()
+        .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+    {
+        return Some(None);
+    }
+    let cells: Vec<&str> = trimmed
+        .trim_matches('|')
+        .split('|')
+        .map(str::trim)
+        .filter(|cell| !cell.is_empty())
+        .collect();
+    Some((!cells.is_empty()).then(|| cells.join(", ")))
+}
+
+/// Clean inline markup and make sure the text ends like a sentence
+fn sentence(text: &str) -> Option<String> {
+    let text = inline_text(text);
+    let text = text.trim();
+    if !text.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    let ends_sentence = text
+        .trim_end_matches(['"', '\'', ')', ']', '”', '’'])
+        .ends_with(['.', '!', '?', ':', ';', '…']);
+    Some(if ends_sentence {
+        text.to_string()
+    } else {
+        format!("{}.", text)
+    })
+}
+
+/// Remove inline Markdown and HTML markup, keeping the readable text
+fn inline_text(text: &str) -> String {
+    let text = park_escapes(text);
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in CODE_SPAN_REGEX.captures_iter(&text) {
+        let span = caps.get(0).unwrap();
+        if caps[1].len() != caps[3].len() {
+            continue;
+        }
+        out.push_str(&clean_markup(&text[last..span.start()]));
+        out.push_str(caps[2].trim());
+        last = span.end();
+    }
+    out.push_str(&clean_markup(&text[last..]));
+    let out = unpark_escapes(&decode_entities(&out));
+    let out = WHITESPACE_REGEX.replace_all(&out, " ");
+    SPACE_BEFORE_PUNCTUATION_REGEX
+        .replace_all(&out, "$1$2")
+        .into_owned()
+}
+
+fn clean_markup(text: &str) -> String {
+    let text = IMAGE_REGEX.replace_all(text, "$1");
+    let text = LINK_REGEX.replace_all(&text, "$1");
+    let text = FOOTNOTE_REFERENCE_REGEX.replace_all(&text, "");
+    let text = AUTOLINK_REGEX.replace_all(&text, "");
+    let mut text = HTML_TAG_REGEX.replace_all(&text, "").into_owned();
+    for regex in EMPHASIS_REGEXES.iter() {
+        text = regex
+            .replace_all(&text, |caps: &Captures| {
+                if is_emphasis(&text, caps) {
+                    caps[1].to_string()
+                } else {
+                    caps[0].to_string()
+                }
+            })
+            .into_owned();
+    }
+    text
+}
+
+/// A marker next to a digit inside only counts when it is not glued to a
+/// letter or digit outside, so "2*3*4" stays arithmetic
+fn is_emphasis(text: &str, caps: &Captures) -> bool {
+    let (span, inner) = (caps.get(0).unwrap(), &caps[1]);
+    let glued = |outside: Option<char>, inside: Option<char>| {
+        outside.is_some_and(char::is_alphanumeric) && inside.is_some_and(|c| c.is_ascii_digit())
+    };
+    !glued(
+        text[..span.start()].chars().next_back(),
+        inner.chars().next(),
+    ) && !glued(text[span.end()..].chars().next(), inner.chars().next_back())
+}
+
+fn is_parked(c: char) -> bool {
+    (ESCAPE_OFFSET..ESCAPE_OFFSET + ESCAPABLE.len() as u32).contains(&(c as u32))
+}
+
+fn park_escapes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().filter(|&c| !is_parked(c)).peekable();
+    while let Some(c) = chars.next() {
+        let escaped = chars
+            .peek()
+            .filter(|_| c == '\\')
+            .and_then(|&next| ESCAPABLE.find(next));
+        match escaped {
+            Some(index) => {
+                chars.next();
+                out.push(char::from_u32(ESCAPE_OFFSET + index as u32).unwrap());
+            }
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+fn unpark_escapes(text: &str) -> String {
+    text.chars()
+        .map(|c| match is_parked(c) {
+            true => ESCAPABLE.as_bytes()[(c as u32 - ESCAPE_OFFSET) as usize] as char,
+            false => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(markdown: &str) -> String {
+        parse_markdown(markdown, &MarkdownOptions::default())
+            .into_iter()
+            .filter_map(|item| match item {
+                MarkdownItem::Text(text) => Some(text),
+                MarkdownItem::Break(_) => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    #[test]
+    fn test_headings_get_pauses() {
+        let items = parse_markdown(
+            "Intro text\n\n## Setup ##\n\nRun it.\n\nUsage\n=====\nDone",
+            &MarkdownOptions::default(),
+        );
+        assert_eq!(
+            items,
+            vec![
+                MarkdownItem::Text("Intro text.".to_string()),
+                MarkdownItem::Break(700),
+                MarkdownItem::Text("Setup.".to_string()),
+                MarkdownItem::Break(700),
+                MarkdownItem::Text("Run it.".to_string()),
+                MarkdownItem::Break(700),
+                MarkdownItem::Text("Usage.".to_string()),
+                MarkdownItem::Break(700),
+                MarkdownItem::Text("Done.".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lists_and_paragraphs() {
+        assert_eq!(
+            text(
+                "Changes:\n\n- Added **bold** support\n  across lines\n* [x] Fixed a bug!\n1. Third\n\nA paragraph\nwrapped here."
+            ),
+            "Changes:\n\nAdded bold support across lines.\nFixed a bug!\nThird.\n\nA paragraph wrapped here."
+        );
+    }
+
+    #[test]
+    fn test_links_images_and_urls() {
+        assert_eq!(
+            text(
+                "See [the guide](https://x.dev/guide \"Guide\") and ![a diagram](d.png), \
+                 or visit <https://x.dev> https://x.dev/raw.[^1]\n\n[guide]: https://x.dev\n[^1]: A footnote"
+            ),
+            "See the guide and a diagram, or visit.\n\nA footnote."
+        );
+        assert_eq!(text("Read [the docs][guide]."), "Read the docs.");
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let markdown = "Install:\n\n```bash\ncargo install # not a heading\n```\n\n    indented code\n\nThen run `pocket-tts`.";
+        assert_eq!(text(markdown), "Install:\n\nThen run pocket-tts.");
+
+        let options = MarkdownOptions {
+            code_block_phrase: Some("Code sample omitted".to_string()),
+            ..MarkdownOptions::default()
+        };
+        let items = parse_markdown(markdown, &options);
+        assert_eq!(
+            items,
+            vec![MarkdownItem::Text(
+                "Install:\n\nCode sample omitted.\n\nCode sample omitted.\n\nThen run pocket-tts."
+                    .to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_emphasis_needs_flanking_text() {
+        assert_eq!(inline_text("2*3*4 and 2 * 3 * 4"), "2*3*4 and 2 * 3 * 4");
+        assert_eq!(inline_text("** not bold **"), "** not bold **");
+        assert_eq!(inline_text("* not a list item *"), "* not a list item *");
+        assert_eq!(
+            inline_text("**2024** was *very* good"),
+            "2024 was very good"
+        );
+    }
+
+    #[test]
+    fn test_private_use_text_is_kept() {
+        let text = "\u{F002A} stays, \\* is escaped, \u{FDD0} is dropped";
+        assert_eq!(
+            inline_text(text),
+            "\u{F002A} stays, * is escaped, is dropped"
+        );
+    }
+
+    #[test]
+    fn test_inline_markup_tables_and_front_matter() {
+        let markdown = "---\ntitle: Notes\n---\n> Quoted _emphasis_ and snake_case \\*stars\\* &amp; <b>tags</b>\n\n<!-- hidden\ncomment -->\n| Name | Value |\n|------|:-----:|\n| speed | 1.5 |\n\n***\n~~Old~~ text";
+        assert_eq!(
+            text(markdown),
+            "Quoted emphasis and snake_case *stars* & tags.\n\nName, Value.\nspeed, 1.5.\n\nOld text."
+        );
+    }
+}
@@ -3,6 +3,7 @@ use crate::models::transformer::StreamingTransformer;
 use crate::modules::mlp::{LayerNorm, ModulationParams, SimpleMLPAdaLN};
 use candle_core::{Result, Tensor};
 use candle_nn::{Linear, Module, VarBuilder};
+use rand::rngs::StdRng;
 
 pub fn lsd_decode(
     flow_net: &SimpleMLPAdaLN,
@@ -33,35 +34,38 @@ pub struct FlowLMModel {
     pub emb_std: Tensor,
     pub ldim: usize,
     pub dim: usize,
-    pub noise_clamp: Option<f32>,
 }
 
 fn sample_noise(
+    rng: &mut StdRng,
     device: &candle_core::Device,
     shape: (usize, usize),
     temp: f32,
     clamp: Option<f32>,
 ) -> Result<Tensor> {
     let std = temp.sqrt();
+    let count = shape.0 * shape.1;
+    let mut data = Vec::with_capacity(count);
+    let dist =
+        rand_distr::Normal::new(0.0f32, std).map_err(|e| candle_core::Error::Msg(e.to_string()))?;
+
     match clamp {
-        None => Tensor::randn(0.0f32, std, shape, device),
+        None => {
+            for _ in 0..count {
+                data.push(rand_distr::Distribution::sample(&dist, rng));
+            }
+        }
         Some(limit) => {
             // Rejection sampling for truncated normal
-            let count = shape.0 * shape.1;
-            let mut data = Vec::with_capacity(count);
-            let mut rng = rand::thread_rng();
-            let dist = rand_distr::Normal::new(0.0f32, std)
-                .map_err(|e| candle_core::Error::Msg(e.to_string()))?;
-
             while data.len() < count {
-                let v = rand_distr::Distribution::sample(&dist, &mut rng);
+                let v = rand_distr::Distribution::sample(&dist, rng);
                 if v.abs() <= limit {
                     data.push(v);
                 }
             }
-            Tensor::from_vec(data, shape, device)
         }
     }
+    Tensor::from_vec(data, shape, device)
 }
 
 impl FlowLMModel {
@@ -90,7 +94,6 @@ impl FlowLMModel {
             emb_std,
             ldim,
             dim,
-            noise_clamp: None, // Default to no clamp
         })
     }
 
@@ -103,8 +106,38 @@ impl FlowLMModel {
         time_embeddings: &Tensor,
         temp: f32,
         eos_threshold: f32,
+        noise_clamp: Option<f32>,
         step: usize,
+        rng: &mut StdRng,
     ) -> Result<(Tensor, bool)> {
+        let (next_latent, is_eos) = self.forward_batch(
+            sequence,
+            text_embeddings,
+            model_state,
+            time_embeddings,
+            temp,
+            eos_threshold,
+            noise_clamp,
+            step,
+            std::slice::from_mut(rng),
+        )?;
+        Ok((next_latent, is_eos[0]))
+    }
+
+    /// Batched variant of [`Self::forward`]: one RNG and one EOS flag per row.
+    #[allow(clippy::too_many_arguments)]
+    pub fn forward_batch(
+        &self,
+        sequence: &Tensor,
+        text_embeddings: &Tensor,
+        model_state: &mut ModelState,
+        time_embeddings: &Tensor,
+        temp: f32,
+        eos_threshold: f32,
+        noise_clamp: Option<f32>,
+        step: usize,
+        rngs: &mut [StdRng],
+    ) -> Result<(Tensor, Vec<bool>)> {
         // sequence is [B, T, ldim]
         // text_embeddings is [B, S, dim]
 
@@ -136,21 +169,30 @@ impl FlowLMModel {
             .narrow(1, transformer_out.dims()[1] - 1, 1)?
             .squeeze(1)?;
 
-        let eos_score = self
+        let batch = last_frame.dims()[0];
+        if rngs.len() != batch {
+            return Err(candle_core::Error::Msg(format!(
+                "expected {} RNGs for batch, got {}",
+                batch,
+                rngs.len()
+            )));
+        }
+
+        let is_eos = self
             .out_eos
             .forward(&last_frame)?
-            .squeeze(0)?
-            .squeeze(0)?
-            .to_scalar::<f32>()?;
-        let is_eos = eos_score > eos_threshold;
-
-        // Generate noise with optional clamping
-        let noise = sample_noise(
-            last_frame.device(),
-            (last_frame.dims()[0], self.ldim),
-            temp,
-            self.noise_clamp,
-        )?;
+            .flatten_all()?
+            .to_vec1::<f32>()?
+            .into_iter()
+            .map(|score| score > eos_threshold)
+            .collect();
+
+        // Generate noise with optional clamping, drawing each row from its own RNG
+        let rows = rngs
+            .iter_mut()
+            .map(|rng| sample_noise(rng, last_frame.device(), (1, self.ldim), temp, noise_clamp))
+            .collect::<Result<Vec<_>>>()?;
+        let noise = Tensor::cat(&rows, 0)?;
 
         // Pre-compute all modulations for this frame's ODE steps (8 steps * N blocks) in batch
         let c_emb = self.flow_net.embed_condition(&last_frame)?;
@@ -163,3 +205,33 @@ impl FlowLMModel {
         Ok((next_latent, is_eos))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::Device;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_sample_noise_is_reproducible_with_seed() -> Result<()> {
+        let mut rng_a = StdRng::seed_from_u64(7);
+        let mut rng_b = StdRng::seed_from_u64(7);
+
+        let a = sample_noise(&mut rng_a, &Device::Cpu, (2, 8), 0.7, None)?;
+        let b = sample_noise(&mut rng_b, &Device::Cpu, (2, 8), 0.7, None)?;
+
+        assert_eq!(a.to_vec2::<f32>()?, b.to_vec2::<f32>()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_noise_respects_clamp() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let noise = sample_noise(&mut rng, &Device::Cpu, (4, 32), 1.0, Some(0.5))?;
+
+        for v in noise.flatten_all()?.to_vec1::<f32>()? {
+            assert!(v.abs() <= 0.5);
+        }
+        Ok(())
+    }
+}
@@ -50,6 +50,7 @@ pub struct MimiModel {
     pub sample_rate: usize,
     pub channels: usize,
     pub dimension: usize,
+    pub output_dimension: usize,
 }
 
 impl MimiModel {
@@ -103,6 +104,7 @@ impl MimiModel {
             sample_rate,
             channels,
             dimension,
+            output_dimension,
         })
     }
 
@@ -259,4 +261,93 @@ mod tests {
         assert_eq!(mimi.frame_size(), 1280);
         Ok(())
     }
+
+    /// Codec files carry encoder latents, which are `output_dimension` wide
+    #[test]
+    fn test_codec_roundtrip_latent_dim() -> anyhow::Result<()> {
+        use crate::codec::{EncodedAudio, LatentPrecision};
+        use crate::voice_state::init_states;
+
+        let device = Device::Cpu;
+        let vb = VarBuilder::zeros(DType::F32, &device);
+        let encoder = SEANetEncoder::new(
+            1,
+            64,
+            16,
+            1,
+            &[2, 2],
+            7,
+            7,
+            3,
+            2,
+            "constant",
+            2,
+            "encoder",
+            vb.pp("encoder"),
+        )?;
+        let decoder = SEANetDecoder::new(
+            1,
+            64,
+            16,
+            1,
+            &[2, 2],
+            7,
+            7,
+            3,
+            2,
+            "constant",
+            2,
+            "decoder",
+            vb.pp("decoder"),
+        )?;
+        let transformer = |name: &str| {
+            ProjectedTransformer::new(
+                64,
+                vec![64],
+                64,
+                4,
+                1,
+                0.1,
+                10,
+                10000.0,
+                128,
+                name,
+                vb.pp(name),
+            )
+        };
+        let mimi = MimiModel::new(
+            encoder,
+            decoder,
+            transformer("enc_tr")?,
+            transformer("dec_tr")?,
+            2000.0,
+            4000.0,
+            16000,
+            1,
+            32,
+            64,
+            "mimi",
+            vb.pp("mimi"),
+        )?;
+
+        let audio = Tensor::zeros((1, 1, 80), DType::F32, &device)?;
+        let latents = mimi.encode_to_latent(&audio, &mut init_states(1, 1000), 0)?;
+        let encoded = EncodedAudio {
+            sample_rate: 16000,
+            frame_rate: 2000.0,
+            num_samples: 80,
+            latents: latents.squeeze(0)?.t()?.contiguous()?,
+        };
+        let bytes = encoded.to_bytes(LatentPrecision::I8)?;
+        let parsed = EncodedAudio::from_bytes(&bytes, &device)?;
+
+        assert_eq!(parsed.latent_dim(), mimi.output_dimension);
+        parsed.check_decodable(16000, mimi.output_dimension)?;
+        assert!(parsed.check_decodable(16000, mimi.dimension).is_err());
+
+        let latents = parsed.latents.t()?.unsqueeze(0)?.contiguous()?;
+        let decoded = mimi.decode_from_latent(&latents, &mut init_states(1, 1000), 0)?;
+        assert_eq!(decoded.dims(), &[1, 1, 80]);
+        Ok(())
+    }
 }
@@ -151,6 +151,31 @@ impl StreamingTransformer {
         }
         Ok(x)
     }
+
+    /// Combine per-utterance states (batch size 1 each) into one batched state.
+    ///
+    /// Rows are left-padded to the longest KV cache and padding is masked in
+    /// attention, so every row keeps attending only to its own prompt.
+    pub fn stack_states(
+        &self,
+        states: &[&ModelState],
+        device: &candle_core::Device,
+    ) -> Result<ModelState> {
+        let mut stacked = ModelState::new();
+        for (i, layer) in self.layers.iter().enumerate() {
+            let layer_name = format!("{}.layers.{}.self_attn", self.name, i);
+            let rows = states
+                .iter()
+                .map(|state| {
+                    state.get(&layer_name).ok_or_else(|| {
+                        candle_core::Error::Msg(format!("missing state for {}", layer_name))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            stacked.insert(layer_name, layer.self_attn.stack_states(&rows, device)?);
+        }
+        Ok(stacked)
+    }
 }
 
 #[derive(Clone)]
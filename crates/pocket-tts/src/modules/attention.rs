@@ -1,7 +1,7 @@
 use crate::ModelState;
 use crate::modules::rope::RotaryEmbedding;
 use crate::voice_state::{
-    ATTN_K_BUF_KEY, ATTN_LEN_KEY, ATTN_POS_KEY, ATTN_V_BUF_KEY, AttentionCursor,
+    ATTN_K_BUF_KEY, ATTN_LEN_KEY, ATTN_PAD_KEY, ATTN_POS_KEY, ATTN_V_BUF_KEY, AttentionCursor,
     read_attention_cursor, write_attention_cursor,
 };
 use candle_core::{DType, Result, Tensor};
@@ -101,6 +101,94 @@ impl StreamingMultiheadAttention {
         Ok(state)
     }
 
+    /// Stack single-row KV caches into one left-padded batched cache.
+    ///
+    /// Each row's keys are shifted (via RoPE) so that all rows share the same
+    /// position counter; the padding is recorded under [`ATTN_PAD_KEY`] and
+    /// masked out during attention. Only unwindowed (FlowLM) caches are supported.
+    pub fn stack_states(
+        &self,
+        rows: &[&HashMap<String, Tensor>],
+        device: &candle_core::Device,
+    ) -> Result<HashMap<String, Tensor>> {
+        if self.context.is_some() {
+            return Err(candle_core::Error::Msg(format!(
+                "{}: cannot stack windowed attention state",
+                self.name
+            )));
+        }
+
+        let mut cached = Vec::with_capacity(rows.len());
+        for row in rows {
+            if row.contains_key(ATTN_PAD_KEY) {
+                return Err(candle_core::Error::Msg(format!(
+                    "{}: state is already batched",
+                    self.name
+                )));
+            }
+            let cursor = read_attention_cursor(row);
+            let (k_buf, v_buf) = match (row.get(ATTN_K_BUF_KEY), row.get(ATTN_V_BUF_KEY)) {
+                (Some(k), Some(v)) if k.dim(0)? == 1 => (k, v),
+                _ => {
+                    return Err(candle_core::Error::Msg(format!(
+                        "{}: expected a single-row KV cache",
+                        self.name
+                    )));
+                }
+            };
+            let start = cursor.pos.checked_sub(cursor.len).ok_or_else(|| {
+                candle_core::Error::Msg(format!("{}: cache longer than position", self.name))
+            })?;
+            cached.push((
+                k_buf.narrow(2, 0, cursor.len)?,
+                v_buf.narrow(2, 0, cursor.len)?,
+                start,
+                cursor.len,
+            ));
+        }
+
+        let max_len = cached.iter().map(|(_, _, _, len)| *len).max().unwrap_or(0);
+        let max_start = cached
+            .iter()
+            .map(|(_, _, start, _)| *start)
+            .max()
+            .unwrap_or(0);
+        let d = self.embed_dim / self.num_heads;
+
+        let mut k_rows = Vec::with_capacity(cached.len());
+        let mut v_rows = Vec::with_capacity(cached.len());
+        let mut pads = Vec::with_capacity(cached.len());
+        for (k, v, start, len) in cached {
+            let pad = max_len - len;
+            // Row keys were rotated starting at `start`; realign them so slot `pad + j`
+            // sits at shared position `max_start + pad + j`.
+            let delta = max_start + pad - start;
+            let k = self
+                .rope
+                .shift(&k.transpose(1, 2)?, delta)?
+                .transpose(1, 2)?;
+            let zeros = Tensor::zeros((1, self.num_heads, pad, d), k.dtype(), device)?;
+            k_rows.push(Tensor::cat(&[&zeros, &k], 2)?);
+            v_rows.push(Tensor::cat(&[&zeros, &v], 2)?);
+            pads.push(pad as u32);
+        }
+
+        let mut state = HashMap::new();
+        state.insert(ATTN_K_BUF_KEY.to_string(), Tensor::cat(&k_rows, 0)?);
+        state.insert(ATTN_V_BUF_KEY.to_string(), Tensor::cat(&v_rows, 0)?);
+        state.insert(ATTN_PAD_KEY.to_string(), Tensor::new(pads, device)?);
+        write_attention_cursor(
+            &mut state,
+            AttentionCursor {
+                pos: max_start + max_len,
+                len: max_len,
+                head: 0,
+            },
+            device,
+        )?;
+        Ok(state)
+    }
+
     pub fn forward(
         &self,
         query: &Tensor,
@@ -126,6 +214,8 @@ impl StreamingMultiheadAttention {
             cursor.len = current_len;
         }
 
+        let key_pad = module_state.get(ATTN_PAD_KEY).cloned();
+
         let projected = self.in_proj.forward(query)?;
 
         // Reshape to (b, t, 3, h, d)
@@ -227,7 +317,10 @@ impl StreamingMultiheadAttention {
             let kc = k_buf.narrow(2, 0, cache_len)?;
             let vc = v_buf.narrow(2, 0, cache_len)?;
             let scale = 1.0 / (d as f64).sqrt();
-            crate::modules::sdpa::sdpa(&q, &kc, &vc, scale, true, self.context)?
+            match &key_pad {
+                Some(pad) => crate::modules::sdpa::sdpa_key_padded(&q, &kc, &vc, scale, true, pad)?,
+                None => crate::modules::sdpa::sdpa(&q, &kc, &vc, scale, true, self.context)?,
+            }
         };
 
         if let Some(window_size) = self.context {
@@ -282,3 +375,58 @@ impl StreamingMultiheadAttention {
         Ok(x)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::Device;
+    use candle_nn::VarMap;
+
+    fn decode_step(
+        attn: &StreamingMultiheadAttention,
+        x: &Tensor,
+        state: &mut ModelState,
+    ) -> Result<Tensor> {
+        let cursor = crate::voice_state::get_attention_cursor(state, "attn");
+        attn.forward(x, state, cursor.pos, cursor.len)
+    }
+
+    #[test]
+    fn test_stacked_states_match_single_rows() -> Result<()> {
+        let device = Device::Cpu;
+        let varmap = VarMap::new();
+        let vb = VarBuilder::from_varmap(&varmap, DType::F32, &device);
+        let rope = RotaryEmbedding::new(10000.0, 8, &device)?;
+        let attn = StreamingMultiheadAttention::new(32, 4, rope, None, "attn", vb)?;
+
+        // Two rows with different prefix lengths
+        let mut rows = Vec::new();
+        for prefix_len in [3usize, 6] {
+            let mut state = ModelState::new();
+            let prefix = Tensor::randn(0f32, 1.0, (1, prefix_len, 32), &device)?;
+            decode_step(&attn, &prefix, &mut state)?;
+            rows.push(state);
+        }
+
+        let step_input = Tensor::randn(0f32, 1.0, (2, 1, 32), &device)?;
+        let mut expected = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let mut state = row.clone();
+            expected.push(decode_step(
+                &attn,
+                &step_input.narrow(0, i, 1)?,
+                &mut state,
+            )?);
+        }
+        let expected = Tensor::cat(&expected, 0)?;
+
+        let row_states: Vec<&HashMap<String, Tensor>> = rows.iter().map(|s| &s["attn"]).collect();
+        let mut batched = ModelState::new();
+        batched.insert("attn".to_string(), attn.stack_states(&row_states, &device)?);
+        let actual = decode_step(&attn, &step_input, &mut batched)?;
+
+        let diff = (actual - expected)?.abs()?.max_all()?.to_scalar::<f32>()?;
+        assert!(diff < 1e-4, "batched decode diverged: diff={}", diff);
+        Ok(())
+    }
+}
@@ -324,9 +324,11 @@ impl SimpleMLPAdaLN {
         c_emb: &Tensor,
         time_embeddings: &Tensor,
     ) -> Result<Vec<Vec<ModulationParams>>> {
-        // c_emb: [1, 512], time_embeddings: [8, 512]
+        // c_emb: [B, 512], time_embeddings: [8, 512]
         let num_steps = time_embeddings.dim(0)?;
-        let y = time_embeddings.broadcast_add(c_emb)?; // [8, 512]
+        let y = time_embeddings
+            .unsqueeze(1)?
+            .broadcast_add(&c_emb.unsqueeze(0)?)?; // [8, B, 512]
         let y_silu = y.silu()?;
 
         let mut all_step_modulations =
@@ -334,11 +336,11 @@ impl SimpleMLPAdaLN {
 
         // ResBlocks
         for block in &self.res_blocks {
-            let mod_batch = block.ada_ln_lin.forward(&y_silu)?; // [8, 1536]
+            let mod_batch = block.ada_ln_lin.forward(&y_silu)?; // [8, B, 1536]
             let dim = mod_batch.dim(candle_core::D::Minus1)? / 3;
 
             for s in 0..num_steps {
-                let modulation = mod_batch.narrow(0, s, 1)?; // [1, 1536]
+                let modulation = mod_batch.get(s)?; // [B, 1536]
                 let shift = modulation.narrow(candle_core::D::Minus1, 0, dim)?;
                 let scale = modulation.narrow(candle_core::D::Minus1, dim, dim)?;
                 let gate = modulation.narrow(candle_core::D::Minus1, 2 * dim, dim)?;
@@ -351,10 +353,10 @@ impl SimpleMLPAdaLN {
         }
 
         // Final layer
-        let mod_batch = self.final_layer.ada_ln_lin.forward(&y_silu)?; // [8, 1024]
+        let mod_batch = self.final_layer.ada_ln_lin.forward(&y_silu)?; // [8, B, 1024]
         let dim = mod_batch.dim(candle_core::D::Minus1)? / 2;
         for s in 0..num_steps {
-            let modulation = mod_batch.narrow(0, s, 1)?; // [1, 1024]
+            let modulation = mod_batch.get(s)?; // [B, 1024]
             let shift = modulation.narrow(candle_core::D::Minus1, 0, dim)?;
             let scale = modulation.narrow(candle_core::D::Minus1, dim, dim)?;
             all_step_modulations[s].push(ModulationParams {
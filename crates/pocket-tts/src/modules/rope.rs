@@ -16,10 +16,27 @@ impl RotaryEmbedding {
     }
 
     pub fn forward(&self, q: &Tensor, k: &Tensor, offset: usize) -> Result<(Tensor, Tensor)> {
-        let (b, t, h, d_full) = q.dims4()?;
-        let (_bk, _tk, hk, _dk) = k.dims4()?;
-        let d = d_full / 2;
-        let dev = q.device();
+        let t = q.dims4()?.1;
+        let (cos, sin) = self.cos_sin(t, offset, q.device())?;
+        Ok((apply_rotary(q, &cos, &sin)?, apply_rotary(k, &cos, &sin)?))
+    }
+
+    /// Move already-rotated (B, T, H, D) keys `delta` positions later.
+    ///
+    /// Rotations compose, so applying the same extra angle to every frame is
+    /// equivalent to having rotated them at `position + delta` originally.
+    pub fn shift(&self, x: &Tensor, delta: usize) -> Result<Tensor> {
+        let (cos, sin) = self.cos_sin(1, delta, x.device())?;
+        apply_rotary(x, &cos, &sin)
+    }
+
+    fn cos_sin(
+        &self,
+        t: usize,
+        offset: usize,
+        dev: &candle_core::Device,
+    ) -> Result<(Tensor, Tensor)> {
+        let d = self.inv_freq.dim(0)?;
 
         // ts = (arange(T) + offset).view(-1, 1, 1)
         let ts = if t == 1 {
@@ -33,31 +50,25 @@ impl RotaryEmbedding {
 
         // freqs * ts -> shape (t, 1, d)
         let freqs_ts = self.inv_freq.reshape((1, 1, d))?.broadcast_mul(&ts)?;
-        let cos = freqs_ts.cos()?;
-        let sin = freqs_ts.sin()?;
-
-        // Reshape q and k to (b, t, h, d, 2)
-        let q = q.reshape((b, t, h, d, 2))?;
-        let k = k.reshape((b, t, hk, d, 2))?;
-
-        let qr = q.narrow(4, 0, 1)?.squeeze(4)?;
-        let qi = q.narrow(4, 1, 1)?.squeeze(4)?;
-        let kr = k.narrow(4, 0, 1)?.squeeze(4)?;
-        let ki = k.narrow(4, 1, 1)?.squeeze(4)?;
+        Ok((freqs_ts.cos()?, freqs_ts.sin()?))
+    }
+}
 
-        // qor = qr * cos - qi * sin
-        // qoi = qr * sin + qi * cos
-        let qor = (qr.broadcast_mul(&cos)? - qi.broadcast_mul(&sin)?)?;
-        let qoi = (qr.broadcast_mul(&sin)? + qi.broadcast_mul(&cos)?)?;
+fn apply_rotary(x: &Tensor, cos: &Tensor, sin: &Tensor) -> Result<Tensor> {
+    let (b, t, h, d_full) = x.dims4()?;
+    let d = d_full / 2;
 
-        let kor = (kr.broadcast_mul(&cos)? - ki.broadcast_mul(&sin)?)?;
-        let koi = (kr.broadcast_mul(&sin)? + ki.broadcast_mul(&cos)?)?;
+    // Reshape to (b, t, h, d, 2)
+    let x = x.reshape((b, t, h, d, 2))?;
+    let xr = x.narrow(4, 0, 1)?.squeeze(4)?;
+    let xi = x.narrow(4, 1, 1)?.squeeze(4)?;
 
-        let qo = Tensor::stack(&[qor, qoi], 4)?.reshape((b, t, h, d_full))?;
-        let ko = Tensor::stack(&[kor, koi], 4)?.reshape((b, t, hk, d_full))?;
+    // xor = xr * cos - xi * sin
+    // xoi = xr * sin + xi * cos
+    let xor = (xr.broadcast_mul(cos)? - xi.broadcast_mul(sin)?)?;
+    let xoi = (xr.broadcast_mul(sin)? + xi.broadcast_mul(cos)?)?;
 
-        Ok((qo, ko))
-    }
+    Tensor::stack(&[xor, xoi], 4)?.reshape((b, t, h, d_full))
 }
 
 #[cfg(test)]
@@ -76,4 +87,23 @@ mod tests {
         assert_eq!(ko.dims(), &[1, 10, 4, 32]);
         Ok(())
     }
+
+    #[test]
+    fn test_shift_matches_later_offset() -> Result<()> {
+        let device = Device::Cpu;
+        let x = Tensor::randn(0f32, 1.0, (2, 3, 4, 32), &device)?;
+        let rope = RotaryEmbedding::new(10000.0, 32, &device)?;
+
+        let (_, at_5) = rope.forward(&x, &x, 5)?;
+        let (_, at_12) = rope.forward(&x, &x, 12)?;
+        let shifted = rope.shift(&at_5, 7)?;
+
+        let diff = (shifted - at_12)?.abs()?.max_all()?.to_scalar::<f32>()?;
+        assert!(
+            diff < 1e-4,
+            "shift should match rotating later: diff={}",
+            diff
+        );
+        Ok(())
+    }
 }
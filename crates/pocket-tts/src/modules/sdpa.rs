@@ -170,6 +170,55 @@ fn generate_mask_chunk(
     mask.reshape((1, 1, num_q, k_len))
 }
 
+/// SDPA for batches whose rows are left-padded to a common KV length.
+///
+/// `key_pad` is a `[Batch]` u32 tensor giving the number of leading padding
+/// slots in each row; those keys are masked out in addition to the causal mask.
+/// Uses the naive (untiled) path, which is what batched decoding needs.
+pub fn sdpa_key_padded(
+    q: &Tensor,
+    k: &Tensor,
+    v: &Tensor,
+    scale: f64,
+    is_causal: bool,
+    key_pad: &Tensor,
+) -> Result<Tensor> {
+    let q = q.contiguous()?;
+    let v = v.contiguous()?;
+    let (b, _h, q_len, _dim) = q.dims4()?;
+    let kv_len = k.dims()[2];
+
+    let k_t = k.transpose(2, 3)?.contiguous()?;
+    let mut scores = (q.matmul(&k_t)? * scale)?;
+
+    if is_causal && !can_skip_mask_for_single_query(q_len, kv_len, is_causal, None) {
+        let mask = generate_mask_chunk(0, q_len, kv_len, q_len, is_causal, None, q.device())?;
+        scores = scores.broadcast_add(&mask)?;
+    }
+    scores = scores.broadcast_add(&key_padding_mask(key_pad, b, kv_len)?)?;
+
+    let probs = candle_nn::ops::softmax(&scores, D::Minus1)?;
+    probs.matmul(&v)
+}
+
+/// Additive `[Batch, 1, 1, KV_Len]` mask hiding each row's leading padding slots
+fn key_padding_mask(key_pad: &Tensor, batch: usize, k_len: usize) -> Result<Tensor> {
+    let device = key_pad.device();
+    let pad = key_pad
+        .to_dtype(candle_core::DType::F32)?
+        .reshape((batch, 1))?;
+    let pos_k = Tensor::arange(0u32, k_len as u32, device)?
+        .to_dtype(candle_core::DType::F32)?
+        .reshape((1, k_len))?;
+
+    let is_pad = pos_k.broadcast_lt(&pad)?;
+    let mask = is_pad.where_cond(
+        &Tensor::full(f32::NEG_INFINITY, (batch, k_len), device)?,
+        &Tensor::zeros((batch, k_len), candle_core::DType::F32, device)?,
+    )?;
+    mask.reshape((batch, 1, 1, k_len))
+}
+
 /// Chunked version of SDPA that accepts a list of Key/Value pointers
 /// to avoid concatenating the full KV cache.
 pub fn sdpa_chunked(
@@ -335,6 +384,18 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_key_padding_mask() -> Result<()> {
+        let device = Device::Cpu;
+        let pad = Tensor::new(&[0u32, 2], &device)?;
+        let mask = key_padding_mask(&pad, 2, 3)?
+            .reshape((2, 3))?
+            .to_vec2::<f32>()?;
+        assert_eq!(mask[0], vec![0.0, 0.0, 0.0]);
+        assert_eq!(mask[1], vec![f32::NEG_INFINITY, f32::NEG_INFINITY, 0.0]);
+        Ok(())
+    }
+
     #[test]
     fn test_can_skip_mask_for_single_query() {
         assert!(can_skip_mask_for_single_query(1, 64, true, None));
@@ -2,9 +2,12 @@
 //!
 //! Supports:
 //! - Explicit pause markers: `[pause:Xms]` or `[pause:Xs]`
-//! - Natural pauses from punctuation: `...`, `,`
+//! - Natural pauses from punctuation: `...`, `,`, `;`, `:`, sentence ends and
+//!   blank lines, with durations set by [`PauseConfig`]
 
+use crate::text_normalize::is_abbreviation_period;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::sync::LazyLock;
 
 /// Pause marker found in text
@@ -28,6 +31,117 @@ pub mod defaults {
     pub const PERIOD_MS: u32 = 400;
     /// Semicolon pause duration
     pub const SEMICOLON_MS: u32 = 300;
+    /// Colon pause duration
+    pub const COLON_MS: u32 = 300;
+    /// Paragraph break (blank line) pause duration
+    pub const PARAGRAPH_MS: u32 = 600;
+    /// Largest accepted [`super::PauseConfig::scale`]
+    pub const MAX_SCALE: f32 = 10.0;
+    /// Longest explicit pause (`[pause:...]` or SSML `<break>`); longer
+    /// requests are cut to this
+    pub const MAX_PAUSE_MS: u32 = 10_000;
+}
+
+/// Durations of the natural pauses inserted at punctuation
+///
+/// A duration of 0 turns that class off. The default keeps the pauses the
+/// pipeline has always inserted (ellipses and commas);
+/// [`PauseConfig::punctuated`] also pauses at sentence ends, semicolons,
+/// colons and blank lines. Explicit `[pause:...]` markers are never scaled
+/// or disabled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PauseConfig {
+    /// Insert natural pauses at all
+    pub enabled: bool,
+    /// Multiplier applied to every natural pause duration
+    pub scale: f32,
+    /// Pause after `,` (not inside numbers like "1,000")
+    pub comma_ms: u32,
+    /// Pause after `;`
+    pub semicolon_ms: u32,
+    /// Pause after `:` followed by a space (not in times like "5:30")
+    pub colon_ms: u32,
+    /// Pause after `.`, `!` or `?` followed by a new sentence
+    pub sentence_end_ms: u32,
+    /// Pause at a blank line
+    pub paragraph_ms: u32,
+    /// Pause at `...`
+    pub ellipsis_ms: u32,
+}
+
+impl Default for PauseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            scale: 1.0,
+            comma_ms: defaults::COMMA_MS,
+            semicolon_ms: 0,
+            colon_ms: 0,
+            sentence_end_ms: 0,
+            paragraph_ms: 0,
+            ellipsis_ms: defaults::ELLIPSIS_MS,
+        }
+    }
+}
+
+impl PauseConfig {
+    /// Pause at every punctuation class with the [`defaults`] durations
+    pub fn punctuated() -> Self {
+        Self {
+            semicolon_ms: defaults::SEMICOLON_MS,
+            colon_ms: defaults::COLON_MS,
+            sentence_end_ms: defaults::PERIOD_MS,
+            paragraph_ms: defaults::PARAGRAPH_MS,
+            ..Self::default()
+        }
+    }
+
+    /// No natural pauses; explicit markers still apply
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    /// Check that the scale and every class duration are usable
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(0.0..=defaults::MAX_SCALE).contains(&self.scale) {
+            anyhow::bail!(
+                "pause scale must be between 0 and {}, got {}",
+                defaults::MAX_SCALE,
+                self.scale
+            );
+        }
+        for (name, ms) in [
+            ("comma_ms", self.comma_ms),
+            ("semicolon_ms", self.semicolon_ms),
+            ("colon_ms", self.colon_ms),
+            ("sentence_end_ms", self.sentence_end_ms),
+            ("paragraph_ms", self.paragraph_ms),
+            ("ellipsis_ms", self.ellipsis_ms),
+        ] {
+            if ms > defaults::MAX_PAUSE_MS {
+                anyhow::bail!(
+                    "{} must be at most {}, got {}",
+                    name,
+                    defaults::MAX_PAUSE_MS,
+                    ms
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Scaled duration of a pause class, or `None` if it is off; never longer
+    /// than [`defaults::MAX_PAUSE_MS`]
+    fn duration(&self, ms: u32) -> Option<u32> {
+        let scaled = (ms as f32 * self.scale)
+            .round()
+            .min(defaults::MAX_PAUSE_MS as f32) as u32;
+        (self.enabled && scaled > 0).then_some(scaled)
+    }
 }
 
 // Regex patterns for pause parsing
@@ -38,6 +152,13 @@ static EXPLICIT_PAUSE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
 
 static ELLIPSIS_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\.{3,}").unwrap());
 
+static PARAGRAPH_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[ \t]*\r?\n[ \t]*\r?\n\s*").unwrap());
+
+// Sentence-final punctuation, closing quotes or brackets, and the whitespace after
+static SENTENCE_END_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([.!?]+)["')\]]*(\s+)"#).unwrap());
+
 /// Parse explicit pause markers from text
 ///
 /// # Example
@@ -58,10 +179,18 @@ pub fn parse_explicit_pauses(text: &str) -> Vec<PauseMarker> {
             let unit = cap.get(2)?.as_str();
 
             let duration_ms = match unit {
-                "ms" => value as u32,
-                "s" => (value * 1000.0) as u32,
+                "ms" => value,
+                "s" => value * 1000.0,
                 _ => return None,
             };
+            if duration_ms > defaults::MAX_PAUSE_MS as f64 {
+                tracing::warn!(
+                    "{} is longer than {} ms and was shortened",
+                    full_match.as_str(),
+                    defaults::MAX_PAUSE_MS
+                );
+            }
+            let duration_ms = duration_ms.min(defaults::MAX_PAUSE_MS as f64) as u32;
 
             Some(PauseMarker {
                 original: full_match.as_str().to_string(),
@@ -72,40 +201,85 @@ pub fn parse_explicit_pauses(text: &str) -> Vec<PauseMarker> {
         .collect()
 }
 
-/// Parse natural pauses from punctuation
+/// Parse natural pauses from punctuation with the default [`PauseConfig`]
 pub fn parse_natural_pauses(text: &str) -> Vec<PauseMarker> {
+    parse_natural_pauses_with_config(text, &PauseConfig::default())
+}
+
+/// Parse natural pauses from punctuation
+///
+/// Comma, semicolon, colon and ellipsis markers cover the punctuation itself,
+/// so it is dropped from the surrounding text. Sentence-end and paragraph
+/// markers cover the whitespace after the sentence, so `?` and `!` stay with
+/// the sentence they end.
+pub fn parse_natural_pauses_with_config(text: &str, config: &PauseConfig) -> Vec<PauseMarker> {
     let mut pauses = Vec::new();
+    let mut push = |original: &str, ms: u32, position: usize| {
+        if let Some(duration_ms) = config.duration(ms) {
+            pauses.push(PauseMarker {
+                original: original.to_string(),
+                duration_ms,
+                position,
+            });
+        }
+    };
 
     // Find ellipses
     for cap in ELLIPSIS_REGEX.find_iter(text) {
-        pauses.push(PauseMarker {
-            original: cap.as_str().to_string(),
-            duration_ms: defaults::ELLIPSIS_MS,
-            position: cap.start(),
-        });
+        push(cap.as_str(), config.ellipsis_ms, cap.start());
     }
 
-    // Find commas (but not inside numbers like "1,000")
+    // Find commas (but not inside numbers like "1,000"), semicolons and
+    // colons followed by a space (not "5:30" or "http://")
     for (i, c) in text.char_indices() {
-        if c == ',' {
-            // Check if it's not surrounded by digits
-            let prev_is_digit =
-                i > 0 && text[..i].chars().last().is_some_and(|c| c.is_ascii_digit());
-            let next_is_digit = text[(i + 1)..]
-                .chars()
-                .next()
-                .is_some_and(|c| c.is_ascii_digit());
-
-            if !prev_is_digit || !next_is_digit {
-                pauses.push(PauseMarker {
-                    original: ",".to_string(),
-                    duration_ms: defaults::COMMA_MS,
-                    position: i,
-                });
-            }
+        let prev_is_digit = text[..i].chars().last().is_some_and(|c| c.is_ascii_digit());
+        let next = text[(i + 1)..].chars().next();
+        let next_is_digit = next.is_some_and(|c| c.is_ascii_digit());
+        match c {
+            ',' if !prev_is_digit || !next_is_digit => push(",", config.comma_ms, i),
+            ';' => push(";", config.semicolon_ms, i),
+            ':' if next.is_some_and(char::is_whitespace) => push(":", config.colon_ms, i),
+            _ => {}
         }
     }
 
+    // Blank lines
+    let paragraphs: Vec<_> = PARAGRAPH_REGEX
+        .find_iter(text)
+        .filter(|m| m.start() > 0 && m.end() < text.len())
+        .collect();
+    for m in &paragraphs {
+        push(m.as_str(), config.paragraph_ms, m.start());
+    }
+
+    // Sentence ends followed by another sentence, unless a blank line already
+    // pauses there or the period belongs to an abbreviation like "Dr."
+    for cap in SENTENCE_END_REGEX.captures_iter(text) {
+        let space = cap.get(2).unwrap();
+        let next = text[space.end()..].chars().next();
+        if cap[1].contains("..")
+            || next.is_none_or(char::is_lowercase)
+            || paragraphs
+                .iter()
+                .any(|p| p.start() < space.end() && space.start() < p.end())
+        {
+            continue;
+        }
+        let before = &text[..space.start()];
+        let word = before
+            .split_whitespace()
+            .last()
+            .unwrap_or("")
+            .trim_start_matches(['"', '\'', '(', '['])
+            .trim_end_matches(['"', '\'', ')', ']']);
+        if let Some(word) = word.strip_suffix('.')
+            && is_abbreviation_period(word)
+        {
+            continue;
+        }
+        push(space.as_str(), config.sentence_end_ms, space.start());
+    }
+
     // Sort by position
     pauses.sort_by_key(|p| p.position);
     pauses
@@ -127,47 +301,29 @@ pub struct ParsedText {
 
 /// Parse text for all pause markers (explicit and natural)
 pub fn parse_text_with_pauses(text: &str) -> ParsedText {
-    // First, find explicit pauses in original text
-    let mut all_pauses = parse_explicit_pauses(text);
+    parse_text_with_pauses_with_config(text, &PauseConfig::default())
+}
 
+/// Parse text for all pause markers, with natural pauses set by `config`
+pub fn parse_text_with_pauses_with_config(text: &str, config: &PauseConfig) -> ParsedText {
     // Strip explicit markers to get clean text
     let clean_text = strip_pause_markers(text);
 
     // Find natural pauses in clean text
-    let natural_pauses = parse_natural_pauses(&clean_text);
-
-    // Note: positions in all_pauses are relative to original text
-    // We need to adjust them to the clean text
-    // For simplicity, we'll recalculate based on clean text positions
+    let mut all_pauses = parse_natural_pauses_with_config(&clean_text, config);
 
-    // Clear and rebuild with correct positions
-    all_pauses.clear();
-    all_pauses.extend(natural_pauses);
-
-    // Re-parse explicit pauses and calculate where they would be in clean text
+    // Explicit pauses (already capped at `MAX_PAUSE_MS`) are positioned in
+    // the original text; move them to where they land in the clean text
     let mut offset = 0;
-    for cap in EXPLICIT_PAUSE_REGEX.captures_iter(text) {
-        let full_match = cap.get(0).unwrap();
-        let original_pos = full_match.start();
-        let adjusted_pos = original_pos.saturating_sub(offset);
-        let value: f64 = cap.get(1).unwrap().as_str().parse().unwrap_or(0.0);
-        let unit = cap.get(2).unwrap().as_str();
-
-        let duration_ms = match unit {
-            "ms" => value as u32,
-            "s" => (value * 1000.0) as u32,
-            _ => 0,
-        };
-
-        if duration_ms > 0 {
+    for pause in parse_explicit_pauses(text) {
+        let marker_len = pause.original.len();
+        if pause.duration_ms > 0 {
             all_pauses.push(PauseMarker {
-                original: full_match.as_str().to_string(),
-                duration_ms,
-                position: adjusted_pos,
+                position: pause.position.saturating_sub(offset),
+                ..pause
             });
         }
-
-        offset += full_match.len() - 1; // -1 for the space we replace with
+        offset += marker_len - 1; // -1 for the space we replace with
     }
 
     // Sort by position
@@ -204,6 +360,25 @@ mod tests {
         assert_eq!(pauses[1].duration_ms, 1500);
     }
 
+    #[test]
+    fn test_explicit_pause_is_capped() {
+        let pauses = parse_explicit_pauses("Wait [pause:99999999s] and [pause:20000ms]");
+        assert_eq!(pauses[0].duration_ms, defaults::MAX_PAUSE_MS);
+        assert_eq!(pauses[1].duration_ms, defaults::MAX_PAUSE_MS);
+    }
+
+    #[test]
+    fn test_parsed_text_pauses_are_capped() {
+        let parsed = parse_text_with_pauses("Wait [pause:99999999s] then [pause:250ms] go");
+        assert_eq!(parsed.clean_text, "Wait   then   go");
+        let pauses: Vec<(usize, u32)> = parsed
+            .pauses
+            .iter()
+            .map(|p| (p.position, p.duration_ms))
+            .collect();
+        assert_eq!(pauses, vec![(5, defaults::MAX_PAUSE_MS), (12, 250)]);
+    }
+
     #[test]
     fn test_parse_ellipsis() {
         let pauses = parse_natural_pauses("Hello... world");
@@ -239,6 +414,84 @@ mod tests {
         assert_eq!(parsed.pauses.len(), 3);
     }
 
+    #[test]
+    fn test_default_config_keeps_comma_and_ellipsis_only() {
+        let pauses = parse_natural_pauses("One; two: three. Four...\n\nFive, six");
+        let durations: Vec<u32> = pauses.iter().map(|p| p.duration_ms).collect();
+        assert_eq!(durations, vec![defaults::ELLIPSIS_MS, defaults::COMMA_MS]);
+    }
+
+    #[test]
+    fn test_punctuated_config() {
+        let text = "Hi; there: Dr. Smith asked why? Yes. At 5:30 e.g. now.\n\nNext paragraph.";
+        let pauses = parse_natural_pauses_with_config(text, &PauseConfig::punctuated());
+        let found: Vec<(&str, u32)> = pauses
+            .iter()
+            .map(|p| {
+                (
+                    &text[p.position..p.position + p.original.len()],
+                    p.duration_ms,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (";", defaults::SEMICOLON_MS),
+                (":", defaults::COLON_MS),
+                (" ", defaults::PERIOD_MS),
+                (" ", defaults::PERIOD_MS),
+                ("\n\n", defaults::PARAGRAPH_MS),
+            ]
+        );
+        // Question mark stays with its sentence
+        assert_eq!(
+            &text[..pauses[2].position],
+            "Hi; there: Dr. Smith asked why?"
+        );
+    }
+
+    #[test]
+    fn test_config_scale_and_disable() {
+        let config = PauseConfig {
+            scale: 1.5,
+            ..PauseConfig::default()
+        };
+        let pauses = parse_natural_pauses_with_config("Hello, world", &config);
+        assert_eq!(pauses[0].duration_ms, 300);
+
+        let parsed =
+            parse_text_with_pauses_with_config("Hello, [pause:1s] world", &PauseConfig::disabled());
+        assert_eq!(parsed.pauses.len(), 1);
+        assert_eq!(parsed.pauses[0].duration_ms, 1000);
+
+        let invalid = PauseConfig {
+            scale: -1.0,
+            ..PauseConfig::default()
+        };
+        assert!(invalid.validate().is_err());
+        assert!(PauseConfig::punctuated().validate().is_ok());
+
+        let too_long = PauseConfig {
+            comma_ms: u32::MAX,
+            ..PauseConfig::default()
+        };
+        assert!(too_long.validate().is_err());
+
+        let scaled = PauseConfig {
+            paragraph_ms: defaults::MAX_PAUSE_MS,
+            scale: defaults::MAX_SCALE,
+            ..PauseConfig::punctuated()
+        };
+        assert!(scaled.validate().is_ok());
+        let pauses = parse_natural_pauses_with_config("One.\n\nTwo.", &scaled);
+        assert!(
+            pauses
+                .iter()
+                .all(|p| p.duration_ms <= defaults::MAX_PAUSE_MS)
+        );
+    }
+
     #[test]
     fn test_silence_samples() {
         // 500ms at 24kHz = 12000 samples
@@ -0,0 +1,359 @@
+//! Sentence splitting for long-form generation
+//!
+//! Long texts are generated one chunk at a time, and every chunk restarts
+//! from the voice state, so chunk boundaries should fall between sentences.
+//! A [`SentenceSplitter`] finds the sentences; its provided
+//! [`SentenceSplitter::chunks`] packs them into chunks that fit the per-chunk
+//! token budget. [`DefaultSentenceSplitter`] handles English punctuation:
+//! - Splits after `.`, `!`, `?` and `…` followed by whitespace, so "3.14",
+//!   "10:30" and "v1.2" stay whole
+//! - Keeps closing quotes and brackets with their sentence: `"Stop!" `
+//! - Does not split after abbreviations and initials ("Dr. Smith",
+//!   "e.g. this", "J. R. R. Tolkien"), page and item references before a
+//!   number ("p. 5", "pp. 10-12"), list numbers ("1. First"), or before a
+//!   lowercase word (`"Wait!" she said`)
+//!
+//! Plug a custom splitter into a model with
+//! [`crate::TTSModel::with_sentence_splitter`].
+
+use crate::text_normalize::is_abbreviation_period;
+use std::collections::BTreeSet;
+
+/// Abbreviations that rarely end a sentence, on top of the ones text
+/// normalization expands (titles, "vs", "approx") and single initials
+const ABBREVIATIONS: &[&str] = &[
+    "No", "Nos", "Fig", "Figs", "Eq", "Vol", "Ch", "Sec", "Art", "cf", "al", "ca", "Gov", "Sen",
+    "Rep", "Pres", "Hon", "Mx",
+];
+
+/// Reference abbreviations that do not end a sentence when a number follows,
+/// as in "see p. 5" or "pp. 10-12"
+const NUMBER_ABBREVIATIONS: &[&str] = &["p", "pp", "vs", "etc", "v", "no", "nos", "para"];
+
+/// Closing quotes and brackets that stay attached to the sentence they end
+const CLOSING: &[char] = &['"', '\'', '”', '’', '»', ')', ']', '}'];
+
+/// Opening quotes and brackets ignored when reading the word before a period
+const OPENING: &[char] = &['"', '\'', '“', '‘', '«', '(', '[', '{'];
+
+/// Splits text into sentences and packs them into generation chunks
+pub trait SentenceSplitter: Send + Sync {
+    /// Split `text` into sentences, each keeping its punctuation
+    fn sentences(&self, text: &str) -> Vec<String>;
+
+    /// Split `text` into chunks of at most `max_tokens` tokens as measured by
+    /// `count_tokens`, keeping whole sentences together where they fit
+    fn chunks(
+        &self,
+        text: &str,
+        max_tokens: usize,
+        count_tokens: &dyn Fn(&str) -> usize,
+    ) -> Vec<String> {
+        pack_sentences(&self.sentences(text), max_tokens, count_tokens)
+    }
+}
+
+/// Abbreviation-, number- and quote-aware English sentence splitter
+#[derive(Debug, Clone, Default)]
+pub struct DefaultSentenceSplitter {
+    abbreviations: BTreeSet<String>,
+}
+
+impl DefaultSentenceSplitter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also treat these words as abbreviations, written without the period
+    /// and matched case-sensitively, e.g. `["Approx", "Corp"]`
+    pub fn with_abbreviations<I, S>(mut self, abbreviations: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.abbreviations
+            .extend(abbreviations.into_iter().map(Into::into));
+        self
+    }
+
+    fn is_abbreviation(&self, word: &str) -> bool {
+        word != "I"
+            && (is_abbreviation_period(word)
+                || ABBREVIATIONS.contains(&word)
+                || self.abbreviations.contains(word))
+    }
+
+    /// Whether `sentence` (ending in punctuation) ends before `rest`
+    fn is_boundary(&self, sentence: &str, rest: &str) -> bool {
+        let Some(next) = rest.trim_start().chars().find(|c| !OPENING.contains(c)) else {
+            return true;
+        };
+        if next.is_lowercase() {
+            return false;
+        }
+        let ending = sentence.trim_end_matches(CLOSING);
+        let Some(before) = ending.strip_suffix('.') else {
+            return true;
+        };
+        if before.ends_with('.') {
+            // Ellipsis
+            return true;
+        }
+        let word = before
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or("")
+            .trim_start_matches(OPENING);
+        let is_list_number =
+            before.trim() == word && !word.is_empty() && word.chars().all(|c| c.is_ascii_digit());
+        let is_reference =
+            next.is_ascii_digit() && NUMBER_ABBREVIATIONS.contains(&word.to_lowercase().as_str());
+        !(is_list_number || is_reference || self.is_abbreviation(word))
+    }
+}
+
+impl SentenceSplitter for DefaultSentenceSplitter {
+    fn sentences(&self, text: &str) -> Vec<String> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            if !is_terminator(chars[i].1) {
+                i += 1;
+                continue;
+            }
+            let mut j = i;
+            while j < chars.len() && is_terminator(chars[j].1) {
+                j += 1;
+            }
+            while j < chars.len() && CLOSING.contains(&chars[j].1) {
+                j += 1;
+            }
+            let end = chars.get(j).map_or(text.len(), |&(pos, _)| pos);
+            let at_space = chars.get(j).is_none_or(|(_, c)| c.is_whitespace());
+            if at_space && self.is_boundary(&text[start..end], &text[end..]) {
+                push_trimmed(&mut sentences, &text[start..end]);
+                start = end;
+            }
+            i = j;
+        }
+        push_trimmed(&mut sentences, &text[start..]);
+        sentences
+    }
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+fn push_trimmed(sentences: &mut Vec<String>, sentence: &str) {
+    let sentence = sentence.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence.to_string());
+    }
+}
+
+/// Greedily pack sentences into chunks of at most `max_tokens` tokens
+///
+/// A sentence over the budget is split at clause punctuation (`,`, `;`, `:`)
+/// and, failing that, into word batches.
+pub fn pack_sentences(
+    sentences: &[String],
+    max_tokens: usize,
+    count_tokens: &dyn Fn(&str) -> usize,
+) -> Vec<String> {
+    let max_tokens = max_tokens.max(1);
+    let mut chunks = Vec::new();
+    let mut current_chunk = String::new();
+    let mut current_token_count = 0;
+
+    for sentence in sentences {
+        let sentence_tokens = count_tokens(sentence);
+
+        if sentence_tokens > max_tokens {
+            if !current_chunk.is_empty() {
+                chunks.push(std::mem::take(&mut current_chunk));
+                current_token_count = 0;
+            }
+            let clauses = split_clauses(sentence);
+            if clauses.len() > 1 {
+                chunks.extend(pack_sentences(&clauses, max_tokens, count_tokens));
+            } else {
+                chunks.extend(split_words(sentence, max_tokens, count_tokens));
+            }
+            continue;
+        }
+
+        if current_chunk.is_empty() {
+            current_chunk = sentence.clone();
+            current_token_count = sentence_tokens;
+        } else if current_token_count + sentence_tokens > max_tokens {
+            chunks.push(std::mem::replace(&mut current_chunk, sentence.clone()));
+            current_token_count = sentence_tokens;
+        } else {
+            current_chunk.push(' ');
+            current_chunk.push_str(sentence);
+            current_token_count += sentence_tokens;
+        }
+    }
+
+    if !current_chunk.is_empty() {
+        chunks.push(current_chunk);
+    }
+    chunks
+}
+
+/// Split after `,`, `;` and `:` followed by whitespace
+fn split_clauses(sentence: &str) -> Vec<String> {
+    let mut clauses = Vec::new();
+    let mut start = 0;
+    let mut chars = sentence.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let at_space = chars.peek().is_some_and(|(_, next)| next.is_whitespace());
+        if matches!(c, ',' | ';' | ':') && at_space {
+            let end = pos + c.len_utf8();
+            push_trimmed(&mut clauses, &sentence[start..end]);
+            start = end;
+        }
+    }
+    push_trimmed(&mut clauses, &sentence[start..]);
+    clauses
+}
+
+/// Split an over-long run of text into word batches that fit `max_tokens`
+fn split_words(text: &str, max_tokens: usize, count_tokens: &dyn Fn(&str) -> usize) -> Vec<String> {
+    // Estimate batches at ~1.3 tokens per word instead of counting every
+    // word: ~35 words for the default 50 tokens, leaving a safe margin
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let words_per_batch = (max_tokens * 7 / 10).max(1);
+
+    let mut chunks = Vec::new();
+    for word_batch in words.chunks(words_per_batch) {
+        let chunk_str = word_batch.join(" ");
+        // Verify this batch is actually under limit (should almost always pass)
+        if count_tokens(&chunk_str) <= max_tokens || word_batch.len() < 2 {
+            chunks.push(chunk_str);
+        } else {
+            // Rare case: batch still too big, split in half
+            let mid = word_batch.len() / 2;
+            chunks.push(word_batch[..mid].join(" "));
+            chunks.push(word_batch[mid..].join(" "));
+        }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(text: &str) -> Vec<String> {
+        DefaultSentenceSplitter::new().sentences(text)
+    }
+
+    fn word_count(text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+
+    #[test]
+    fn test_abbreviations_numbers_and_times() {
+        assert_eq!(
+            split(
+                "Dr. Smith arrived at 10:30 a.m. sharp. Pi is 3.14, e.g. roughly. So did I. Done"
+            ),
+            vec![
+                "Dr. Smith arrived at 10:30 a.m. sharp.",
+                "Pi is 3.14, e.g. roughly.",
+                "So did I.",
+                "Done",
+            ]
+        );
+        assert_eq!(
+            split("1. Install it. 2. Run it. J. R. R. Tolkien wrote it; Fig. 3 shows it."),
+            vec![
+                "1. Install it.",
+                "2. Run it.",
+                "J. R. R. Tolkien wrote it; Fig. 3 shows it."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_abbreviations_before_numbers() {
+        assert_eq!(
+            split("See p. 5 for details. Read pp. 10-12 next. Round 3 vs. 4 went on."),
+            vec![
+                "See p. 5 for details.",
+                "Read pp. 10-12 next.",
+                "Round 3 vs. 4 went on."
+            ]
+        );
+        assert_eq!(
+            split("Bring pens, paper, etc. 2 people will help. Ask for pp. Then leave."),
+            vec![
+                "Bring pens, paper, etc. 2 people will help.",
+                "Ask for pp.",
+                "Then leave."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quotes_and_brackets_stay_attached() {
+        assert_eq!(
+            split("\"Stop!\" she said. \"Why?\" He left (quietly.) Then... Nothing?! Really."),
+            vec![
+                "\"Stop!\" she said.",
+                "\"Why?\"",
+                "He left (quietly.)",
+                "Then...",
+                "Nothing?!",
+                "Really.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_abbreviations() {
+        let splitter = DefaultSentenceSplitter::new().with_abbreviations(["Corp"]);
+        assert_eq!(
+            splitter.sentences("Acme Corp. Builds rockets. Acme Inc. Builds cars."),
+            vec!["Acme Corp. Builds rockets.", "Acme Inc.", "Builds cars."]
+        );
+    }
+
+    #[test]
+    fn test_chunks_pack_to_budget() {
+        let splitter = DefaultSentenceSplitter::new();
+        let text = "One two. Three four five. Six. Seven eight nine ten, eleven twelve; thirteen fourteen.";
+        assert_eq!(
+            splitter.chunks(text, 5, &word_count),
+            vec![
+                "One two. Three four five.",
+                "Six.",
+                "Seven eight nine ten,",
+                "eleven twelve; thirteen fourteen.",
+            ]
+        );
+        // No clause punctuation: word batches
+        assert_eq!(
+            splitter.chunks("a b c d e f g", 5, &word_count),
+            vec!["a b c", "d e f", "g"]
+        );
+    }
+
+    #[test]
+    fn test_custom_splitter_uses_default_packing() {
+        struct Lines;
+        impl SentenceSplitter for Lines {
+            fn sentences(&self, text: &str) -> Vec<String> {
+                text.lines().map(str::to_string).collect()
+            }
+        }
+        assert_eq!(
+            Lines.chunks("a b\nc\nd e f", 3, &word_count),
+            vec!["a b c", "d e f"]
+        );
+    }
+}
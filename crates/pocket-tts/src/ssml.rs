@@ -0,0 +1,864 @@
+//! SSML subset input
+//!
+//! Parses the parts of SSML that map onto the long-form segment pipeline:
+//! - `<speak>` root (optional)
+//! - `<break time="500ms"/>` and `<break strength="strong"/>`
+//! - `<p>` and `<s>` paragraph and sentence boundaries
+//! - `<prosody rate="slow" volume="+6dB">` (nested prosody multiplies)
+//! - `<say-as interpret-as="characters|digits|date|telephone">`
+//! - `<sub alias="...">`
+//! - `<audio src="...">fallback</audio>`
+//!
+//! Any other element is dropped with a warning and its text is still spoken.
+//! Breaks longer than [`MAX_BREAK_MS`] are shortened, and a `<` that does
+//! not start a tag is read as text, both with a warning. Malformed markup
+//! (unclosed or mismatched tags) is an error.
+
+use crate::text_normalize::{
+    date_words, digits_to_words, number_str_to_words, ordinal_to_words, year_to_words,
+};
+use anyhow::{Result, bail};
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Pause inserted between `<p>` paragraphs
+pub const PARAGRAPH_BREAK_MS: u32 = crate::pause::defaults::PARAGRAPH_MS;
+
+/// Longest `<break>`, as in common SSML engines
+pub const MAX_BREAK_MS: u32 = crate::pause::defaults::MAX_PAUSE_MS;
+
+/// `<break>` strengths and their durations in milliseconds
+const BREAK_STRENGTHS: &[(&str, u32)] = &[
+    ("none", 0),
+    ("x-weak", 100),
+    ("weak", 200),
+    ("medium", 400),
+    ("strong", 700),
+    ("x-strong", 1000),
+];
+
+/// `<prosody rate>` labels and their speed multipliers
+const RATES: &[(&str, f32)] = &[
+    ("x-slow", 0.5),
+    ("slow", 0.75),
+    ("medium", 1.0),
+    ("default", 1.0),
+    ("fast", 1.25),
+    ("x-fast", 1.5),
+];
+
+/// `<prosody volume>` labels and their gain in dB
+const VOLUMES: &[(&str, f32)] = &[
+    ("x-soft", -12.0),
+    ("soft", -6.0),
+    ("medium", 0.0),
+    ("default", 0.0),
+    ("loud", 6.0),
+    ("x-loud", 12.0),
+];
+
+static ATTRIBUTE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([A-Za-z_][\w:.-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+
+static TIME_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(\d+(?:\.\d+)?)\s*(ms|s)\s*$").unwrap());
+
+static DB_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*([+-]?\d+(?:\.\d+)?)\s*dB\s*$").unwrap());
+
+static CARDINAL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(-?)((?:\d{1,3}(?:,\d{3})+|\d+)(?:\.\d+)?)$").unwrap());
+
+static PERCENT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*([+-]?)(\d+(?:\.\d+)?)\s*%\s*$").unwrap());
+
+/// Speaking rate and volume in effect for a piece of text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prosody {
+    /// Multiplier applied to the call's speaking rate
+    pub rate: f32,
+    /// Linear gain applied to the generated audio
+    pub volume: f32,
+}
+
+impl Default for Prosody {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            volume: 1.0,
+        }
+    }
+}
+
+/// One item of parsed SSML, in document order
+#[derive(Debug, Clone, PartialEq)]
+pub enum SsmlItem {
+    /// Text to speak with the prosody in effect
+    Text { text: String, prosody: Prosody },
+    /// Silence of the given length in milliseconds
+    Break(u32),
+    /// Audio clip to play; `fallback` is spoken if it cannot be loaded
+    Audio {
+        src: String,
+        fallback: String,
+        prosody: Prosody,
+    },
+}
+
+/// Result of [`parse_ssml`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SsmlDocument {
+    /// Text, breaks and audio clips in document order
+    pub items: Vec<SsmlItem>,
+    /// Unsupported elements and attribute values that were ignored
+    pub warnings: Vec<String>,
+}
+
+/// Parse an SSML document (or a fragment without `<speak>`)
+///
+/// # Example
+/// ```
+/// use pocket_tts::ssml::{SsmlItem, parse_ssml};
+///
+/// let doc = parse_ssml(r#"<speak>Hello <break time="300ms"/> world</speak>"#).unwrap();
+/// assert_eq!(doc.items.len(), 3);
+/// assert_eq!(doc.items[1], SsmlItem::Break(300));
+/// ```
+pub fn parse_ssml(input: &str) -> Result<SsmlDocument> {
+    let mut parser = Parser::default();
+    for token in tokenize(input, &mut parser.warnings)? {
+        match token {
+            Token::Text(text) => parser.text(&text),
+            Token::Open {
+                name,
+                attributes,
+                self_closing,
+            } => {
+                parser.open(&name, attributes)?;
+                if self_closing {
+                    parser.close(&name)?;
+                }
+            }
+            Token::Close(name) => parser.close(&name)?,
+        }
+    }
+    if let Some(element) = parser.stack.last() {
+        bail!("SSML element <{}> is never closed", element.name);
+    }
+    parser.flush();
+    Ok(SsmlDocument {
+        items: parser.items,
+        warnings: parser.warnings,
+    })
+}
+
+enum Token {
+    Text(String),
+    Open {
+        name: String,
+        attributes: Vec<(String, String)>,
+        self_closing: bool,
+    },
+    Close(String),
+}
+
+/// Split markup into text and tags, skipping comments and declarations
+fn tokenize(input: &str, warnings: &mut Vec<String>) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(decode_entities(rest)));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(decode_entities(&rest[..start])));
+        }
+        rest = &rest[start..];
+
+        // A tag starts with a name, `/`, `!` or `?`; anything else is text
+        let starts_tag = rest[1..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || matches!(c, '_' | ':' | '/' | '!' | '?'));
+        if !starts_tag {
+            warnings.push(format!(
+                "\"<\" does not start a tag and is read as text: {}",
+                truncate(rest)
+            ));
+            tokens.push(Token::Text("<".to_string()));
+            rest = &rest[1..];
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let Some(end) = after.find("-->") else {
+                bail!("SSML comment is never closed");
+            };
+            rest = &after[end + 3..];
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let Some(end) = after.find("]]>") else {
+                bail!("SSML CDATA section is never closed");
+            };
+            tokens.push(Token::Text(after[..end].to_string()));
+            rest = &after[end + 3..];
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            bail!("SSML tag is never closed: {}", truncate(rest));
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            // <?xml ...?> and <!DOCTYPE ...>
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(name.trim().to_string()));
+            continue;
+        }
+
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        if name.is_empty() {
+            bail!("SSML tag has no name: <{}>", tag);
+        }
+        let attributes = ATTRIBUTE_REGEX
+            .captures_iter(&tag[name_end..])
+            .map(|caps| {
+                let value = caps
+                    .get(2)
+                    .or_else(|| caps.get(3))
+                    .map_or("", |m| m.as_str());
+                (caps[1].to_string(), decode_entities(value))
+            })
+            .collect();
+        tokens.push(Token::Open {
+            name: name.to_string(),
+            attributes,
+            self_closing,
+        });
+    }
+    Ok(tokens)
+}
+
+fn truncate(s: &str) -> &str {
+    match s.char_indices().nth(40) {
+        Some((i, _)) => &s[..i],
+        None => s,
+    }
+}
+
+/// Replace the predefined XML entities and numeric character references
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// What an open element does with the text inside it
+enum Capture {
+    /// Text flows into the current sentence
+    None,
+    /// Text is rewritten on close according to `interpret-as`
+    SayAs {
+        interpret_as: String,
+        format: Option<String>,
+        text: String,
+    },
+    /// Text is replaced by the alias on close
+    Sub { alias: Option<String>, text: String },
+    /// Text is the fallback for an audio clip
+    Audio { src: Option<String>, text: String },
+}
+
+impl Capture {
+    fn buffer(&mut self) -> Option<&mut String> {
+        match self {
+            Self::None => None,
+            Self::SayAs { text, .. } | Self::Sub { text, .. } | Self::Audio { text, .. } => {
+                Some(text)
+            }
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    prosody: Prosody,
+    capture: Capture,
+}
+
+#[derive(Default)]
+struct Parser {
+    stack: Vec<Element>,
+    /// Text not yet emitted, spoken with the prosody of the innermost element
+    pending: String,
+    items: Vec<SsmlItem>,
+    warnings: Vec<String>,
+}
+
+impl Parser {
+    fn prosody(&self) -> Prosody {
+        self.stack.last().map(|e| e.prosody).unwrap_or_default()
+    }
+
+    fn text(&mut self, text: &str) {
+        match self.stack.iter_mut().rev().find_map(|e| e.capture.buffer()) {
+            Some(buffer) => buffer.push_str(text),
+            None => self.pending.push_str(text),
+        }
+    }
+
+    /// Emit pending text as one item with the current prosody
+    fn flush(&mut self) {
+        let text = self
+            .pending
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.pending.clear();
+        if !text.is_empty() {
+            let prosody = self.prosody();
+            self.items.push(SsmlItem::Text { text, prosody });
+        }
+    }
+
+    /// End the pending sentence with punctuation so it is split there
+    fn end_sentence(&mut self) {
+        let trimmed = self.pending.trim_end();
+        if !trimmed.is_empty() && !trimmed.ends_with(['.', '!', '?', '…', ';', ':']) {
+            self.pending.truncate(trimmed.len());
+            self.pending.push('.');
+        }
+        self.pending.push(' ');
+    }
+
+    fn open(&mut self, name: &str, attributes: Vec<(String, String)>) -> Result<()> {
+        let attr = |key: &str| {
+            attributes
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+        let capturing = self
+            .stack
+            .iter()
+            .rev()
+            .find(|e| !matches!(e.capture, Capture::None))
+            .map(|e| e.name.clone());
+        if let Some(parent) = capturing {
+            self.warnings
+                .push(format!("<{}> inside <{}> is ignored", name, parent));
+            self.push(name, self.prosody(), Capture::None);
+            return Ok(());
+        }
+
+        let mut prosody = self.prosody();
+        let mut capture = Capture::None;
+        match name {
+            "speak" => {}
+            "break" => {
+                let ms = self.break_ms(attr("time"), attr("strength"));
+                self.flush();
+                self.items.push(SsmlItem::Break(ms));
+            }
+            "p" => {
+                self.end_sentence();
+                self.flush();
+                if !matches!(self.items.last(), None | Some(SsmlItem::Break(_))) {
+                    self.items.push(SsmlItem::Break(PARAGRAPH_BREAK_MS));
+                }
+            }
+            "s" => self.end_sentence(),
+            "prosody" => {
+                self.flush();
+                if let Some(rate) = attr("rate") {
+                    match parse_rate(&rate) {
+                        Some(r) => prosody.rate *= r,
+                        None => self
+                            .warnings
+                            .push(format!("unsupported prosody rate \"{}\" ignored", rate)),
+                    }
+                }
+                if let Some(volume) = attr("volume") {
+                    match parse_volume(&volume) {
+                        Some(v) => prosody.volume *= v,
+                        None => self
+                            .warnings
+                            .push(format!("unsupported prosody volume \"{}\" ignored", volume)),
+                    }
+                }
+                for (key, _) in &attributes {
+                    if key != "rate" && key != "volume" {
+                        self.warnings
+                            .push(format!("prosody attribute \"{}\" is not supported", key));
+                    }
+                }
+            }
+            "say-as" => match attr("interpret-as") {
+                Some(interpret_as) => {
+                    capture = Capture::SayAs {
+                        interpret_as,
+                        format: attr("format"),
+                        text: String::new(),
+                    }
+                }
+                None => self
+                    .warnings
+                    .push("<say-as> without interpret-as is read as plain text".to_string()),
+            },
+            "sub" => {
+                capture = Capture::Sub {
+                    alias: attr("alias"),
+                    text: String::new(),
+                }
+            }
+            "audio" => {
+                capture = Capture::Audio {
+                    src: attr("src"),
+                    text: String::new(),
+                }
+            }
+            _ => self
+                .warnings
+                .push(format!("unsupported SSML element <{}> ignored", name)),
+        }
+        self.push(name, prosody, capture);
+        Ok(())
+    }
+
+    fn push(&mut self, name: &str, prosody: Prosody, capture: Capture) {
+        self.stack.push(Element {
+            name: name.to_string(),
+            prosody,
+            capture,
+        });
+    }
+
+    fn close(&mut self, name: &str) -> Result<()> {
+        let Some(element) = self.stack.last() else {
+            bail!("unexpected SSML closing tag </{}>", name);
+        };
+        if element.name != name {
+            bail!("SSML element <{}> closed by </{}>", element.name, name);
+        }
+        if name == "prosody" {
+            // Text inside the element keeps its prosody
+            self.flush();
+        }
+        let element = self.stack.pop().expect("checked above");
+        match element.capture {
+            Capture::None => {
+                if name == "s" || name == "p" {
+                    self.end_sentence();
+                }
+            }
+            Capture::SayAs {
+                interpret_as,
+                format,
+                text,
+            } => {
+                let spoken = match say_as(&interpret_as, format.as_deref(), &text) {
+                    Some(spoken) => spoken,
+                    None => {
+                        self.warnings.push(format!(
+                            "say-as interpret-as=\"{}\" could not read \"{}\"",
+                            interpret_as,
+                            text.trim()
+                        ));
+                        text
+                    }
+                };
+                self.append(&spoken);
+            }
+            Capture::Sub { alias, text } => match alias {
+                Some(alias) => self.append(&alias),
+                None => {
+                    self.warnings
+                        .push("<sub> without alias is read as plain text".to_string());
+                    self.append(&text);
+                }
+            },
+            Capture::Audio { src, text } => match src {
+                Some(src) => {
+                    self.flush();
+                    let fallback = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    self.items.push(SsmlItem::Audio {
+                        src,
+                        fallback,
+                        prosody: element.prosody,
+                    });
+                }
+                None => {
+                    self.warnings
+                        .push("<audio> without src speaks its fallback text".to_string());
+                    self.append(&text);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Append rewritten text to wherever text currently goes
+    fn append(&mut self, text: &str) {
+        self.text(&format!(" {}", text.trim()));
+    }
+
+    fn break_ms(&mut self, time: Option<String>, strength: Option<String>) -> u32 {
+        if let Some(time) = time {
+            if let Some(caps) = TIME_REGEX.captures(&time) {
+                let value: f64 = caps[1].parse().unwrap_or(0.0);
+                let ms = match &caps[2] {
+                    "s" => value * 1000.0,
+                    _ => value,
+                };
+                if ms > MAX_BREAK_MS as f64 {
+                    self.warnings.push(format!(
+                        "break time \"{}\" shortened to {} ms",
+                        time, MAX_BREAK_MS
+                    ));
+                }
+                return ms.min(MAX_BREAK_MS as f64) as u32;
+            }
+            self.warnings
+                .push(format!("unsupported break time \"{}\" ignored", time));
+        }
+        let strength = strength.unwrap_or_else(|| "medium".to_string());
+        match BREAK_STRENGTHS.iter().find(|(s, _)| *s == strength) {
+            Some((_, ms)) => *ms,
+            None => {
+                self.warnings.push(format!(
+                    "unsupported break strength \"{}\" ignored",
+                    strength
+                ));
+                400
+            }
+        }
+    }
+}
+
+/// Rate multiplier from a label, a percentage ("80%", "+20%") or a number
+fn parse_rate(rate: &str) -> Option<f32> {
+    let rate = rate.trim();
+    if let Some((_, r)) = RATES.iter().find(|(label, _)| *label == rate) {
+        return Some(*r);
+    }
+    let value = if let Some(caps) = PERCENT_REGEX.captures(rate) {
+        let percent: f32 = caps[2].parse().ok()?;
+        match &caps[1] {
+            "+" => 1.0 + percent / 100.0,
+            "-" => 1.0 - percent / 100.0,
+            _ => percent / 100.0,
+        }
+    } else {
+        rate.parse().ok()?
+    };
+    (value > 0.0).then_some(value)
+}
+
+/// Linear gain from a label or a relative level in dB ("+6dB")
+fn parse_volume(volume: &str) -> Option<f32> {
+    let volume = volume.trim();
+    if volume == "silent" {
+        return Some(0.0);
+    }
+    let db = match VOLUMES.iter().find(|(label, _)| *label == volume) {
+        Some((_, db)) => *db,
+        None => DB_REGEX.captures(volume)?[1].parse().ok()?,
+    };
+    Some(10f32.powf(db / 20.0))
+}
+
+/// Spoken form of `<say-as>` content, or `None` if it cannot be read that way
+fn say_as(interpret_as: &str, format: Option<&str>, text: &str) -> Option<String> {
+    let text = text.trim();
+    match interpret_as {
+        "characters" | "spell-out" | "verbatim" => {
+            let words: Vec<String> = text
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .map(|c| match c.to_digit(10) {
+                    Some(_) => digits_to_words(&c.to_string()),
+                    None => c.to_uppercase().to_string(),
+                })
+                .collect();
+            (!words.is_empty()).then(|| words.join(" "))
+        }
+        "digits" => {
+            let words = digits_to_words(text);
+            (!words.is_empty()).then_some(words)
+        }
+        "cardinal" | "number" => {
+            let caps = CARDINAL_REGEX.captures(text)?;
+            let words = number_str_to_words(&caps[2]);
+            Some(if caps[1].is_empty() {
+                words
+            } else {
+                format!("minus {}", words)
+            })
+        }
+        "ordinal" => text
+            .trim_end_matches(|c: char| c.is_alphabetic())
+            .parse()
+            .ok()
+            .map(ordinal_to_words),
+        "date" => say_date(format.unwrap_or("mdy"), text),
+        "telephone" => {
+            let groups: Vec<String> = text
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|g| !g.is_empty())
+                .map(digits_to_words)
+                .collect();
+            if groups.is_empty() {
+                return None;
+            }
+            let number = groups.join(", ");
+            Some(if text.starts_with('+') {
+                format!("plus {}", number)
+            } else {
+                number
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Read a numeric date whose field order is given by `format` ("mdy", "dmy", ...)
+fn say_date(format: &str, text: &str) -> Option<String> {
+    let parts: Vec<u64> = text
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    if parts.len() != format.len() {
+        return None;
+    }
+    let field = |name: char| format.find(name).map(|i| parts[i]);
+    match (field('m'), field('d'), field('y')) {
+        (Some(month), Some(day), year) => date_words(month, day, year),
+        (None, None, Some(year)) => Some(year_to_words(year)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(doc: &SsmlDocument) -> Vec<&str> {
+        doc.items
+            .iter()
+            .filter_map(|item| match item {
+                SsmlItem::Text { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_breaks_and_sentences() {
+        let doc = parse_ssml(
+            r#"<?xml version="1.0"?>
+            <speak>
+              <p><s>First sentence</s><s>Second one</s></p>
+              <p>New paragraph<break time="1.5s"/>after<break strength="weak"/>end</p>
+            </speak>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.items,
+            vec![
+                SsmlItem::Text {
+                    text: "First sentence. Second one.".to_string(),
+                    prosody: Prosody::default(),
+                },
+                SsmlItem::Break(PARAGRAPH_BREAK_MS),
+                SsmlItem::Text {
+                    text: "New paragraph".to_string(),
+                    prosody: Prosody::default(),
+                },
+                SsmlItem::Break(1500),
+                SsmlItem::Text {
+                    text: "after".to_string(),
+                    prosody: Prosody::default(),
+                },
+                SsmlItem::Break(200),
+                SsmlItem::Text {
+                    text: "end.".to_string(),
+                    prosody: Prosody::default(),
+                },
+            ]
+        );
+        assert!(doc.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_nested_prosody() {
+        let doc = parse_ssml(
+            r#"<speak>normal <prosody rate="slow" volume="+6dB">slow
+            <prosody rate="200%" volume="silent">inner</prosody> back</prosody> done</speak>"#,
+        )
+        .unwrap();
+        let prosodies: Vec<Prosody> = doc
+            .items
+            .iter()
+            .map(|item| match item {
+                SsmlItem::Text { prosody, .. } => *prosody,
+                other => panic!("unexpected item {:?}", other),
+            })
+            .collect();
+        assert_eq!(texts(&doc), ["normal", "slow", "inner", "back", "done"]);
+        assert_eq!(prosodies[0], Prosody::default());
+        assert_eq!(prosodies[1].rate, 0.75);
+        assert!((prosodies[1].volume - 1.995).abs() < 1e-3);
+        assert_eq!(prosodies[2].rate, 1.5);
+        assert_eq!(prosodies[2].volume, 0.0);
+        assert_eq!(prosodies[3], prosodies[1]);
+        assert_eq!(prosodies[4], Prosody::default());
+    }
+
+    #[test]
+    fn test_say_as_and_sub() {
+        let doc = parse_ssml(
+            r#"<speak>Call <say-as interpret-as="telephone">(555) 123-4567</say-as>
+            about <say-as interpret-as="characters">SSML</say-as>,
+            code <say-as interpret-as="digits">2048</say-as>,
+            on <say-as interpret-as="date" format="dmy">4/3/2025</say-as>.
+            <sub alias="World Wide Web Consortium">W3C</sub> &amp; friends.</speak>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            texts(&doc),
+            [
+                "Call five five five, one two three, four five six seven about S S M L, \
+                 code two zero four eight, on March fourth, twenty twenty-five. \
+                 World Wide Web Consortium & friends."
+            ]
+        );
+        assert!(doc.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_audio_and_unknown_elements() {
+        let doc = parse_ssml(
+            r#"<speak><mark name="a"/>Before <emphasis>this</emphasis>
+            <audio src="chime.wav">ding</audio> after</speak>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.items[1],
+            SsmlItem::Audio {
+                src: "chime.wav".to_string(),
+                fallback: "ding".to_string(),
+                prosody: Prosody::default(),
+            }
+        );
+        assert_eq!(texts(&doc), ["Before this", "after"]);
+        assert_eq!(doc.warnings.len(), 2);
+        assert!(doc.warnings[0].contains("<mark>"));
+        assert!(doc.warnings[1].contains("<emphasis>"));
+    }
+
+    #[test]
+    fn test_fragment_and_bad_values() {
+        let doc = parse_ssml(
+            r#"Plain <break time="soon"/> text <say-as interpret-as="telephone">none</say-as>"#,
+        )
+        .unwrap();
+        assert_eq!(doc.items[1], SsmlItem::Break(400));
+        assert_eq!(texts(&doc), ["Plain", "text none"]);
+        assert_eq!(doc.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_long_breaks_are_capped() {
+        let doc =
+            parse_ssml(r#"<speak>a<break time="99999999s"/>b<break time="2s"/>c</speak>"#).unwrap();
+        assert_eq!(doc.items[1], SsmlItem::Break(MAX_BREAK_MS));
+        assert_eq!(doc.items[3], SsmlItem::Break(2000));
+        assert_eq!(doc.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_say_as_cardinal() {
+        let read = |text: &str| say_as("cardinal", None, text);
+        assert_eq!(
+            read("1,250").as_deref(),
+            Some("one thousand two hundred fifty")
+        );
+        assert_eq!(read("-5").as_deref(), Some("minus five"));
+        assert_eq!(read("2.5").as_deref(), Some("two point five"));
+        for bad in ["NaN", "inf", "1e400", "+-3", "1,2,3", ""] {
+            assert_eq!(read(bad), None, "{bad}");
+        }
+
+        let doc =
+            parse_ssml(r#"<speak><say-as interpret-as="cardinal">NaN</say-as></speak>"#).unwrap();
+        assert_eq!(texts(&doc), ["NaN"]);
+        assert_eq!(doc.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_stray_less_than_is_text() {
+        let doc = parse_ssml("<speak>a < b and c <= d</speak>").unwrap();
+        assert_eq!(texts(&doc), ["a < b and c <= d"]);
+        assert_eq!(doc.warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_malformed_markup_is_an_error() {
+        assert!(parse_ssml("<speak>unclosed").is_err());
+        assert!(parse_ssml("<speak><p>crossed</speak></p>").is_err());
+        assert!(parse_ssml("<speak>text</speak></speak>").is_err());
+        assert!(parse_ssml("<speak attr=\"x\"").is_err());
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#65;&#x42; &unknown; & c"),
+            "a <b> AB &unknown; & c"
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Tokenizer coverage diagnostics and unsupported-character handling
+//!
+//! The model's SentencePiece vocabulary covers English text. Emoji, CJK and
+//! unusual Unicode punctuation tokenize to `<unk>` or byte-fallback pieces
+//! the model never learned to voice, which comes out as garbled audio.
+//! [`crate::TTSModel::analyze_text`] reports such characters together with the
+//! token count of every chunk, and the long-form entry points apply an
+//! [`UnsupportedCharPolicy`] to them before generation:
+//!
+//! - `Transliterate` (default) maps typographic punctuation and accented
+//!   Latin letters to ASCII (`“` → `"`, `—` → `-`, `é` → `e`) and removes
+//!   characters without a supported replacement
+//! - `Strip` removes every unsupported character
+//! - `Error` fails the call instead
+
+use std::collections::HashMap;
+use std::ops::Range;
+use tokenizers::normalizers::StripAccents;
+use tokenizers::{NormalizedString, Normalizer};
+
+/// What the long-form entry points do with characters the tokenizer cannot
+/// encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsupportedCharPolicy {
+    /// Remove the characters
+    Strip,
+    /// Replace characters with a supported ASCII spelling, removing the rest
+    #[default]
+    Transliterate,
+    /// Fail the generation call
+    Error,
+}
+
+/// A character the tokenizer has no vocabulary piece for
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct UnsupportedChar {
+    pub ch: char,
+    /// Number of occurrences in the analyzed text
+    pub count: usize,
+    /// Text that replaces the character under the policy; `None` removes it
+    /// (or, with [`UnsupportedCharPolicy::Error`], rejects the text)
+    pub replacement: Option<String>,
+}
+
+impl UnsupportedChar {
+    /// One-line ASCII description, e.g. `U+2014 replaced with "-" (2x)`
+    pub fn describe(&self, policy: UnsupportedCharPolicy) -> String {
+        let action = match (policy, &self.replacement) {
+            (UnsupportedCharPolicy::Error, _) => "not supported".to_string(),
+            (_, Some(replacement)) => format!("replaced with {:?}", replacement),
+            (_, None) => "removed".to_string(),
+        };
+        format!("U+{:04X} {} ({}x)", self.ch as u32, action, self.count)
+    }
+}
+
+/// Token statistics for one chunk as it will be fed to the model
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ChunkAnalysis {
+    /// Chunk text after markup parsing, lexicon, normalization and the
+    /// unsupported-character policy
+    pub text: String,
+    pub tokens: usize,
+    pub unknown_tokens: usize,
+    pub byte_fallback_tokens: usize,
+}
+
+/// Result of [`crate::TTSModel::analyze_text`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct TextAnalysis {
+    /// Policy the analysis was run with
+    pub policy: UnsupportedCharPolicy,
+    /// Unsupported characters in order of first appearance
+    pub unsupported: Vec<UnsupportedChar>,
+    /// Sentence chunks in generation order
+    pub chunks: Vec<ChunkAnalysis>,
+    /// Per-chunk token budget the chunks were split with
+    pub max_tokens_per_chunk: usize,
+}
+
+impl TextAnalysis {
+    /// Whether the text will be generated exactly as written
+    pub fn is_clean(&self) -> bool {
+        self.warnings().is_empty()
+    }
+
+    /// ASCII warnings for unsupported characters, chunks that still contain
+    /// unknown or byte-fallback tokens, and chunks over the token budget
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<String> = self
+            .unsupported
+            .iter()
+            .map(|c| format!("unsupported character {}", c.describe(self.policy)))
+            .collect();
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let unsupported = chunk.unknown_tokens + chunk.byte_fallback_tokens;
+            if unsupported > 0 {
+                warnings.push(format!(
+                    "chunk {} has {} unknown or byte-fallback tokens",
+                    index, unsupported
+                ));
+            }
+            if chunk.tokens > self.max_tokens_per_chunk {
+                warnings.push(format!(
+                    "chunk {} has {} tokens, over the budget of {}",
+                    index, chunk.tokens, self.max_tokens_per_chunk
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// ASCII spelling of typographic punctuation, spaces and Latin letters, or
+/// `None` for characters without one (emoji, CJK, ...)
+pub fn transliterate(c: char) -> Option<String> {
+    let mapped = match c {
+        '‘' | '’' | '‚' | '‛' | '′' | '‹' | '›' | '`' | '´' => "'",
+        '“' | '”' | '„' | '‟' | '″' | '«' | '»' => "\"",
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' | '⁃' => "-",
+        '…' => "...",
+        '•' | '·' | '‧' | '◦' => ",",
+        '×' => "x",
+        '\u{a0}' | '\u{2000}'..='\u{200a}' | '\u{202f}' | '\u{205f}' | '\u{3000}' => " ",
+        '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}' | '\u{ad}' => "",
+        'ß' => "ss",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ø' => "o",
+        'Ø' => "O",
+        'ł' => "l",
+        'Ł' => "L",
+        'đ' | 'ð' => "d",
+        'Đ' | 'Ð' => "D",
+        'þ' => "th",
+        'Þ' => "Th",
+        'ı' => "i",
+        _ => return decompose(c),
+    };
+    Some(mapped.to_string())
+}
+
+/// Compatibility decomposition without combining marks: `é` → `e`, `ﬁ` →
+/// `fi`, `²` → `2`
+fn decompose(c: char) -> Option<String> {
+    let mut normalized = NormalizedString::from(c.to_string());
+    normalized.nfkd();
+    StripAccents.normalize(&mut normalized).ok()?;
+    let decomposed = normalized.get();
+    (decomposed.is_ascii() && !decomposed.is_empty()).then(|| decomposed.to_string())
+}
+
+/// Distinct characters inside `spans` (byte ranges of `text`) with their
+/// number of occurrences, in order of first appearance
+pub(crate) fn chars_in_spans(text: &str, spans: &[Range<usize>]) -> Vec<(char, usize)> {
+    let mut counts: Vec<(char, usize)> = Vec::new();
+    for span in spans {
+        let Some(chars) = text.get(span.clone()) else {
+            continue;
+        };
+        for c in chars.chars().filter(|c| !c.is_ascii_whitespace()) {
+            match counts.iter_mut().find(|(seen, _)| *seen == c) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((c, 1)),
+            }
+        }
+    }
+    counts
+}
+
+/// Replace or remove every unsupported character in `text`, collapsing the
+/// runs of spaces that removals leave behind
+pub(crate) fn replace_unsupported(text: &str, unsupported: &[UnsupportedChar]) -> String {
+    let replacements: HashMap<char, &str> = unsupported
+        .iter()
+        .map(|c| (c.ch, c.replacement.as_deref().unwrap_or("")))
+        .collect();
+    let mut replaced = String::with_capacity(text.len());
+    for c in text.chars() {
+        match replacements.get(&c) {
+            Some(replacement) => replaced.push_str(replacement),
+            None => replaced.push(c),
+        }
+    }
+    let mut collapsed = String::with_capacity(replaced.len());
+    for c in replaced.chars() {
+        if !(c == ' ' && collapsed.ends_with(' ')) {
+            collapsed.push(c);
+        }
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(transliterate('“').as_deref(), Some("\""));
+        assert_eq!(transliterate('—').as_deref(), Some("-"));
+        assert_eq!(transliterate('…').as_deref(), Some("..."));
+        assert_eq!(transliterate('é').as_deref(), Some("e"));
+        assert_eq!(transliterate('Ö').as_deref(), Some("O"));
+        assert_eq!(transliterate('ß').as_deref(), Some("ss"));
+        assert_eq!(transliterate('ﬁ').as_deref(), Some("fi"));
+        assert_eq!(transliterate('\u{a0}').as_deref(), Some(" "));
+        assert_eq!(transliterate('😀'), None);
+        assert_eq!(transliterate('你'), None);
+    }
+
+    #[test]
+    fn test_chars_in_spans_counts_in_order() {
+        let text = "a😀 b你😀 c";
+        let spans = [1..5, 7..14];
+        assert_eq!(chars_in_spans(text, &spans), vec![('😀', 2), ('你', 1)]);
+        // Unicode spaces are reported, ASCII whitespace inside a span is not
+        assert_eq!(
+            chars_in_spans("a\u{a0}\n😀", &[1..4, 4..8]),
+            vec![('\u{a0}', 1), ('😀', 1)]
+        );
+    }
+
+    #[test]
+    fn test_replace_unsupported() {
+        let unsupported = [
+            UnsupportedChar {
+                ch: '😀',
+                count: 1,
+                replacement: None,
+            },
+            UnsupportedChar {
+                ch: '—',
+                count: 1,
+                replacement: Some("-".to_string()),
+            },
+        ];
+        assert_eq!(
+            replace_unsupported("Great 😀 news—really", &unsupported),
+            "Great news-really"
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let analysis = TextAnalysis {
+            policy: UnsupportedCharPolicy::Transliterate,
+            unsupported: vec![
+                UnsupportedChar {
+                    ch: '—',
+                    count: 2,
+                    replacement: Some("-".to_string()),
+                },
+                UnsupportedChar {
+                    ch: '😀',
+                    count: 1,
+                    replacement: None,
+                },
+            ],
+            chunks: vec![
+                ChunkAnalysis {
+                    text: "Hello.".to_string(),
+                    tokens: 3,
+                    unknown_tokens: 0,
+                    byte_fallback_tokens: 0,
+                },
+                ChunkAnalysis {
+                    text: "Supercalifragilistic.".to_string(),
+                    tokens: 12,
+                    unknown_tokens: 1,
+                    byte_fallback_tokens: 0,
+                },
+            ],
+            max_tokens_per_chunk: 10,
+        };
+        assert_eq!(
+            analysis.warnings(),
+            vec![
+                "unsupported character U+2014 replaced with \"-\" (2x)",
+                "unsupported character U+1F600 removed (1x)",
+                "chunk 1 has 1 unknown or byte-fallback tokens",
+                "chunk 1 has 12 tokens, over the budget of 10",
+            ]
+        );
+        assert!(!analysis.is_clean());
+    }
+}
@@ -0,0 +1,870 @@
+//! Text normalization frontend
+//!
+//! Expands numbers, currency, percentages, dates, times, units and common
+//! abbreviations into words before text reaches the tokenizer, so
+//! "$1,250 on 3/4/2025 at 5pm" is read as "one thousand two hundred fifty
+//! dollars on March fourth, twenty twenty-five at five P M".
+//!
+//! This also keeps the sentence splitter from breaking on the periods and
+//! colons inside "Dr.", "3.5" or "5:30".
+//!
+//! Tokens that are not plain numbers are read piecewise: phone numbers and
+//! dotted versions ("1.5.2") digit by digit, ranges ("1990-2000") with "to",
+//! simple fractions ("3/4") as fractions, and numbers glued to letters ("5g",
+//! "3.5x") as the number followed by the letters.
+
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [&str; 7] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Abbreviations whose period never ends a sentence
+const INLINE_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("Dr", "Doctor"),
+    ("Mr", "Mister"),
+    ("Mrs", "Missus"),
+    ("Ms", "Miz"),
+    ("Prof", "Professor"),
+    ("Capt", "Captain"),
+    ("Gen", "General"),
+    ("Lt", "Lieutenant"),
+    ("Sgt", "Sergeant"),
+    ("Rev", "Reverend"),
+    ("Mt", "Mount"),
+    ("vs", "versus"),
+    ("approx", "approximately"),
+];
+
+/// Abbreviations that may end a sentence
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("Jr", "Junior"),
+    ("Sr", "Senior"),
+    ("Ave", "Avenue"),
+    ("Blvd", "Boulevard"),
+    ("Rd", "Road"),
+    ("etc", "et cetera"),
+    ("dept", "department"),
+    ("Dept", "Department"),
+];
+
+/// Units as (symbol, singular, plural); longer symbols first
+const UNITS: &[(&str, &str, &str)] = &[
+    ("km/h", "kilometer per hour", "kilometers per hour"),
+    ("mph", "mile per hour", "miles per hour"),
+    ("kph", "kilometer per hour", "kilometers per hour"),
+    ("kHz", "kilohertz", "kilohertz"),
+    ("MHz", "megahertz", "megahertz"),
+    ("GHz", "gigahertz", "gigahertz"),
+    ("Hz", "hertz", "hertz"),
+    ("km", "kilometer", "kilometers"),
+    ("cm", "centimeter", "centimeters"),
+    ("mm", "millimeter", "millimeters"),
+    ("mi", "mile", "miles"),
+    ("ft", "foot", "feet"),
+    ("kg", "kilogram", "kilograms"),
+    ("mg", "milligram", "milligrams"),
+    ("g", "gram", "grams"),
+    ("lbs", "pound", "pounds"),
+    ("lb", "pound", "pounds"),
+    ("oz", "ounce", "ounces"),
+    ("ml", "milliliter", "milliliters"),
+    ("TB", "terabyte", "terabytes"),
+    ("GB", "gigabyte", "gigabytes"),
+    ("MB", "megabyte", "megabytes"),
+    ("KB", "kilobyte", "kilobytes"),
+    ("kB", "kilobyte", "kilobytes"),
+    ("m", "meter", "meters"),
+];
+
+/// Number with optional thousands separators and decimals, e.g. `1,250.5`
+const NUMBER: &str = r"\d{1,3}(?:,\d{3})+(?:\.\d+)?|\d+(?:\.\d+)?";
+
+static INLINE_ABBREVIATION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    let names: Vec<&str> = INLINE_ABBREVIATIONS.iter().map(|(abbr, _)| *abbr).collect();
+    Regex::new(&format!(r"\b({})\.", names.join("|"))).unwrap()
+});
+
+static ABBREVIATION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    let names: Vec<&str> = ABBREVIATIONS.iter().map(|(abbr, _)| *abbr).collect();
+    Regex::new(&format!(r"\b({}|St)\.", names.join("|"))).unwrap()
+});
+
+static LATIN_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:(e)\.g|(i)\.e)\.,?").unwrap());
+
+static NUMBER_SIGN_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:\bNo\.\s*|#)(\d)").unwrap());
+
+static CURRENCY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"([$€£¥])\s?({NUMBER}|\.\d+)(?:\s+(thousand|million|billion|trillion)\b|([KMB]|bn)\b)?"
+    ))
+    .unwrap()
+});
+
+static PERCENT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"({NUMBER})\s?%")).unwrap());
+
+static SLASH_DATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{1,2})/(\d{1,2})/(\d{4}|\d{2})\b").unwrap());
+
+static ISO_DATE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap());
+
+static MONTH_DAY_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"\b({})\s+(\d{{1,2}})(?:st|nd|rd|th)?\b",
+        MONTHS.join("|")
+    ))
+    .unwrap()
+});
+
+/// "5:30", "5:30pm" or "5.30pm"; a period separates minutes only before am/pm
+static CLOCK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(\d{1,2})(?::(\d{2})(?:\s?([aApP])\.?[mM]\b\.?|\b)|\.(\d{2})\s?([aApP])\.?[mM]\b\.?)",
+    )
+    .unwrap()
+});
+
+static HOUR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{1,2})\s?([aApP])\.?[mM]\b\.?").unwrap());
+
+static ORDINAL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(\d{1,3}(?:,\d{3})+|\d+)(st|nd|rd|th)\b").unwrap());
+
+static UNIT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    let symbols: Vec<String> = UNITS.iter().map(|(s, _, _)| regex::escape(s)).collect();
+    Regex::new(&format!(
+        r"\b({NUMBER})\s?(?:({})\b|°\s?([CF])\b|°)",
+        symbols.join("|")
+    ))
+    .unwrap()
+});
+
+static NEGATIVE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(^|[\s(])-(\d)").unwrap());
+
+/// "555-1234", "555-123-4567" or "(555) 123-4567"
+static PHONE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:\((\d{3})\)\s?|\b(\d{3})-)?\b(\d{3})-(\d{4})\b").unwrap());
+
+/// Three or more dotted parts, e.g. versions or IP addresses
+static DOTTED_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b\d+(?:\.\d+){2,}\b").unwrap());
+
+static RANGE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"\b({NUMBER})(?:-|–| - | – )({NUMBER})\b")).unwrap());
+
+/// A fraction with an optional whole part, e.g. "3/4" or "1 1/2"
+static FRACTION_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:(\d{1,2})\s+)?(\d{1,2})/(\d{1,3})\b").unwrap());
+
+static DECADE_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(\d{1,3}0)s\b").unwrap());
+
+/// Letters and digits in one word, e.g. "5g", "3.5x", "A380" or "mp3"
+static ALPHANUMERIC_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(?:\d+(?:\.\d+)?[A-Za-z]|[A-Za-z]+\d)[A-Za-z\d]*\b").unwrap());
+
+static NUMBER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(&format!(r"\b(?:{NUMBER})\b")).unwrap());
+
+/// Expand numbers, currency, dates, times, units and abbreviations into words
+///
+/// Text that needs no expansion is returned unchanged.
+pub fn normalize_text(text: &str) -> String {
+    let text = expand_abbreviations(text);
+    let text = NUMBER_SIGN_REGEX.replace_all(&text, "number $1");
+    let text = CURRENCY_REGEX.replace_all(&text, expand_currency);
+    let text = PERCENT_REGEX.replace_all(&text, |caps: &Captures| {
+        format!("{} percent", number_str_to_words(&caps[1]))
+    });
+    let text = expand_dates(&text);
+    let text = expand_times(&text);
+    let text = ORDINAL_REGEX.replace_all(&text, |caps: &Captures| match parse_integer(&caps[1]) {
+        Some(n) => ordinal_to_words(n),
+        None => caps[0].to_string(),
+    });
+    let text = UNIT_REGEX.replace_all(&text, expand_unit);
+    let text = PHONE_REGEX.replace_all(&text, |caps: &Captures| {
+        (1..=4)
+            .filter_map(|i| caps.get(i))
+            .map(|m| digits_to_words(m.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    });
+    let text = DOTTED_REGEX.replace_all(&text, |caps: &Captures| {
+        caps[0]
+            .split('.')
+            .map(digits_to_words)
+            .collect::<Vec<_>>()
+            .join(" point ")
+    });
+    let text = RANGE_REGEX.replace_all(&text, |caps: &Captures| {
+        let value = |s: &str| s.replace(',', "").parse::<f64>().ok();
+        match (value(&caps[1]), value(&caps[2])) {
+            (Some(from), Some(to)) if from < to => {
+                format!(
+                    "{} to {}",
+                    cardinal_words(&caps[1]),
+                    cardinal_words(&caps[2])
+                )
+            }
+            _ => caps[0].to_string(),
+        }
+    });
+    let text = expand_fractions(&text);
+    let text = DECADE_REGEX.replace_all(&text, |caps: &Captures| {
+        let words = cardinal_words(&caps[1]);
+        match words.strip_suffix('y') {
+            Some(stem) => format!("{stem}ies"),
+            None => format!("{words}s"),
+        }
+    });
+    let text =
+        ALPHANUMERIC_REGEX.replace_all(&text, |caps: &Captures| alphanumeric_words(&caps[0]));
+    let text = NEGATIVE_REGEX.replace_all(&text, "${1}minus $2");
+    let text = text.replace('&', " and ");
+    let text = NUMBER_REGEX.replace_all(&text, |caps: &Captures| cardinal_words(&caps[0]));
+    text.split(' ')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Spell out a non-negative integer, e.g. `1250` -> "one thousand two hundred fifty"
+pub fn number_to_words(n: u64) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut groups = Vec::new();
+    let mut rest = n;
+    while rest > 0 {
+        groups.push((rest % 1000) as usize);
+        rest /= 1000;
+    }
+    let mut parts = Vec::new();
+    for (i, &group) in groups.iter().enumerate().rev() {
+        if group == 0 {
+            continue;
+        }
+        parts.push(hundreds_to_words(group));
+        if i > 0 {
+            parts.push(SCALES[i].to_string());
+        }
+    }
+    parts.join(" ")
+}
+
+/// Spell out an ordinal, e.g. `21` -> "twenty-first"
+pub fn ordinal_to_words(n: u64) -> String {
+    let words = number_to_words(n);
+    let split = words.rfind([' ', '-']).map_or(0, |i| i + 1);
+    let (head, last) = words.split_at(split);
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        w if w.ends_with('y') => format!("{}ieth", &w[..w.len() - 1]),
+        w => format!("{w}th"),
+    };
+    format!("{head}{last}")
+}
+
+/// Read a year the way it is spoken, e.g. `1999` -> "nineteen ninety-nine"
+pub fn year_to_words(year: u64) -> String {
+    let (hi, lo) = (year / 100, year % 100);
+    match year {
+        2000..=2009 => number_to_words(year),
+        1000..=9999 if year.is_multiple_of(1000) => number_to_words(year),
+        1000..=9999 if lo == 0 => format!("{} hundred", number_to_words(hi)),
+        1000..=9999 if lo < 10 => format!("{} oh {}", number_to_words(hi), ONES[lo as usize]),
+        1000..=9999 => format!("{} {}", number_to_words(hi), number_to_words(lo)),
+        _ => number_to_words(year),
+    }
+}
+
+fn hundreds_to_words(n: usize) -> String {
+    let mut parts = Vec::new();
+    if n >= 100 {
+        parts.push(format!("{} hundred", ONES[n / 100]));
+    }
+    let rest = n % 100;
+    if rest > 0 {
+        parts.push(tens_to_words(rest));
+    }
+    parts.join(" ")
+}
+
+fn tens_to_words(n: usize) -> String {
+    match n {
+        0..=19 => ONES[n].to_string(),
+        _ if n.is_multiple_of(10) => TENS[n / 10].to_string(),
+        _ => format!("{}-{}", TENS[n / 10], ONES[n % 10]),
+    }
+}
+
+/// Parse an integer with optional thousands separators
+fn parse_integer(s: &str) -> Option<u64> {
+    s.replace(',', "").parse().ok()
+}
+
+/// Read digits one by one, e.g. "14" -> "one four"
+pub(crate) fn digits_to_words(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| ONES[d as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Spell out a number, reading four-digit numbers that look like years as years
+fn cardinal_words(number: &str) -> String {
+    match parse_integer(number) {
+        Some(n) if number.len() == 4 && (1100..2100).contains(&n) => year_to_words(n),
+        _ => number_str_to_words(number),
+    }
+}
+
+/// Read a word mixing letters and digits: a leading number as a number and
+/// later digits one by one, e.g. "3.5x" -> "three point five x", "A380" ->
+/// "A three eight zero"
+fn alphanumeric_words(word: &str) -> String {
+    let number_len = word
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(word.len());
+    let mut parts = Vec::new();
+    if number_len > 0 {
+        parts.push(number_str_to_words(&word[..number_len]));
+    }
+    let rest = &word[number_len..];
+    let mut start = 0;
+    for (i, c) in rest.char_indices().skip(1) {
+        let prev = rest[..i].chars().last().unwrap_or(c);
+        if prev.is_ascii_digit() != c.is_ascii_digit() {
+            parts.push(rest[start..i].to_string());
+            start = i;
+        }
+    }
+    parts.push(rest[start..].to_string());
+    parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            if part.starts_with(|c: char| c.is_ascii_digit()) {
+                digits_to_words(&part)
+            } else {
+                part
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Spell out a number string such as "1,250" or "3.14"
+pub(crate) fn number_str_to_words(number: &str) -> String {
+    let (int_part, frac_part) = match number.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (number, None),
+    };
+    let int_words = match parse_integer(int_part) {
+        Some(n) => number_to_words(n),
+        None => digits_to_words(int_part),
+    };
+    match frac_part {
+        Some(frac) => format!("{} point {}", int_words, digits_to_words(frac)),
+        None => int_words,
+    }
+}
+
+/// Whether the text after `end` starts a new sentence (end of text or a capital)
+fn sentence_ends_at(text: &str, end: usize) -> bool {
+    text[end..]
+        .chars()
+        .find(|c| !c.is_whitespace())
+        .is_none_or(|c| c.is_uppercase())
+}
+
+/// Whether a period right after `word` marks a title, an initial or a dotted
+/// abbreviation rather than a sentence end, e.g. "Dr." in "Dr. Smith", "J." in
+/// "J. Smith" or the last period of "U.S."
+pub(crate) fn is_abbreviation_period(word: &str) -> bool {
+    let mut chars = word.chars();
+    let is_initial = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_uppercase());
+    is_initial || word == "St" || word.contains('.') || lookup(INLINE_ABBREVIATIONS, word).is_some()
+}
+
+fn expand_abbreviations(text: &str) -> String {
+    let text = INLINE_ABBREVIATION_REGEX.replace_all(text, |caps: &Captures| {
+        lookup(INLINE_ABBREVIATIONS, &caps[1])
+            .unwrap_or(&caps[0])
+            .to_string()
+    });
+    let text = LATIN_REGEX.replace_all(&text, |caps: &Captures| {
+        if caps.get(1).is_some() {
+            "for example,".to_string()
+        } else {
+            "that is,".to_string()
+        }
+    });
+
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in ABBREVIATION_REGEX.captures_iter(&text) {
+        let m = caps.get(0).unwrap();
+        let abbr = &caps[1];
+        let expansion = if abbr == "St" {
+            // "Main St." is a street, "St. Louis" a saint
+            let prev_word = text[..m.start()].split_whitespace().last();
+            if prev_word.is_some_and(|w| w.starts_with(char::is_uppercase)) {
+                "Street"
+            } else {
+                "Saint"
+            }
+        } else {
+            lookup(ABBREVIATIONS, abbr).unwrap_or(abbr)
+        };
+        out.push_str(&text[last..m.start()]);
+        out.push_str(expansion);
+        if expansion != "Saint" && sentence_ends_at(&text, m.end()) {
+            out.push('.');
+        }
+        last = m.end();
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+fn lookup<'a>(table: &[(&str, &'a str)], key: &str) -> Option<&'a str> {
+    table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn expand_currency(caps: &Captures) -> String {
+    let (unit, units, sub, subs) = match &caps[1] {
+        "$" => ("dollar", "dollars", "cent", "cents"),
+        "€" => ("euro", "euros", "cent", "cents"),
+        "£" => ("pound", "pounds", "penny", "pence"),
+        _ => ("yen", "yen", "", ""),
+    };
+    let amount = &caps[2];
+    let scale = caps.get(3).map(|m| m.as_str()).or(match caps.get(4) {
+        Some(m) if m.as_str() == "K" => Some("thousand"),
+        Some(m) if m.as_str() == "M" => Some("million"),
+        Some(_) => Some("billion"),
+        None => None,
+    });
+    if let Some(scale) = scale {
+        return format!("{} {} {}", number_str_to_words(amount), scale, units);
+    }
+
+    let amount = if amount.starts_with('.') {
+        format!("0{amount}")
+    } else {
+        amount.to_string()
+    };
+    let (int_part, frac_part) = match amount.split_once('.') {
+        Some((i, f)) => (i, Some(f)),
+        None => (amount.as_str(), None),
+    };
+    let Some(whole) = parse_integer(int_part) else {
+        return format!("{} {}", number_str_to_words(&amount), units);
+    };
+    // Only two decimals are cents; "$12.345" is a decimal amount
+    let cents = match frac_part {
+        None => 0,
+        Some(f) if f.len() == 2 && !sub.is_empty() => f.parse::<u64>().unwrap_or(0),
+        Some(_) => return format!("{} {}", number_str_to_words(&amount), units),
+    };
+
+    let whole_words = format!(
+        "{} {}",
+        number_to_words(whole),
+        if whole == 1 { unit } else { units }
+    );
+    let cents_words = format!(
+        "{} {}",
+        number_to_words(cents),
+        if cents == 1 { sub } else { subs }
+    );
+    match (whole, cents) {
+        (_, 0) => whole_words,
+        (0, _) => cents_words,
+        _ => format!("{whole_words} and {cents_words}"),
+    }
+}
+
+/// Spoken date, e.g. "March fourth, twenty twenty-five"
+pub(crate) fn date_words(month: u64, day: u64, year: Option<u64>) -> Option<String> {
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let month_day = format!("{} {}", MONTHS[month as usize - 1], ordinal_to_words(day));
+    Some(match year {
+        Some(year) => format!("{}, {}", month_day, year_to_words(year)),
+        None => month_day,
+    })
+}
+
+fn expand_dates(text: &str) -> String {
+    let text = SLASH_DATE_REGEX.replace_all(text, |caps: &Captures| {
+        let year = &caps[3];
+        let year_words = if year.len() == 2 {
+            number_to_words(year.parse().unwrap_or(0))
+        } else {
+            year_to_words(year.parse().unwrap_or(0))
+        };
+        match date_words(
+            caps[1].parse().unwrap_or(0),
+            caps[2].parse().unwrap_or(0),
+            None,
+        ) {
+            Some(words) => format!("{words}, {year_words}"),
+            None => caps[0].to_string(),
+        }
+    });
+    let text = ISO_DATE_REGEX.replace_all(&text, |caps: &Captures| {
+        date_words(
+            caps[2].parse().unwrap_or(0),
+            caps[3].parse().unwrap_or(0),
+            caps[1].parse().ok(),
+        )
+        .unwrap_or_else(|| caps[0].to_string())
+    });
+    MONTH_DAY_REGEX
+        .replace_all(&text, |caps: &Captures| {
+            format!(
+                "{} {}",
+                &caps[1],
+                ordinal_to_words(caps[2].parse().unwrap_or(0))
+            )
+        })
+        .into_owned()
+}
+
+/// "3/4" -> "three quarters", "1 1/2" -> "one and one half"; only proper
+/// fractions outside of longer slash-separated runs
+fn expand_fractions(text: &str) -> String {
+    FRACTION_REGEX
+        .replace_all(text, |caps: &Captures| {
+            let m = caps.get(0).unwrap();
+            let numerator: u64 = caps[2].parse().unwrap_or(0);
+            let denominator: u64 = caps[3].parse().unwrap_or(0);
+            let in_run = text[..m.start()].ends_with('/') || text[m.end()..].starts_with('/');
+            if in_run || numerator == 0 || numerator >= denominator {
+                return caps[0].to_string();
+            }
+            let plural = numerator > 1;
+            let unit = match denominator {
+                2 if plural => "halves".to_string(),
+                2 => "half".to_string(),
+                4 if plural => "quarters".to_string(),
+                4 => "quarter".to_string(),
+                _ if plural => format!("{}s", ordinal_to_words(denominator)),
+                _ => ordinal_to_words(denominator),
+            };
+            let fraction = format!("{} {}", number_to_words(numerator), unit);
+            match caps.get(1) {
+                Some(whole) => format!("{} and {}", number_str_to_words(whole.as_str()), fraction),
+                None => fraction,
+            }
+        })
+        .into_owned()
+}
+
+fn meridiem(caps: &Captures, group: usize) -> Option<&'static str> {
+    caps.get(group).map(|m| match m.as_str() {
+        "a" | "A" => "A M",
+        _ => "P M",
+    })
+}
+
+/// Re-append a sentence-final period swallowed by a trailing "a.m." / "p.m."
+fn keep_sentence_end(text: &str, caps: &Captures, words: String) -> String {
+    let m = caps.get(0).unwrap();
+    if m.as_str().ends_with('.') && sentence_ends_at(text, m.end()) {
+        format!("{words}.")
+    } else {
+        words
+    }
+}
+
+fn expand_times(text: &str) -> String {
+    let text = CLOCK_REGEX.replace_all(text, |caps: &Captures| {
+        let hour: u64 = caps[1].parse().unwrap_or(99);
+        let minute: u64 = caps
+            .get(2)
+            .or(caps.get(4))
+            .and_then(|m| m.as_str().parse().ok())
+            .unwrap_or(99);
+        if hour > 23 || minute > 59 {
+            return caps[0].to_string();
+        }
+        let suffix = meridiem(caps, 3).or(meridiem(caps, 5));
+        let minute_words = match minute {
+            0 if suffix.is_some() => String::new(),
+            0 if hour <= 12 => " o'clock".to_string(),
+            0 => " hundred".to_string(),
+            1..=9 => format!(" oh {}", ONES[minute as usize]),
+            _ => format!(" {}", tens_to_words(minute as usize)),
+        };
+        let words = match suffix {
+            Some(suffix) => format!("{}{} {}", number_to_words(hour), minute_words, suffix),
+            None => format!("{}{}", number_to_words(hour), minute_words),
+        };
+        keep_sentence_end(text, caps, words)
+    });
+    HOUR_REGEX
+        .replace_all(&text, |caps: &Captures| {
+            let hour: u64 = caps[1].parse().unwrap_or(99);
+            if hour > 12 {
+                return caps[0].to_string();
+            }
+            let words = format!(
+                "{} {}",
+                number_to_words(hour),
+                meridiem(caps, 2).unwrap_or_default()
+            );
+            keep_sentence_end(&text, caps, words)
+        })
+        .into_owned()
+}
+
+fn expand_unit(caps: &Captures) -> String {
+    let number = &caps[1];
+    let singular = number == "1";
+    let (one, many) = if let Some(symbol) = caps.get(2) {
+        // "5g" or "4m" is as likely a name as grams or meters
+        if symbol.len() == 1 && symbol.start() == caps.get(1).unwrap().end() {
+            return caps[0].to_string();
+        }
+        UNITS
+            .iter()
+            .find(|(s, _, _)| *s == symbol.as_str())
+            .map(|(_, one, many)| (*one, *many))
+            .unwrap_or(("", ""))
+    } else {
+        match caps.get(3).map(|m| m.as_str()) {
+            Some("C") => ("degree Celsius", "degrees Celsius"),
+            Some(_) => ("degree Fahrenheit", "degrees Fahrenheit"),
+            None => ("degree", "degrees"),
+        }
+    };
+    format!(
+        "{} {}",
+        number_str_to_words(number),
+        if singular { one } else { many }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_to_words() {
+        assert_eq!(number_to_words(0), "zero");
+        assert_eq!(number_to_words(13), "thirteen");
+        assert_eq!(number_to_words(42), "forty-two");
+        assert_eq!(number_to_words(100), "one hundred");
+        assert_eq!(number_to_words(1250), "one thousand two hundred fifty");
+        assert_eq!(number_to_words(2_000_019), "two million nineteen");
+    }
+
+    #[test]
+    fn test_ordinals_and_years() {
+        assert_eq!(ordinal_to_words(1), "first");
+        assert_eq!(ordinal_to_words(12), "twelfth");
+        assert_eq!(ordinal_to_words(20), "twentieth");
+        assert_eq!(ordinal_to_words(21), "twenty-first");
+        assert_eq!(ordinal_to_words(103), "one hundred third");
+        assert_eq!(year_to_words(1999), "nineteen ninety-nine");
+        assert_eq!(year_to_words(2005), "two thousand five");
+        assert_eq!(year_to_words(2025), "twenty twenty-five");
+        assert_eq!(year_to_words(1900), "nineteen hundred");
+        assert_eq!(year_to_words(1905), "nineteen oh five");
+    }
+
+    #[test]
+    fn test_request_example() {
+        assert_eq!(
+            normalize_text("$1,250 on 3/4/2025 at 5pm, Dr. Smith"),
+            "one thousand two hundred fifty dollars on March fourth, twenty twenty-five \
+             at five P M, Doctor Smith"
+        );
+    }
+
+    #[test]
+    fn test_currency() {
+        assert_eq!(normalize_text("$1"), "one dollar");
+        assert_eq!(
+            normalize_text("It costs $5.99."),
+            "It costs five dollars and ninety-nine cents."
+        );
+        assert_eq!(normalize_text("$0.50"), "fifty cents");
+        assert_eq!(normalize_text("£20"), "twenty pounds");
+        assert_eq!(
+            normalize_text("€2.5 million"),
+            "two point five million euros"
+        );
+        assert_eq!(normalize_text("$3M"), "three million dollars");
+    }
+
+    #[test]
+    fn test_numbers_and_percentages() {
+        assert_eq!(normalize_text("3.14"), "three point one four");
+        assert_eq!(normalize_text("45%"), "forty-five percent");
+        assert_eq!(
+            normalize_text("It was -5 outside"),
+            "It was minus five outside"
+        );
+        assert_eq!(
+            normalize_text("the 21st century"),
+            "the twenty-first century"
+        );
+        assert_eq!(normalize_text("in 1999"), "in nineteen ninety-nine");
+        assert_eq!(normalize_text("No. 7"), "number seven");
+        assert_eq!(normalize_text("1, 2, 3"), "one, two, three");
+    }
+
+    #[test]
+    fn test_dates_and_times() {
+        assert_eq!(
+            normalize_text("2025-03-04"),
+            "March fourth, twenty twenty-five"
+        );
+        assert_eq!(normalize_text("June 1st"), "June first");
+        assert_eq!(normalize_text("5:30pm"), "five thirty P M");
+        assert_eq!(normalize_text("at 9:05"), "at nine oh five");
+        assert_eq!(normalize_text("at 7:00"), "at seven o'clock");
+        assert_eq!(normalize_text("17:45"), "seventeen forty-five");
+        assert_eq!(
+            normalize_text("Meet at 5 p.m. Bring food."),
+            "Meet at five P M. Bring food."
+        );
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(normalize_text("5km"), "five kilometers");
+        assert_eq!(normalize_text("1 kg"), "one kilogram");
+        assert_eq!(normalize_text("60 mph"), "sixty miles per hour");
+        assert_eq!(normalize_text("20°C"), "twenty degrees Celsius");
+        assert_eq!(normalize_text("2.5 GB"), "two point five gigabytes");
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        assert_eq!(
+            normalize_text("Mr. and Mrs. Jones"),
+            "Mister and Missus Jones"
+        );
+        assert_eq!(normalize_text("St. Louis"), "Saint Louis");
+        assert_eq!(normalize_text("on Main St. today"), "on Main Street today");
+        assert_eq!(
+            normalize_text("apples, pears, etc. More later."),
+            "apples, pears, et cetera. More later."
+        );
+        assert_eq!(
+            normalize_text("fruit, e.g. apples"),
+            "fruit, for example, apples"
+        );
+        assert_eq!(normalize_text("salt & pepper"), "salt and pepper");
+        assert_eq!(normalize_text("Cats vs. Dogs"), "Cats versus Dogs");
+    }
+
+    #[test]
+    fn test_tokens_that_are_not_plain_numbers() {
+        assert_eq!(
+            normalize_text("version 1.5.2"),
+            "version one point five point two"
+        );
+        assert_eq!(normalize_text("3.5x faster"), "three point five x faster");
+        assert_eq!(normalize_text("at 7.30pm"), "at seven thirty P M");
+        assert_eq!(normalize_text("$.50"), "fifty cents");
+        assert_eq!(normalize_text("3/4 cup"), "three quarters cup");
+        assert_eq!(normalize_text("1 1/2 cups"), "one and one half cups");
+        assert_eq!(normalize_text("2/3"), "two thirds");
+        assert_eq!(
+            normalize_text("1990-2000"),
+            "nineteen ninety to two thousand"
+        );
+        assert_eq!(normalize_text("pages 10 - 20"), "pages ten to twenty");
+        assert_eq!(
+            normalize_text("call 555-1234"),
+            "call five five five, one two three four"
+        );
+        assert_eq!(normalize_text("5g network"), "five g network");
+        assert_eq!(normalize_text("5 g of salt"), "five grams of salt");
+        assert_eq!(normalize_text("the A380"), "the A three eight zero");
+        assert_eq!(normalize_text("the 1990s"), "the nineteen nineties");
+    }
+
+    #[test]
+    fn test_currency_decimals_beyond_cents() {
+        assert_eq!(
+            normalize_text("$12.345"),
+            "twelve point three four five dollars"
+        );
+        assert_eq!(normalize_text("$1.5"), "one point five dollars");
+    }
+
+    #[test]
+    fn test_plain_text_is_unchanged() {
+        let text = "Hello world! How are you today?";
+        assert_eq!(normalize_text(text), text);
+    }
+}
@@ -4,19 +4,43 @@
 //! matching Python's `pocket_tts/models/tts_model.py`.
 
 use crate::ModelState;
+use crate::codec::EncodedAudio;
 use crate::conditioners::text::LUTConditioner;
 use crate::config::{Config, defaults, load_config};
+use crate::generation::{
+    CandidateScore, CandidateStats, GenerationEvent, GenerationEventKind, GenerationOptions,
+    GenerationProgress, InputFormat, LatentRateResampler, MAX_SPEED, MAX_TOKENS_PER_CHUNK,
+    MIN_SPEED, best_candidate,
+};
+use crate::markdown::MarkdownItem;
 use crate::models::flow_lm::FlowLMModel;
 use crate::models::mimi::MimiModel;
 use crate::models::seanet::{SEANetDecoder, SEANetEncoder};
 use crate::models::transformer::{ProjectedTransformer, StreamingTransformer};
 use crate::modules::mlp::SimpleMLPAdaLN;
-use crate::voice_state::{increment_steps, init_states, ATTN_POS_KEY, ATTN_LEN_KEY, ATTN_K_BUF_KEY, ATTN_V_BUF_KEY};
-use std::collections::HashMap;
+use crate::sentence::{DefaultSentenceSplitter, SentenceSplitter};
+use crate::ssml::{Prosody, SsmlItem};
+use crate::text_coverage::{
+    ChunkAnalysis, TextAnalysis, UnsupportedChar, UnsupportedCharPolicy, chars_in_spans,
+    replace_unsupported, transliterate,
+};
+use crate::text_normalize::normalize_text;
+use crate::voice::{FileVoiceResolver, VoiceResolver};
+use crate::voice_clips::VoiceClipJoin;
+use crate::voice_preprocess::VoicePreprocessing;
+use crate::voice_prompt::{VoicePrompt, VoicePromptFormat, VoicePromptMetadata};
+use crate::voice_state::{increment_steps, init_states};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
-use candle_core::{DType, Device, Tensor};
+use candle_core::{D, DType, Device, Tensor};
 use candle_nn::VarBuilder;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+/// Latent frames decoded through Mimi at a time by [`TTSModel::decode_audio`]
+const CODEC_DECODE_CHUNK_FRAMES: usize = 25;
 
 /// Main TTS model that orchestrates the entire pipeline
 #[derive(Clone)]
@@ -39,6 +63,14 @@ pub struct TTSModel {
     /// Optional override for voice-conditioning Mimi chunk size (in frames).
     /// If `None`, an adaptive heuristic is used.
     pub voice_prompt_chunk_frames: Option<usize>,
+    /// Splits long texts into sentence chunks
+    pub sentence_splitter: Arc<dyn SentenceSplitter>,
+    /// Resolves inline `[voice:...]` markers
+    pub voice_resolver: Arc<dyn VoiceResolver>,
+    /// Model variant (e.g. "b6369a24"), when the config names one
+    pub variant: Option<String>,
+    /// Cleanup applied to reference audio before voice cloning
+    pub voice_preprocessing: Option<VoicePreprocessing>,
     /// Sample rate
     pub sample_rate: usize,
     /// Model dimension
@@ -319,8 +351,7 @@ impl TTSModel {
             vb.pp("flow_lm.transformer"),
         )?;
 
-        let mut flow_lm = FlowLMModel::new(flow_net, transformer, ldim, dim, vb.pp("flow_lm"))?;
-        flow_lm.noise_clamp = noise_clamp;
+        let flow_lm = FlowLMModel::new(flow_net, transformer, ldim, dim, vb.pp("flow_lm"))?;
 
         // Build Mimi components
         let seanet_cfg = &config.mimi.seanet;
@@ -419,6 +450,10 @@ impl TTSModel {
             eos_threshold,
             noise_clamp,
             voice_prompt_chunk_frames: None,
+            sentence_splitter: Arc::new(DefaultSentenceSplitter::new()),
+            voice_resolver: Arc::new(FileVoiceResolver),
+            variant: config.variant(),
+            voice_preprocessing: None,
             sample_rate: config.mimi.sample_rate,
             dim,
             ldim,
@@ -429,132 +464,154 @@ impl TTSModel {
     /// Create voice state from audio prompt bytes for voice cloning
     pub fn get_voice_state_from_bytes(&self, bytes: &[u8]) -> Result<ModelState> {
         let (audio, sample_rate) = crate::audio::read_wav_from_bytes(bytes)?;
+        let audio = self.prepare_voice_audio(&audio, sample_rate)?;
+        self.get_voice_state_from_tensor(&audio)
+    }
 
+    /// Resample reference audio (`[C, T]`) to the model rate, apply the
+    /// voice preprocessing if enabled and add the batch dimension
+    pub fn prepare_voice_audio(&self, audio: &Tensor, sample_rate: u32) -> Result<Tensor> {
         // Resample to model sample rate if needed
         let audio = if sample_rate != self.sample_rate as u32 {
-            crate::audio::resample(&audio, sample_rate, self.sample_rate as u32)?
+            crate::audio::resample(audio, sample_rate, self.sample_rate as u32)?
         } else {
-            audio
+            audio.clone()
+        };
+
+        let audio = match &self.voice_preprocessing {
+            Some(preprocessing) => preprocessing.apply(&audio, self.sample_rate as u32)?,
+            None => audio,
         };
+        if audio.dim(D::Minus1)? == 0 {
+            anyhow::bail!("Reference audio is empty");
+        }
 
         // Add batch dimension: [C, T] -> [B, C, T]
-        let audio = audio.unsqueeze(0)?;
+        Ok(audio.unsqueeze(0)?)
+    }
+
+    /// Prepare several reference clips (`[C, T]` at their own sample rates)
+    /// of one speaker as a single `[1, 1, T]` prompt
+    ///
+    /// Every clip goes through [`Self::prepare_voice_audio`]; `join` then
+    /// levels and joins them.
+    pub fn prepare_voice_clips(
+        &self,
+        clips: &[(Tensor, u32)],
+        join: &VoiceClipJoin,
+    ) -> Result<Tensor> {
+        let mut prepared = Vec::with_capacity(clips.len());
+        for (audio, sample_rate) in clips {
+            let audio = self.prepare_voice_audio(audio, *sample_rate)?.squeeze(0)?;
+            prepared.push((audio, self.sample_rate as u32));
+        }
+        Ok(join
+            .join(&prepared, self.sample_rate as u32)?
+            .unsqueeze(0)?)
+    }
 
+    /// Create voice state from several reference clips of one speaker
+    ///
+    /// The clips are joined with [`VoiceClipJoin::default`].
+    pub fn get_voice_state_from_clips(&self, clips: &[(Tensor, u32)]) -> Result<ModelState> {
+        let audio = self.prepare_voice_clips(clips, &VoiceClipJoin::default())?;
         self.get_voice_state_from_tensor(&audio)
     }
 
+    /// Create voice state from several WAV files of one speaker
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn save_audio_as_voice_prompt<P: AsRef<std::path::Path>>(
+    pub fn get_voice_state_from_files<P: AsRef<std::path::Path>>(
+        &self,
+        audio_paths: &[P],
+    ) -> Result<ModelState> {
+        self.get_voice_state_from_clips(&read_voice_clips(audio_paths)?)
+    }
+
+    /// Encode several WAV files of one speaker into a voice prompt of the
+    /// given format
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn create_voice_prompt_from_files<P: AsRef<std::path::Path>>(
+        &self,
+        audio_paths: &[P],
+        format: VoicePromptFormat,
+    ) -> Result<VoicePrompt> {
+        let audio =
+            self.prepare_voice_clips(&read_voice_clips(audio_paths)?, &VoiceClipJoin::default())?;
+        self.create_voice_prompt_from_tensor(&audio, format)
+    }
+
+    /// Encode a WAV file into a voice prompt of the given format
+    ///
+    /// Save it with [`VoicePrompt::save`] and load it back with
+    /// [`Self::get_voice_state_from_prompt_file`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn create_voice_prompt<P: AsRef<std::path::Path>>(
         &self,
         audio_path: P,
-        safetensors_path: P,
-    ) -> Result<()> {
+        format: VoicePromptFormat,
+    ) -> Result<VoicePrompt> {
         let (audio, sample_rate) = crate::audio::read_wav(audio_path)?;
+        let audio = self.prepare_voice_audio(&audio, sample_rate)?;
+        let mut prompt = self.create_voice_prompt_from_tensor(&audio, format)?;
+        prompt.metadata.source_sample_rate = Some(sample_rate);
+        Ok(prompt)
+    }
 
-        // Resample to model sample rate if needed
-        let audio = if sample_rate != self.sample_rate as u32 {
-            crate::audio::resample(&audio, sample_rate, self.sample_rate as u32)?
-        } else {
-            audio
+    /// Encode audio (`[B, C, T]` at the model sample rate) into a voice
+    /// prompt of the given format
+    pub fn create_voice_prompt_from_tensor(
+        &self,
+        audio: &Tensor,
+        format: VoicePromptFormat,
+    ) -> Result<VoicePrompt> {
+        let metadata = VoicePromptMetadata {
+            source_sample_rate: Some(self.sample_rate as u32),
+            duration_secs: Some(audio.dim(D::Minus1)? as f32 / self.sample_rate as f32),
+            ..VoicePromptMetadata::new(self.variant.clone())
         };
+        match format {
+            VoicePromptFormat::AudioPrompt => Ok(VoicePrompt::from_conditioning(
+                self.get_conditioning(audio)?,
+                metadata,
+            )),
+            _ => {
+                let voice_state = self.get_voice_state_from_tensor(audio)?;
+                VoicePrompt::from_voice_state(&voice_state, format, metadata)
+            }
+        }
+    }
 
-        // Add batch dimension: [C, T] -> [B, C, T]
-        let audio = audio.unsqueeze(0)?;
-
-        let conditioning = self.get_conditioning(&audio)?;
-        let data = HashMap::from([("audio_prompt", conditioning)]);
-        candle_core::safetensors::save(&data, safetensors_path)?;
-
-        Ok(())
+    /// Save a WAV file as a v1 (`audio_prompt`) voice prompt
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_audio_as_voice_prompt<P: AsRef<std::path::Path>>(
+        &self,
+        audio_path: P,
+        safetensors_path: P,
+    ) -> Result<()> {
+        self.create_voice_prompt(audio_path, VoicePromptFormat::AudioPrompt)?
+            .save(safetensors_path)
     }
 
+    /// Save a WAV file as a v2 (KV cache with `current_end`) voice prompt
     #[cfg(not(target_arch = "wasm32"))]
     pub fn save_audio_as_voice_prompt_v2<P: AsRef<std::path::Path>>(
         &self,
         audio_path: P,
         safetensors_path: P,
     ) -> Result<()> {
-        let (audio, sample_rate) = crate::audio::read_wav(audio_path)?;
-
-        // Resample to model sample rate if needed
-        let audio = if sample_rate != self.sample_rate as u32 {
-            crate::audio::resample(&audio, sample_rate, self.sample_rate as u32)?
-        } else {
-            audio
-        };
-
-        // Add batch dimension: [C, T] -> [B, C, T]
-        let audio = audio.unsqueeze(0)?;
-
-        let voice_state = self.get_voice_state_from_tensor(&audio)?;
-        
-        let mut flat_map: HashMap<String, Tensor> = HashMap::new();
-        
-        for (outer_key, inner_map) in voice_state {
-            let offset_scalar = inner_map[ATTN_POS_KEY]
-                .to_device(&Device::Cpu)?
-                .to_scalar::<u32>()? as usize;
-            let offset = Tensor::zeros(offset_scalar, DType::F32, &Device::Cpu)?;
-            let k_buf = inner_map[ATTN_K_BUF_KEY].to_device(&Device::Cpu)?;
-            let v_buf = inner_map[ATTN_V_BUF_KEY].to_device(&Device::Cpu)?;
-            let cache = pack_kv_cache(&k_buf, &v_buf, offset_scalar)?;
-            let new_outer_key = outer_key
-                .strip_prefix("flow_lm.")
-                .ok_or_else(|| anyhow::anyhow!("Expected prefix 'flow_lm.' not found in key: {}", outer_key))?;
-            
-            flat_map.insert(format!("{}/{}", new_outer_key, "cache"), cache);
-            flat_map.insert(format!("{}/{}", new_outer_key, "current_end"), offset);
-        }
-        
-        candle_core::safetensors::save(&flat_map, safetensors_path)?;
-
-        Ok(())
+        self.create_voice_prompt(audio_path, VoicePromptFormat::KvCacheCurrentEnd)?
+            .save(safetensors_path)
     }
 
+    /// Save a WAV file as a v3 (KV cache with `offset`) voice prompt
     #[cfg(not(target_arch = "wasm32"))]
     pub fn save_audio_as_voice_prompt_v3<P: AsRef<std::path::Path>>(
         &self,
         audio_path: P,
         safetensors_path: P,
     ) -> Result<()> {
-        let (audio, sample_rate) = crate::audio::read_wav(audio_path)?;
-
-        // Resample to model sample rate if needed
-        let audio = if sample_rate != self.sample_rate as u32 {
-            crate::audio::resample(&audio, sample_rate, self.sample_rate as u32)?
-        } else {
-            audio
-        };
-
-        // Add batch dimension: [C, T] -> [B, C, T]
-        let audio = audio.unsqueeze(0)?;
-
-        let voice_state = self.get_voice_state_from_tensor(&audio)?;
-             
-        let mut flat_map: HashMap<String, Tensor> = HashMap::new();
-
-        for (outer_key, inner_map) in voice_state {
-            let offset = inner_map[ATTN_POS_KEY]
-                .to_dtype(DType::I64)?
-                .unsqueeze(0)?
-                .to_device(&Device::Cpu)?;
-            let offset_scalar = inner_map[ATTN_POS_KEY]
-                .to_device(&Device::Cpu)?
-                .to_scalar::<u32>()? as usize;
-            let k_buf = inner_map[ATTN_K_BUF_KEY].to_device(&Device::Cpu)?;
-            let v_buf = inner_map[ATTN_V_BUF_KEY].to_device(&Device::Cpu)?;
-            let cache = pack_kv_cache(&k_buf, &v_buf, offset_scalar)?;
-            let new_outer_key = outer_key
-                .strip_prefix("flow_lm.")
-                .ok_or_else(|| anyhow::anyhow!("Expected prefix 'flow_lm.' not found in key: {}", outer_key))?;
-
-            flat_map.insert(format!("{}/{}", new_outer_key, "cache"), cache);
-            flat_map.insert(format!("{}/{}", new_outer_key, "offset"), offset);
-        }
-
-        candle_core::safetensors::save(&flat_map, safetensors_path)?;
-
-        Ok(())
+        self.create_voice_prompt(audio_path, VoicePromptFormat::KvCacheOffset)?
+            .save(safetensors_path)
     }
 
     /// Create voice state from audio prompt for voice cloning
@@ -563,144 +620,79 @@ impl TTSModel {
     #[cfg(not(target_arch = "wasm32"))]
     pub fn get_voice_state<P: AsRef<std::path::Path>>(&self, audio_path: P) -> Result<ModelState> {
         let (audio, sample_rate) = crate::audio::read_wav(audio_path)?;
-
-        // Resample to model sample rate if needed
-        let audio = if sample_rate != self.sample_rate as u32 {
-            crate::audio::resample(&audio, sample_rate, self.sample_rate as u32)?
-        } else {
-            audio
-        };
-
-        // Add batch dimension: [C, T] -> [B, C, T]
-        let audio = audio.unsqueeze(0)?;
-
+        let audio = self.prepare_voice_audio(&audio, sample_rate)?;
         self.get_voice_state_from_tensor(&audio)
     }
 
-    /// Create voice state from a pre-calculated latent prompt file (.safetensors)
+    /// Create voice state from a voice prompt file (.safetensors) of any
+    /// format
     #[cfg(not(target_arch = "wasm32"))]
     pub fn get_voice_state_from_prompt_file<P: AsRef<std::path::Path>>(
         &self,
         path: P,
     ) -> Result<ModelState> {
-        let tensors = candle_core::safetensors::load(path, &self.device)?;
-        let prompt = tensors
-            .get("audio_prompt")
-            .ok_or_else(|| anyhow::anyhow!("'audio_prompt' not found in safetensors file"))?;
-
-        self.get_voice_state_from_prompt_tensor(prompt)
+        self.get_voice_state_from_voice_prompt(&VoicePrompt::load(path)?)
     }
 
-    /// Create voice state from a pre-calculated latent prompt file (.safetensors)
+    /// Create voice state from a v2 voice prompt file
     #[cfg(not(target_arch = "wasm32"))]
+    #[deprecated(note = "use `get_voice_state_from_prompt_file`, which detects the format")]
     pub fn get_voice_state_from_prompt_file_v2<P: AsRef<std::path::Path>>(
         &self,
         path: P,
     ) -> Result<ModelState> {
-        let tensors = candle_core::safetensors::load(path, &self.device)?;
-        
-        let n = tensors.len();
-        
-        if n % 2 != 0 {
-            return Err(anyhow::anyhow!(
-                "Expected an even number of tensors in the map, but found {}.",
-                n
-            ));
-        }
-    
-        let mut flow_state = init_states(1, 1000);
-    
-        let num_layers = n / 2;
-    
-        for x in 0..num_layers {
-            let cache_name = format!("transformer.layers.{}.self_attn/cache", x);
-            let offset_name = format!("transformer.layers.{}.self_attn/current_end", x);
-    
-            let cache = tensors
-                .get(&cache_name)
-                .ok_or_else(|| anyhow::anyhow!("Missing expected tensor: {}", cache_name))?;
-                
-            let offset = tensors
-                .get(&offset_name)
-                .ok_or_else(|| anyhow::anyhow!("Missing expected tensor: {}", offset_name))?;
-    
-            let (k_buf, v_buf) = unpack_kv_cache(&cache)?;
-            let offset_len = offset.dim(0)?;
-            let attn_pos = Tensor::new(offset_len as u32, &self.device)?;
-            let attn_len = Tensor::new(offset_len as i64, &self.device)?;
-            
-            let mut layer_weights = HashMap::new();
-            layer_weights.insert(ATTN_K_BUF_KEY.to_string(), k_buf);
-            layer_weights.insert(ATTN_V_BUF_KEY.to_string(), v_buf);
-            layer_weights.insert(ATTN_POS_KEY.to_string(), attn_pos);
-            layer_weights.insert(ATTN_LEN_KEY.to_string(), attn_len);
-
-            let layer_name = format!("flow_lm.transformer.layers.{}.self_attn", x);
-            flow_state.insert(layer_name, layer_weights);
-        }
-    
-        Ok(flow_state)
+        self.get_voice_state_from_prompt_file(path)
     }
 
-    /// Create voice state from a pre-calculated latent prompt file (.safetensors)
+    /// Create voice state from a v3 voice prompt file
     #[cfg(not(target_arch = "wasm32"))]
+    #[deprecated(note = "use `get_voice_state_from_prompt_file`, which detects the format")]
     pub fn get_voice_state_from_prompt_file_v3<P: AsRef<std::path::Path>>(
         &self,
         path: P,
     ) -> Result<ModelState> {
-        let tensors = candle_core::safetensors::load(path, &self.device)?;
-        
-        let n = tensors.len();
-        
-        if n % 2 != 0 {
-            return Err(anyhow::anyhow!(
-                "Expected an even number of tensors in the map, but found {}.",
-                n
-            ));
-        }
-    
-        let mut flow_state = init_states(1, 1000);
-    
-        let num_layers = n / 2;
-    
-        for x in 0..num_layers {
-            let cache_name = format!("transformer.layers.{}.self_attn/cache", x);
-            let offset_name = format!("transformer.layers.{}.self_attn/offset", x);
-    
-            let cache = tensors
-                .get(&cache_name)
-                .ok_or_else(|| anyhow::anyhow!("Missing expected tensor: {}", cache_name))?;
-                
-            let offset = tensors
-                .get(&offset_name)
-                .ok_or_else(|| anyhow::anyhow!("Missing expected tensor: {}", offset_name))?
-                .squeeze(0)?;
-    
-            let (k_buf, v_buf) = unpack_kv_cache(&cache)?;
-            let attn_pos = Tensor::new(offset.to_dtype(DType::U32)?.to_scalar::<u32>()?, &self.device)?;
-            let attn_len = Tensor::new(offset.to_scalar::<i64>()?, &self.device)?;
-            
-            let mut layer_weights = HashMap::new();
-            layer_weights.insert(ATTN_K_BUF_KEY.to_string(), k_buf);
-            layer_weights.insert(ATTN_V_BUF_KEY.to_string(), v_buf);
-            layer_weights.insert(ATTN_POS_KEY.to_string(), attn_pos);
-            layer_weights.insert(ATTN_LEN_KEY.to_string(), attn_len);
-
-            let layer_name = format!("flow_lm.transformer.layers.{}.self_attn", x);
-            flow_state.insert(layer_name, layer_weights);
-        }
-    
-        Ok(flow_state)
+        self.get_voice_state_from_prompt_file(path)
     }
 
-    /// Create voice state from pre-calculated latent prompt bytes (.safetensors)
+    /// Create voice state from voice prompt bytes (.safetensors) of any format
     pub fn get_voice_state_from_prompt_bytes(&self, bytes: &[u8]) -> Result<ModelState> {
-        let tensors = candle_core::safetensors::load_buffer(bytes, &self.device)?;
-        let prompt = tensors
-            .get("audio_prompt")
-            .ok_or_else(|| anyhow::anyhow!("'audio_prompt' not found in safetensors bytes"))?;
+        self.get_voice_state_from_voice_prompt(&VoicePrompt::from_bytes(bytes)?)
+    }
+
+    /// Create voice state from a voice prompt
+    ///
+    /// Fails when the prompt was encoded by a different model variant.
+    pub fn get_voice_state_from_voice_prompt(&self, prompt: &VoicePrompt) -> Result<ModelState> {
+        self.check_voice_prompt(prompt)?;
+        match prompt.conditioning() {
+            Some(conditioning) => self.get_voice_state_from_prompt_tensor(conditioning),
+            None => prompt.kv_cache_state(&self.device),
+        }
+    }
 
-        self.get_voice_state_from_prompt_tensor(prompt)
+    /// Fail when `prompt` was encoded by a different model variant or for a
+    /// different model width
+    pub fn check_voice_prompt(&self, prompt: &VoicePrompt) -> Result<()> {
+        if let (Some(expected), Some(actual)) = (&self.variant, &prompt.metadata.model_variant)
+            && expected != actual
+        {
+            anyhow::bail!(
+                "Voice prompt was created with model variant '{}' but this model is '{}'; \
+                 re-create the prompt from the reference audio",
+                actual,
+                expected
+            );
+        }
+        let dim = prompt.feature_dim()?;
+        if dim != self.dim {
+            anyhow::bail!(
+                "Voice prompt has {} features per frame but this model expects {}; \
+                 it was created with a different model",
+                dim,
+                self.dim
+            );
+        }
+        Ok(())
     }
 
     /// Create voice state from a pre-calculated latent prompt tensor
@@ -718,6 +710,22 @@ impl TTSModel {
     }
 
     pub fn get_conditioning(&self, audio: &Tensor) -> Result<Tensor> {
+        let encoded = self.encode_mimi_latents(audio)?;
+
+        // Transpose from [B, D, T] to [B, T, D]
+        let latents = encoded.transpose(1, 2)?;
+
+        // Project to flow model space: [B, T, ldim] @ [dim, ldim].T -> [B, T, dim]
+        // Candle needs 2D @ 2D for matmul, so reshape
+        let (b, t, d) = latents.dims3()?;
+        let latents_2d = latents.reshape((b * t, d))?;
+        let conditioning_2d = latents_2d.matmul(&self.speaker_proj_weight.t()?)?;
+        let conditioning = conditioning_2d.reshape((b, t, self.dim))?;
+        Ok(conditioning)
+    }
+
+    /// Encode audio `[B, C, T]` through the Mimi encoder into latents `[B, D, frames]`
+    fn encode_mimi_latents(&self, audio: &Tensor) -> Result<Tensor> {
         let mut model_state = init_states(1, 1000);
 
         // Ensure audio tensor is on the same device as the model (fixes Metal device mismatch)
@@ -757,17 +765,67 @@ impl TTSModel {
             encoded_chunks.push(code);
         }
         let encoded = Tensor::cat(&encoded_chunks, 2)?;
+        Ok(encoded.to_dtype(DType::F32)?)
+    }
 
-        // Transpose from [B, D, T] to [B, T, D]
-        let latents = encoded.transpose(1, 2)?.to_dtype(DType::F32)?;
+    /// Encode arbitrary audio `[channels, samples]` with the Mimi codec
+    ///
+    /// Multi-channel audio is mixed down to mono and resampled to the model
+    /// rate. Store the result with [`EncodedAudio::write`] and turn it back into
+    /// audio with [`Self::decode_audio`].
+    pub fn encode_audio(&self, audio: &Tensor, sample_rate: u32) -> Result<EncodedAudio> {
+        let audio = if audio.dims()[0] > 1 {
+            audio.mean_keepdim(0)?
+        } else {
+            audio.clone()
+        };
+        let audio = if sample_rate != self.sample_rate as u32 {
+            crate::audio::resample(&audio, sample_rate, self.sample_rate as u32)?
+        } else {
+            audio
+        };
+        let num_samples = audio.dims()[1];
+        if num_samples == 0 {
+            anyhow::bail!("Cannot encode empty audio");
+        }
 
-        // Project to flow model space: [B, T, ldim] @ [dim, ldim].T -> [B, T, dim]
-        // Candle needs 2D @ 2D for matmul, so reshape
-        let (b, t, d) = latents.dims3()?;
-        let latents_2d = latents.reshape((b * t, d))?;
-        let conditioning_2d = latents_2d.matmul(&self.speaker_proj_weight.t()?)?;
-        let conditioning = conditioning_2d.reshape((b, t, self.dim))?;
-        Ok(conditioning)
+        let encoded = self.encode_mimi_latents(&audio.unsqueeze(0)?)?;
+        // [1, D, frames] -> [frames, D]
+        let latents = encoded.squeeze(0)?.t()?.contiguous()?;
+        Ok(EncodedAudio {
+            sample_rate: self.sample_rate as u32,
+            frame_rate: self.mimi.frame_rate as f32,
+            num_samples: num_samples as u64,
+            latents,
+        })
+    }
+
+    /// Decode Mimi codec latents back to audio `[channels, samples]`
+    pub fn decode_audio(&self, encoded: &EncodedAudio) -> Result<Tensor> {
+        encoded.check_decodable(self.sample_rate as u32, self.mimi.output_dimension)?;
+
+        // [frames, D] -> [1, D, frames]
+        let latents = encoded
+            .latents
+            .to_device(&self.device)?
+            .t()?
+            .unsqueeze(0)?
+            .contiguous()?;
+        let mut mimi_state = init_states(1, 1000);
+        let mut audio_chunks = Vec::new();
+        let num_frames = encoded.num_frames();
+        for start in (0..num_frames).step_by(CODEC_DECODE_CHUNK_FRAMES) {
+            let len = CODEC_DECODE_CHUNK_FRAMES.min(num_frames - start);
+            let chunk = latents.narrow(2, start, len)?;
+            audio_chunks.push(
+                self.mimi
+                    .decode_from_latent(&chunk, &mut mimi_state, start)?,
+            );
+        }
+        let audio = Tensor::cat(&audio_chunks, 2)?.squeeze(0)?;
+
+        let num_samples = (encoded.num_samples as usize).min(audio.dims()[1]);
+        Ok(audio.narrow(1, 0, num_samples)?)
     }
 
     /// Create voice state from audio tensor
@@ -820,98 +878,86 @@ impl TTSModel {
         Ok(())
     }
 
-    /// Split text into optimal chunks for generation, matching Python's logic exactly.
-    /// Uses actual tokenization to ensure chunks never exceed MAX_TOKENS_PER_CHUNK (50).
-    /// This prevents O(N²) attention complexity for long texts.
+    /// Split text into sentence chunks for generation with the model's
+    /// [`SentenceSplitter`]. Chunks never exceed MAX_TOKENS_PER_CHUNK (50)
+    /// tokens, which prevents O(N²) attention complexity for long texts.
     pub fn split_into_best_sentences(&self, text: &str) -> Vec<String> {
-        const MAX_TOKENS_PER_CHUNK: usize = 50;
+        self.split_into_best_sentences_with_max_tokens(text, MAX_TOKENS_PER_CHUNK)
+    }
 
+    /// Split text into chunks of at most `max_tokens` tokens each.
+    pub fn split_into_best_sentences_with_max_tokens(
+        &self,
+        text: &str,
+        max_tokens: usize,
+    ) -> Vec<String> {
+        let max_tokens = max_tokens.max(1);
         let prepared_text = prepare_text_prompt(text);
-
-        // 1. Initial split by punctuation to respect sentence boundaries
-        let raw_sentences: Vec<&str> = prepared_text
-            .split_inclusive(&['.', '!', '?', ';', ':'])
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        if raw_sentences.is_empty() {
-            return vec![prepared_text];
+        let count_tokens = |s: &str| self.conditioner.count_tokens(s).unwrap_or(max_tokens);
+        let chunks = self
+            .sentence_splitter
+            .chunks(&prepared_text, max_tokens, &count_tokens);
+        if chunks.is_empty() {
+            vec![prepared_text]
+        } else {
+            chunks
         }
+    }
 
-        let mut chunks = Vec::new();
-        let mut current_chunk = String::new();
-        let mut current_token_count = 0;
-
-        for sentence in raw_sentences {
-            let sentence_tokens = self
-                .conditioner
-                .count_tokens(sentence)
-                .unwrap_or(MAX_TOKENS_PER_CHUNK);
-
-            // If a single sentence exceeds max tokens, split it by words
-            if sentence_tokens > MAX_TOKENS_PER_CHUNK {
-                // Flush pending chunk first
-                if !current_chunk.is_empty() {
-                    chunks.push(current_chunk);
-                    current_chunk = String::new();
-                    current_token_count = 0;
-                }
+    /// Use `splitter` to split long texts into sentence chunks
+    pub fn with_sentence_splitter(mut self, splitter: impl SentenceSplitter + 'static) -> Self {
+        self.sentence_splitter = Arc::new(splitter);
+        self
+    }
 
-                // Split long sentence using word-batch estimation (~1.3 tokens per word average)
-                // This avoids calling count_tokens for every word (expensive!)
-                let words: Vec<&str> = sentence.split_whitespace().collect();
-                const WORDS_PER_BATCH: usize = 35; // ~45 tokens, safe margin under 50
-
-                for word_batch in words.chunks(WORDS_PER_BATCH) {
-                    let chunk_str = word_batch.join(" ");
-                    // Verify this batch is actually under limit (should almost always pass)
-                    let actual_tokens = self
-                        .conditioner
-                        .count_tokens(&chunk_str)
-                        .unwrap_or(MAX_TOKENS_PER_CHUNK);
-
-                    if actual_tokens <= MAX_TOKENS_PER_CHUNK {
-                        chunks.push(chunk_str);
-                    } else {
-                        // Rare case: batch still too big, split in half recursively
-                        let mid = word_batch.len() / 2;
-                        chunks.push(word_batch[..mid].join(" "));
-                        chunks.push(word_batch[mid..].join(" "));
-                    }
-                }
-                continue;
-            }
+    /// Use `resolver` for inline `[voice:...]` markers
+    pub fn with_voice_resolver(mut self, resolver: impl VoiceResolver + 'static) -> Self {
+        self.voice_resolver = Arc::new(resolver);
+        self
+    }
 
-            // Normal accumulation logic
-            if current_chunk.is_empty() {
-                current_chunk = sentence.to_string();
-                current_token_count = sentence_tokens;
-            } else if current_token_count + sentence_tokens > MAX_TOKENS_PER_CHUNK {
-                chunks.push(current_chunk);
-                current_chunk = sentence.to_string();
-                current_token_count = sentence_tokens;
-            } else {
-                current_chunk.push(' ');
-                current_chunk.push_str(sentence);
-                current_token_count += sentence_tokens;
-            }
-        }
+    /// Clean up reference audio before cloning voices from it; `None` feeds
+    /// it to Mimi as recorded
+    pub fn with_voice_preprocessing(mut self, preprocessing: Option<VoicePreprocessing>) -> Self {
+        self.voice_preprocessing = preprocessing;
+        self
+    }
 
-        if !current_chunk.is_empty() {
-            chunks.push(current_chunk);
+    /// Generation options matching the parameters this model was loaded with
+    pub fn generation_options(&self) -> GenerationOptions {
+        GenerationOptions {
+            temperature: self.temp,
+            lsd_decode_steps: self.lsd_decode_steps,
+            eos_threshold: self.eos_threshold,
+            noise_clamp: self.noise_clamp,
+            ..GenerationOptions::default()
         }
-
-        chunks
     }
 
     /// Generate audio from text with voice state
     pub fn generate(&self, text: &str, voice_state: &ModelState) -> Result<Tensor> {
+        self.generate_with_options(text, voice_state, &self.generation_options())
+    }
+
+    /// Generate audio from text with voice state using per-call options
+    ///
+    /// With `options.seed` set, the same text, voice state and options always
+    /// produce the same waveform; with `None` a fresh entropy-seeded RNG is
+    /// used.
+    pub fn generate_with_options(
+        &self,
+        text: &str,
+        voice_state: &ModelState,
+        options: &GenerationOptions,
+    ) -> Result<Tensor> {
         let mut audio_chunks = Vec::new();
 
-        for chunk in self.generate_stream(text, voice_state) {
+        for chunk in self.generate_stream_with_options(text, voice_state, options) {
             audio_chunks.push(chunk?);
         }
+        if options.is_cancelled() {
+            anyhow::bail!("Generation cancelled");
+        }
 
         // Concatenate all audio chunks
         if audio_chunks.is_empty() {
@@ -924,6 +970,129 @@ impl TTSModel {
         Ok(audio)
     }
 
+    /// Generate FlowLM latents from text without decoding them to audio
+    ///
+    /// Returns normalized latents `[frames, ldim]` at the model frame rate
+    /// (12.5 Hz). Pass them to [`Self::decode_latents`] to get the waveform.
+    pub fn generate_latents(&self, text: &str, voice_state: &ModelState) -> Result<Tensor> {
+        self.generate_latents_with_options(text, voice_state, &self.generation_options())
+    }
+
+    /// Generate FlowLM latents from text using per-call options
+    ///
+    /// Chunks and seeds are handled as in [`Self::generate_with_options`].
+    /// `speed` is not applied here; it is a decode-time setting of
+    /// [`Self::decode_latents_with_options`].
+    pub fn generate_latents_with_options(
+        &self,
+        text: &str,
+        voice_state: &ModelState,
+        options: &GenerationOptions,
+    ) -> Result<Tensor> {
+        options.validate()?;
+        let chunks = self.prepared_chunks(text, options)?;
+        let num_chunks = chunks.len();
+        let mut rng = generation_rng(options.seed);
+        let mut latents = Vec::new();
+        let mut tail = VecDeque::new();
+
+        for (index, chunk_text) in chunks.into_iter().enumerate() {
+            let chunk_rng = StdRng::seed_from_u64(rng.next_u64());
+            if options.is_cancelled() {
+                break;
+            }
+            let state = self.continuity_state(voice_state, &tail)?;
+            let start = latents.len();
+            for frame in self
+                .generate_latent_segment(chunk_text, &state, options, chunk_rng, index, num_chunks)
+            {
+                latents.push(frame?.latent);
+            }
+
+            let window = options.continuity_frames;
+            if window > 0 && latents.len() > start {
+                let from = latents.len().saturating_sub(window).max(start);
+                let audio = self.decode_latents(&Tensor::cat(&latents[from..], 0)?)?;
+                tail = VecDeque::from([audio.unsqueeze(0)?]);
+            }
+        }
+        if options.is_cancelled() {
+            anyhow::bail!("Generation cancelled");
+        }
+
+        if latents.is_empty() {
+            anyhow::bail!("No latents generated");
+        }
+        Ok(Tensor::cat(&latents, 0)?)
+    }
+
+    /// Decode latents `[frames, ldim]` from [`Self::generate_latents`] to audio
+    ///
+    /// Returns `[channels, samples]`, like [`Self::generate`]. All frames are
+    /// decoded as one continuous Mimi stream.
+    pub fn decode_latents(&self, latents: &Tensor) -> Result<Tensor> {
+        self.decode_latents_with_options(latents, &self.generation_options())
+    }
+
+    /// Decode latents to audio, applying the `speed` of `options`
+    pub fn decode_latents_with_options(
+        &self,
+        latents: &Tensor,
+        options: &GenerationOptions,
+    ) -> Result<Tensor> {
+        options.validate()?;
+        let (num_frames, ldim) = latents.dims2()?;
+        if ldim != self.ldim {
+            anyhow::bail!("Expected latents with {} channels, got {}", self.ldim, ldim);
+        }
+
+        let mut mimi_state = init_states(1, 1000);
+        let mut resampler = LatentRateResampler::new(options.speed);
+        let mut decode_step = 0;
+        let mut audio_chunks = Vec::new();
+        for i in 0..num_frames {
+            let frames = resampler.push(&latents.narrow(0, i, 1)?)?;
+            let audio = self.decode_latent_frames(&frames, 1, &mut mimi_state, &mut decode_step)?;
+            if audio.dims()[2] > 0 {
+                audio_chunks.push(audio);
+            }
+        }
+
+        if audio_chunks.is_empty() {
+            anyhow::bail!("No audio decoded");
+        }
+        let audio = Tensor::cat(&audio_chunks, 2)?;
+        Ok(audio.squeeze(0)?)
+    }
+
+    /// Save latents from [`Self::generate_latents`] to a `.safetensors` file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save_latents<P: AsRef<std::path::Path>>(&self, latents: &Tensor, path: P) -> Result<()> {
+        let data = HashMap::from([("latents", latents.clone())]);
+        candle_core::safetensors::save(&data, path)?;
+        Ok(())
+    }
+
+    /// Load latents saved with [`Self::save_latents`]
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_latents<P: AsRef<std::path::Path>>(&self, path: P) -> Result<Tensor> {
+        let tensors = candle_core::safetensors::load(path, &self.device)?;
+        Self::latents_from_tensors(tensors)
+    }
+
+    /// Load latents from `.safetensors` bytes
+    pub fn load_latents_from_bytes(&self, bytes: &[u8]) -> Result<Tensor> {
+        let tensors = candle_core::safetensors::load_buffer(bytes, &self.device)?;
+        Self::latents_from_tensors(tensors)
+    }
+
+    fn latents_from_tensors(mut tensors: HashMap<String, Tensor>) -> Result<Tensor> {
+        let latents = tensors
+            .remove("latents")
+            .ok_or_else(|| anyhow::anyhow!("'latents' not found in safetensors file"))?;
+        Ok(latents.to_dtype(DType::F32)?)
+    }
+
     // =========================================================================
     // EXPERIMENTAL: Parallel FlowLM + Mimi decoding
     // =========================================================================
@@ -1118,20 +1287,20 @@ impl TTSModel {
         text: &'b str,
         voice_state: &'c ModelState,
     ) -> Box<dyn Iterator<Item = Result<Tensor>> + 'a> {
-        // Split text into chunks to avoid quadratic complexity scaling
-        let chunks = self.split_into_best_sentences(text);
-
-        // Clone voice state so the iterator owns a copy, untied from lifetime 'c
-        let voice_state_owned = voice_state.clone();
-
-        // Create an iterator that processes each chunk sequentially
-        let iterator = chunks.into_iter().flat_map(move |chunk_text| {
-            // We need to return an iterator for each chunk.
-            // We pass a reference to the owned voice state captured by the closure.
-            self.generate_stream_segment(chunk_text, &voice_state_owned)
-        });
+        self.generate_stream_with_options(text, voice_state, &self.generation_options())
+    }
 
-        Box::new(iterator)
+    /// Generate audio stream from text with voice state using per-call options
+    ///
+    /// Each sentence chunk draws its own seed from the call-level RNG, so the
+    /// whole stream is reproducible for a given `options.seed`.
+    pub fn generate_stream_with_options<'a, 'b, 'c>(
+        &'a self,
+        text: &'b str,
+        voice_state: &'c ModelState,
+        options: &GenerationOptions,
+    ) -> Box<dyn Iterator<Item = Result<Tensor>> + 'a> {
+        self.generate_stream_owned_with_options(text, voice_state, options)
     }
 
     /// Generate audio stream from text with voice state, returning an owned iterator.
@@ -1142,28 +1311,239 @@ impl TTSModel {
         text: &str,
         voice_state: &ModelState,
     ) -> Box<dyn Iterator<Item = Result<Tensor>> + 'static> {
-        let model = self.clone();
-        let voice_state_owned = voice_state.clone();
-        let chunks = model.split_into_best_sentences(text);
-
-        let iterator = chunks.into_iter().flat_map(move |chunk_text| {
-            model.generate_stream_segment(chunk_text, &voice_state_owned)
-        });
-
-        Box::new(iterator)
+        self.generate_stream_owned_with_options(text, voice_state, &self.generation_options())
     }
 
-    /// Internal helper to generate a single segment (short text) matching Python's _generate
-    fn generate_stream_segment(
+    /// Owned variant of [`Self::generate_stream_with_options`].
+    pub fn generate_stream_owned_with_options(
         &self,
-        text: String,
+        text: &str,
         voice_state: &ModelState,
-    ) -> Box<dyn Iterator<Item = Result<Tensor>>> {
-        let mut state = voice_state.clone();
-        let mut mimi_state = init_states(1, 1000);
+        options: &GenerationOptions,
+    ) -> Box<dyn Iterator<Item = Result<Tensor>> + 'static> {
+        // Split text into chunks to avoid quadratic complexity scaling
+        let chunks = match self.prepared_chunks(text, options) {
+            Ok(chunks) => chunks,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
 
-        // Prepare text
-        let prepared_text = prepare_text_prompt(&text);
+        Box::new(chunk_audio(self.stream_chunks(
+            chunks,
+            voice_state,
+            options,
+            ContinuityTail::default(),
+        )))
+    }
+
+    /// Apply the lexicon and the unsupported-character policy, then split
+    /// text into sentence chunks for generation
+    fn prepared_chunks(&self, text: &str, options: &GenerationOptions) -> Result<Vec<String>> {
+        let text = options.apply_lexicon(text);
+        let text = self.supported_text(&text, options.unsupported_chars)?;
+        Ok(self.text_chunks(&text, options))
+    }
+
+    /// Split text that is ready to speak into sentence chunks for generation
+    fn text_chunks(&self, text: &str, options: &GenerationOptions) -> Vec<String> {
+        self.split_into_best_sentences_with_max_tokens(text, options.max_tokens_per_chunk)
+    }
+
+    /// Generate each sentence chunk in turn, marking where chunks start and end
+    ///
+    /// Every chunk draws its own RNG from the call-level seed. With
+    /// `continuity_frames > 0`, a rolling window of the last frames is
+    /// re-encoded through Mimi and appended to a fresh clone of the voice state
+    /// as extra audio prompt before each chunk. The KV cache therefore holds at
+    /// most voice prompt + window + one chunk, keeping the O(N) cost of
+    /// sentence splitting. The window lives in `tail`, so long-form calls can
+    /// carry it from one text segment to the next.
+    fn stream_chunks(
+        &self,
+        chunks: Vec<String>,
+        voice_state: &ModelState,
+        options: &GenerationOptions,
+        tail: ContinuityTail,
+    ) -> Box<dyn Iterator<Item = Result<ChunkItem>> + 'static> {
+        // TTSModel clones are shallow (tensors are reference counted)
+        let model = self.clone();
+        let voice_state = voice_state.clone();
+        let options = options.clone();
+        let mut rng = generation_rng(options.seed);
+        let window = options.continuity_frames;
+        let num_chunks = chunks.len();
+        let mut chunks = chunks.into_iter().enumerate();
+        let mut segment: Option<(usize, SegmentFrames)> = None;
+        let mut pending: Option<ChunkItem> = None;
+
+        Box::new(std::iter::from_fn(move || {
+            if let Some(item) = pending.take() {
+                return Some(Ok(item));
+            }
+            if let Some((index, frames)) = segment.as_mut() {
+                match frames.next() {
+                    Some(Ok(frame)) => {
+                        if window > 0 && frame.num_samples() > 0 {
+                            tail.push(&frame.audio, window);
+                        }
+                        return Some(Ok(ChunkItem::Frame(frame)));
+                    }
+                    Some(Err(e)) => return Some(Err(e)),
+                    None => {
+                        let index = *index;
+                        segment = None;
+                        return Some(Ok(ChunkItem::Finished { index }));
+                    }
+                }
+            }
+
+            let (index, chunk_text) = chunks.next()?;
+            let chunk_seed = rng.next_u64();
+            if options.is_cancelled() {
+                return None;
+            }
+
+            let recent = tail.frames();
+            let state = if window > 0 && !recent.is_empty() {
+                match model.continuity_state(&voice_state, &recent) {
+                    Ok(state) => state,
+                    Err(e) => return Some(Err(e)),
+                }
+            } else {
+                voice_state.clone()
+            };
+            let frames = if options.candidates > 1 {
+                match model.select_candidate(
+                    &chunk_text,
+                    &state,
+                    &options,
+                    chunk_seed,
+                    index,
+                    num_chunks,
+                ) {
+                    Ok(Some(candidate)) => {
+                        pending = Some(ChunkItem::Selected {
+                            index,
+                            selected: candidate.selected,
+                            scores: candidate.scores,
+                        });
+                        Box::new(candidate.frames.into_iter().map(Ok)) as SegmentFrames
+                    }
+                    Ok(None) => return None,
+                    Err(e) => return Some(Err(e)),
+                }
+            } else {
+                model.generate_stream_segment(
+                    chunk_text.clone(),
+                    &state,
+                    &options,
+                    StdRng::seed_from_u64(chunk_seed),
+                    index,
+                    num_chunks,
+                )
+            };
+            segment = Some((index, frames));
+            Some(Ok(ChunkItem::Started {
+                index,
+                text: chunk_text,
+            }))
+        }))
+    }
+
+    /// Generate `options.candidates` versions of one chunk and keep the best
+    ///
+    /// Candidate `k` is seeded with `chunk_seed + k`, so candidate 0 matches
+    /// what a single-candidate call would produce. Returns `None` when the
+    /// generation was cancelled meanwhile.
+    fn select_candidate(
+        &self,
+        chunk_text: &str,
+        state: &ModelState,
+        options: &GenerationOptions,
+        chunk_seed: u64,
+        index: usize,
+        num_chunks: usize,
+    ) -> Result<Option<SelectedCandidate>> {
+        let max_gen_len = options
+            .max_frames_per_chunk
+            .unwrap_or_else(|| self.estimate_generation_steps(chunk_text));
+        let mut scores = Vec::with_capacity(options.candidates);
+        let mut best: Option<Vec<SegmentFrame>> = None;
+
+        for k in 0..options.candidates {
+            let rng = StdRng::seed_from_u64(chunk_seed.wrapping_add(k as u64));
+            let mut stats = CandidateStats::default();
+            let mut frames = Vec::new();
+            for frame in self.generate_stream_segment(
+                chunk_text.to_string(),
+                state,
+                options,
+                rng,
+                index,
+                num_chunks,
+            ) {
+                let frame = frame?;
+                stats.push(frame.step, frame.eos_detected, &frame.audio)?;
+                frames.push(frame);
+            }
+            if options.is_cancelled() {
+                return Ok(None);
+            }
+            scores.push(stats.score(max_gen_len));
+            if best_candidate(&scores) == k {
+                best = Some(frames);
+            }
+        }
+
+        let selected = best_candidate(&scores);
+        Ok(best.map(|frames| SelectedCandidate {
+            selected,
+            scores,
+            frames,
+        }))
+    }
+
+    /// Voice state prompted with the voice prompt followed by recent audio frames
+    fn continuity_state(
+        &self,
+        voice_state: &ModelState,
+        tail: &VecDeque<Tensor>,
+    ) -> Result<ModelState> {
+        let mut state = voice_state.clone();
+        if tail.is_empty() {
+            return Ok(state);
+        }
+        let frames: Vec<&Tensor> = tail.iter().collect();
+        let audio = Tensor::cat(&frames, 2)?;
+        let conditioning = self.get_conditioning(&audio)?;
+        self.run_flow_lm_prompt(&conditioning, &mut state)?;
+        Ok(state)
+    }
+
+    /// Internal helper to sample the FlowLM latents of a single segment (short text),
+    /// matching Python's _generate without the Mimi decode
+    ///
+    /// Stops before the next frame once `options.handle` is cancelled and reports
+    /// progress to it after every frame.
+    fn generate_latent_segment(
+        &self,
+        text: String,
+        voice_state: &ModelState,
+        options: &GenerationOptions,
+        mut rng: StdRng,
+        chunk_index: usize,
+        num_chunks: usize,
+    ) -> LatentFrames {
+        if options.is_cancelled() {
+            return Box::new(std::iter::empty());
+        }
+        if let Err(e) = options.validate() {
+            return Box::new(std::iter::once(Err(e)));
+        }
+
+        let mut state = voice_state.clone();
+
+        // Prepare text
+        let prepared_text = prepare_text_prompt(&text);
 
         // Error handling for preparation failures inside the iterator
         let tokens = match self.conditioner.prepare(&prepared_text, &self.device) {
@@ -1187,8 +1567,16 @@ impl TTSModel {
 
         // Removed redundant increment_steps("offset") - handled internally by RoPE/Attention with current_end_len
 
-        let max_gen_len = (prepared_text.split_whitespace().count() + 2) * 13;
-        let frames_after_eos = estimate_frames_after_eos(&text);
+        let max_gen_len = options
+            .max_frames_per_chunk
+            .unwrap_or_else(|| (prepared_text.split_whitespace().count() + 2) * 13);
+        let frames_after_eos = options
+            .frames_after_eos
+            .unwrap_or_else(|| estimate_frames_after_eos(&text));
+        let temperature = options.temperature;
+        let eos_threshold = options.eos_threshold;
+        let noise_clamp = options.noise_clamp;
+        let options = options.clone();
 
         let mut backbone_input = match self.flow_lm.bos_emb.clone().reshape((1, 1, self.ldim)) {
             Ok(t) => t,
@@ -1214,7 +1602,7 @@ impl TTSModel {
         // Pre-compute time embeddings for the entire segment to avoid re-computing every frame
         // Now returns a single batched Tensor [num_steps, channels]
         let time_embeddings = match model.flow_lm.flow_net.compute_time_embeddings(
-            model.lsd_decode_steps,
+            options.lsd_decode_steps,
             &model.device,
             DType::F32,
         ) {
@@ -1226,7 +1614,7 @@ impl TTSModel {
             Tensor::zeros((1, 0, model.dim), DType::F32, &model.device).unwrap();
 
         Box::new((0..max_gen_len).map_while(move |step| {
-            if finished {
+            if finished || options.is_cancelled() {
                 return None;
             }
 
@@ -1242,39 +1630,19 @@ impl TTSModel {
                         text_tokens_to_pass,
                         &mut state,
                         &time_embeddings,
-                        model.temp,
-                        model.eos_threshold,
+                        temperature,
+                        eos_threshold,
+                        noise_clamp,
                         step,
+                        &mut rng,
                     )
                 }) {
                 Ok(res) => res,
                 Err(e) => return Some(Err(anyhow::anyhow!(e))),
             };
 
-            let audio_frame = match (|| -> Result<Tensor> {
-                let next_latent_denorm = next_latent
-                    .broadcast_mul(&model.flow_lm.emb_std)?
-                    .broadcast_add(&model.flow_lm.emb_mean)?;
-
-                let mimi_input = next_latent_denorm.unsqueeze(1)?.transpose(1, 2)?;
-                let quantized = model.mimi.quantize(&mimi_input)?;
-                let audio = tracing::info_span!("mimi.decode_from_latent", step = step)
-                    .in_scope(|| {
-                        model
-                            .mimi
-                            .decode_from_latent(&quantized, &mut mimi_state, step)
-                    })
-                    .map_err(|e| anyhow::anyhow!(e))?;
-
-                // Removed redundant increment_steps("offset") for mimi
-
-                Ok(audio)
-            })() {
-                Ok(frame) => frame,
-                Err(e) => return Some(Err(e)),
-            };
-
-            if is_eos && eos_step.is_none() {
+            let eos_detected = is_eos && eos_step.is_none();
+            if eos_detected {
                 eos_step = Some(step);
             }
 
@@ -1288,64 +1656,642 @@ impl TTSModel {
 
             // Removed redundant increment_steps("offset") for FlowLM - handled by attention state
 
-            Some(Ok(audio_frame))
+            options.report_progress(GenerationProgress {
+                chunk_index,
+                num_chunks,
+                step,
+                max_gen_len,
+            });
+
+            Some(Ok(LatentFrame {
+                latent: next_latent,
+                step,
+                eos_detected,
+            }))
+        }))
+    }
+
+    /// Internal helper to generate a single segment (short text) matching Python's _generate
+    ///
+    /// Decodes the latents of [`Self::generate_latent_segment`] through Mimi one
+    /// frame at a time, resampling them first when `options.speed` is not 1.0.
+    fn generate_stream_segment(
+        &self,
+        text: String,
+        voice_state: &ModelState,
+        options: &GenerationOptions,
+        rng: StdRng,
+        chunk_index: usize,
+        num_chunks: usize,
+    ) -> SegmentFrames {
+        let latents =
+            self.generate_latent_segment(text, voice_state, options, rng, chunk_index, num_chunks);
+        let model = self.clone();
+        let mut mimi_state = init_states(1, 1000);
+        let mut resampler = LatentRateResampler::new(options.speed);
+        let mut decode_step = 0;
+
+        Box::new(latents.map(move |frame| {
+            let frame = frame?;
+            let latents = resampler.push(&frame.latent)?;
+            let audio =
+                model.decode_latent_frames(&latents, 1, &mut mimi_state, &mut decode_step)?;
+            Ok(SegmentFrame {
+                audio,
+                step: frame.step,
+                eos_detected: frame.eos_detected,
+            })
         }))
     }
 
+    /// Denormalize one frame of FlowLM latents `[B, ldim]` and decode it through Mimi
+    fn decode_latent_frame(
+        &self,
+        latent: &Tensor,
+        mimi_state: &mut ModelState,
+        step: usize,
+    ) -> Result<Tensor> {
+        let latent_denorm = latent
+            .broadcast_mul(&self.flow_lm.emb_std)?
+            .broadcast_add(&self.flow_lm.emb_mean)?;
+
+        let mimi_input = latent_denorm.unsqueeze(1)?.transpose(1, 2)?;
+        let quantized = self.mimi.quantize(&mimi_input)?;
+        let audio = tracing::info_span!("mimi.decode_from_latent", step = step)
+            .in_scope(|| self.mimi.decode_from_latent(&quantized, mimi_state, step))?;
+
+        Ok(audio)
+    }
+
+    /// Decode a run of latent frames `[B, ldim]` into audio `[B, channels, samples]`
+    ///
+    /// `decode_step` counts frames fed to Mimi so far and is advanced per frame.
+    /// An empty run (the speed resampler skipped this latent) gives zero samples.
+    fn decode_latent_frames(
+        &self,
+        latents: &[Tensor],
+        batch: usize,
+        mimi_state: &mut ModelState,
+        decode_step: &mut usize,
+    ) -> Result<Tensor> {
+        let mut frames = Vec::with_capacity(latents.len());
+        for latent in latents {
+            frames.push(self.decode_latent_frame(latent, mimi_state, *decode_step)?);
+            *decode_step += 1;
+        }
+        match frames.len() {
+            0 => Ok(Tensor::zeros(
+                (batch, self.mimi.channels, 0),
+                DType::F32,
+                &self.device,
+            )?),
+            1 => Ok(frames.remove(0)),
+            _ => Ok(Tensor::cat(&frames, 2)?),
+        }
+    }
+
+    /// Generate several utterances together in one batch
+    ///
+    /// Each `(text, voice_state)` pair is generated as a single chunk (no
+    /// sentence splitting), so this suits many short prompts. Rows that reach
+    /// EOS stop contributing audio while the remaining rows keep going.
+    /// Returns one `[channels, samples]` tensor per input, in input order.
+    pub fn generate_batch(&self, items: &[(&str, &ModelState)]) -> Result<Vec<Tensor>> {
+        self.generate_batch_with_options(items, &self.generation_options())
+    }
+
+    /// Batched generation using per-call options
+    ///
+    /// `frames_after_eos` and `max_frames_per_chunk` apply per row; when `None`
+    /// they are estimated from each row's own text. With a seed, every row draws
+    /// from its own RNG derived from that seed.
+    pub fn generate_batch_with_options(
+        &self,
+        items: &[(&str, &ModelState)],
+        options: &GenerationOptions,
+    ) -> Result<Vec<Tensor>> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+        options.validate()?;
+        let batch = items.len();
+        let mut rng = generation_rng(options.seed);
+
+        // Prompt each row with its own text on top of its own voice state
+        let mut row_states = Vec::with_capacity(batch);
+        let mut row_rngs = Vec::with_capacity(batch);
+        let mut max_gen_lens = Vec::with_capacity(batch);
+        let mut frames_after_eos = Vec::with_capacity(batch);
+        for (text, voice_state) in items {
+            let text =
+                &self.supported_text(&options.apply_lexicon(text), options.unsupported_chars)?;
+            let mut state = (*voice_state).clone();
+            let prepared_text = prepare_text_prompt(text);
+            let tokens = self.conditioner.prepare(&prepared_text, &self.device)?;
+            let text_embeddings = self.conditioner.forward(&tokens)?;
+            self.flow_lm
+                .transformer
+                .forward(&text_embeddings, &mut state, 0)?;
+
+            row_states.push(state);
+            row_rngs.push(StdRng::seed_from_u64(rng.next_u64()));
+            max_gen_lens.push(
+                options
+                    .max_frames_per_chunk
+                    .unwrap_or_else(|| (prepared_text.split_whitespace().count() + 2) * 13),
+            );
+            frames_after_eos.push(
+                options
+                    .frames_after_eos
+                    .unwrap_or_else(|| estimate_frames_after_eos(text)),
+            );
+        }
+
+        let state_refs: Vec<&ModelState> = row_states.iter().collect();
+        let mut state = self
+            .flow_lm
+            .transformer
+            .stack_states(&state_refs, &self.device)?;
+        drop(row_states);
+
+        let time_embeddings = self.flow_lm.flow_net.compute_time_embeddings(
+            options.lsd_decode_steps,
+            &self.device,
+            DType::F32,
+        )?;
+        let empty_text_embeddings = Tensor::zeros((batch, 0, self.dim), DType::F32, &self.device)?;
+        let mut backbone_input = self
+            .flow_lm
+            .bos_emb
+            .reshape((1, 1, self.ldim))?
+            .repeat((batch, 1, 1))?;
+        let mut mimi_state = init_states(batch, 1000);
+
+        let mut resampler = LatentRateResampler::new(options.speed);
+        let mut decode_step = 0;
+        let mut eos_steps: Vec<Option<usize>> = vec![None; batch];
+        let mut finished = vec![false; batch];
+        let mut row_frames: Vec<Vec<Tensor>> = vec![Vec::new(); batch];
+        let max_steps = max_gen_lens.iter().copied().max().unwrap_or(0);
+
+        for step in 0..max_steps {
+            if options.is_cancelled() {
+                anyhow::bail!("Generation cancelled");
+            }
+            let (next_latent, is_eos) = tracing::info_span!("flow_lm.forward_batch", step = step)
+                .in_scope(|| {
+                self.flow_lm.forward_batch(
+                    &backbone_input,
+                    &empty_text_embeddings,
+                    &mut state,
+                    &time_embeddings,
+                    options.temperature,
+                    options.eos_threshold,
+                    options.noise_clamp,
+                    step,
+                    &mut row_rngs,
+                )
+            })?;
+            let latents = resampler.push(&next_latent)?;
+            let audio =
+                self.decode_latent_frames(&latents, batch, &mut mimi_state, &mut decode_step)?;
+
+            // Finished rows keep riding along in the batch but their output is masked out
+            for row in 0..batch {
+                if finished[row] {
+                    continue;
+                }
+                row_frames[row].push(audio.narrow(0, row, 1)?);
+
+                if is_eos[row] && eos_steps[row].is_none() {
+                    eos_steps[row] = Some(step);
+                }
+                let eos_done = eos_steps[row].is_some_and(|e| step >= e + frames_after_eos[row]);
+                if eos_done || step + 1 >= max_gen_lens[row] {
+                    finished[row] = true;
+                }
+            }
+            options.report_progress(GenerationProgress {
+                chunk_index: 0,
+                num_chunks: 1,
+                step,
+                max_gen_len: max_steps,
+            });
+            if finished.iter().all(|&f| f) {
+                break;
+            }
+
+            backbone_input = next_latent.unsqueeze(1)?;
+        }
+
+        row_frames
+            .into_iter()
+            .map(|frames| {
+                if frames.is_empty() {
+                    anyhow::bail!("No audio generated");
+                }
+                Ok(Tensor::cat(&frames, 2)?.squeeze(0)?)
+            })
+            .collect()
+    }
+
     /// Generate audio stream from long text by segmenting it
     pub fn generate_stream_long<'a>(
         &'a self,
         text: &str,
         voice_state: &'a ModelState,
     ) -> impl Iterator<Item = Result<Tensor>> + 'a {
-        use crate::pause::{parse_text_with_pauses, silence_samples};
+        self.generate_stream_long_with_options(text, voice_state, &self.generation_options())
+    }
 
-        let parsed = parse_text_with_pauses(text);
-        let mut segments = Vec::new();
+    /// Generate audio stream from long text by segmenting it, using per-call options
+    pub fn generate_stream_long_with_options<'a>(
+        &'a self,
+        text: &str,
+        voice_state: &'a ModelState,
+        options: &GenerationOptions,
+    ) -> impl Iterator<Item = Result<Tensor>> + use<'a> {
+        let (segments, error) = match self.input_segments(text, options) {
+            Ok(segments) => (segments, None),
+            Err(e) => (Vec::new(), Some(Err(e))),
+        };
 
-        // Interleave text chunks and pauses
-        let mut last_pos = 0;
-        for pause in &parsed.pauses {
-            if pause.position > last_pos {
-                let text_seg = &parsed.clean_text[last_pos..pause.position];
-                if !text_seg.trim().is_empty() {
-                    segments.push(Segment::Text(text_seg.to_string()));
-                }
+        let model = self;
+        let options = options.clone();
+        let mut rng = generation_rng(options.seed);
+        let mut voices = VoiceSwitch::default();
+        // Continuity carries over pauses and commas; a voice change resets it
+        let tail = ContinuityTail::default();
+        let audio = segments.into_iter().flat_map(move |seg| match seg {
+            Segment::Text { text, prosody } => {
+                let segment_options =
+                    prosody_options(&options, prosody).with_seed(Some(rng.next_u64()));
+                let chunks = model.text_chunks(&text, &segment_options);
+                let iter = chunk_audio(model.stream_chunks(
+                    chunks,
+                    voices.current(voice_state),
+                    &segment_options,
+                    tail.clone(),
+                ))
+                .map(move |chunk| apply_gain(chunk?, prosody.volume));
+                Box::new(iter) as Box<dyn Iterator<Item = Result<Tensor>>>
+            }
+            Segment::Pause(_) | Segment::Audio { .. } | Segment::Voice(_)
+                if options.is_cancelled() =>
+            {
+                Box::new(std::iter::empty()) as Box<dyn Iterator<Item = Result<Tensor>>>
+            }
+            Segment::Pause(ms) => Box::new(std::iter::once(model.pause_silence(ms)))
+                as Box<dyn Iterator<Item = Result<Tensor>>>,
+            Segment::Audio { samples, .. } => {
+                Box::new(std::iter::once(Ok(samples))) as Box<dyn Iterator<Item = Result<Tensor>>>
+            }
+            Segment::Voice(spec) => {
+                tail.clear();
+                Box::new(voices.switch(model, &spec).err().map(Err).into_iter())
+                    as Box<dyn Iterator<Item = Result<Tensor>>>
             }
-            segments.push(Segment::Pause(pause.duration_ms));
+        });
+        error.into_iter().chain(audio)
+    }
 
-            // Explicit pauses were replaced by a single space in clean_text
-            // Natural pauses (commas, ellipses) are still in clean_text
-            if pause.original.starts_with("[pause:") {
-                last_pos = pause.position + 1;
-            } else {
-                last_pos = pause.position + pause.original.len();
+    /// Generate audio events from long text with voice state
+    pub fn generate_events(
+        &self,
+        text: &str,
+        voice_state: &ModelState,
+    ) -> Box<dyn Iterator<Item = Result<GenerationEvent>> + 'static> {
+        self.generate_events_with_options(text, voice_state, &self.generation_options())
+    }
+
+    /// Generate audio events from long text using per-call options
+    ///
+    /// Segments text and pauses like [`Self::generate_stream_long_with_options`]
+    /// and yields the same audio for the same seed, wrapped in events that mark
+    /// chunk boundaries, pauses and EOS detection. Chunk indices count across
+    /// the whole call and every event carries its output sample offset.
+    pub fn generate_events_with_options(
+        &self,
+        text: &str,
+        voice_state: &ModelState,
+        options: &GenerationOptions,
+    ) -> Box<dyn Iterator<Item = Result<GenerationEvent>> + 'static> {
+        let segments = match self.input_segments(text, options) {
+            Ok(segments) => segments,
+            Err(e) => return Box::new(std::iter::once(Err(e))),
+        };
+
+        let model = self.clone();
+        let voice_state = voice_state.clone();
+        let options = options.clone();
+        let mut rng = generation_rng(options.seed);
+        let mut chunk_base = 0;
+        let mut voices = VoiceSwitch::default();
+        let tail = ContinuityTail::default();
+        let kinds = segments.into_iter().flat_map(move |seg| match seg {
+            Segment::Text { text, prosody } => {
+                let segment_options =
+                    prosody_options(&options, prosody).with_seed(Some(rng.next_u64()));
+                let chunks = model.text_chunks(&text, &segment_options);
+                let base = chunk_base;
+                chunk_base += chunks.len();
+
+                let iter = model
+                    .stream_chunks(
+                        chunks,
+                        voices.current(&voice_state),
+                        &segment_options,
+                        tail.clone(),
+                    )
+                    .flat_map(move |item| match item {
+                        Ok(ChunkItem::Started { index, text }) => {
+                            vec![Ok(GenerationEventKind::ChunkStarted {
+                                index: base + index,
+                                text,
+                            })]
+                        }
+                        Ok(ChunkItem::Frame(frame)) => {
+                            let mut kinds = Vec::with_capacity(2);
+                            if frame.eos_detected {
+                                kinds.push(Ok(GenerationEventKind::EosDetected {
+                                    step: frame.step,
+                                }));
+                            }
+                            if frame.num_samples() > 0 {
+                                kinds.push(apply_gain(frame.audio, prosody.volume).map(
+                                    |samples| GenerationEventKind::Audio {
+                                        samples,
+                                        frame_step: frame.step,
+                                    },
+                                ));
+                            }
+                            kinds
+                        }
+                        Ok(ChunkItem::Selected {
+                            index,
+                            selected,
+                            scores,
+                        }) => vec![Ok(GenerationEventKind::CandidateSelected {
+                            index: base + index,
+                            selected,
+                            scores,
+                        })],
+                        Ok(ChunkItem::Finished { index }) => {
+                            vec![Ok(GenerationEventKind::ChunkFinished {
+                                index: base + index,
+                            })]
+                        }
+                        Err(e) => vec![Err(e)],
+                    });
+                Box::new(iter) as Box<dyn Iterator<Item = Result<GenerationEventKind>>>
+            }
+            Segment::Pause(_) | Segment::Audio { .. } | Segment::Voice(_)
+                if options.is_cancelled() =>
+            {
+                Box::new(std::iter::empty())
+                    as Box<dyn Iterator<Item = Result<GenerationEventKind>>>
+            }
+            Segment::Pause(ms) => Box::new(std::iter::once(
+                model
+                    .pause_silence(ms)
+                    .map(|samples| GenerationEventKind::Pause { ms, samples }),
+            ))
+                as Box<dyn Iterator<Item = Result<GenerationEventKind>>>,
+            Segment::Audio { src, samples } => {
+                Box::new(std::iter::once(Ok(GenerationEventKind::AudioClip {
+                    src,
+                    samples,
+                }))) as Box<dyn Iterator<Item = Result<GenerationEventKind>>>
+            }
+            Segment::Voice(spec) => {
+                tail.clear();
+                Box::new(std::iter::once(
+                    voices
+                        .switch(&model, &spec)
+                        .map(|()| GenerationEventKind::VoiceChanged { voice: spec }),
+                )) as Box<dyn Iterator<Item = Result<GenerationEventKind>>>
+            }
+        });
+
+        let mut sample_offset = 0;
+        Box::new(kinds.map(move |kind| {
+            kind.map(|kind| {
+                let event = GenerationEvent {
+                    sample_offset,
+                    kind,
+                };
+                sample_offset += event.kind.num_samples();
+                event
+            })
+        }))
+    }
+
+    /// Report how the long-form entry points will tokenize `text`
+    ///
+    /// Parses `text` like [`Self::generate_stream_long_with_options`] does
+    /// (input format, lexicon, normalization and pauses), then lists the
+    /// characters the tokenizer cannot encode, what
+    /// `options.unsupported_chars` does with them, and the token counts of
+    /// every sentence chunk. Never fails because of
+    /// [`UnsupportedCharPolicy::Error`]; the report shows what would be
+    /// rejected.
+    pub fn analyze_text(&self, text: &str, options: &GenerationOptions) -> Result<TextAnalysis> {
+        let policy = options.unsupported_chars;
+        let texts: Vec<String> = self
+            .parse_segments(text, options)?
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect();
+        let unsupported = self.unsupported_chars(&texts.join(" "), policy)?;
+
+        let mut chunks = Vec::new();
+        for text in &texts {
+            let text = match policy {
+                UnsupportedCharPolicy::Error => text.clone(),
+                _ => replace_unsupported(text, &unsupported),
+            };
+            if text.trim().is_empty() {
+                continue;
+            }
+            for chunk in self.text_chunks(&text, options) {
+                let coverage = self.conditioner.coverage(&chunk)?;
+                chunks.push(ChunkAnalysis {
+                    tokens: coverage.tokens,
+                    unknown_tokens: coverage.unknown.len(),
+                    byte_fallback_tokens: coverage.byte_fallback.len(),
+                    text: chunk,
+                });
             }
         }
-        if last_pos < parsed.clean_text.len() {
-            let text_seg = &parsed.clean_text[last_pos..];
-            if !text_seg.trim().is_empty() {
-                segments.push(Segment::Text(text_seg.to_string()));
+        Ok(TextAnalysis {
+            policy,
+            unsupported,
+            chunks,
+            max_tokens_per_chunk: options.max_tokens_per_chunk,
+        })
+    }
+
+    /// Characters of `text` the tokenizer cannot encode, with their
+    /// replacement under `policy`
+    fn unsupported_chars(
+        &self,
+        text: &str,
+        policy: UnsupportedCharPolicy,
+    ) -> Result<Vec<UnsupportedChar>> {
+        let coverage = self.conditioner.coverage(text)?;
+        let mut unsupported = Vec::new();
+        for (ch, count) in chars_in_spans(text, &coverage.unsupported_spans()) {
+            let replacement = match policy {
+                UnsupportedCharPolicy::Transliterate => transliterate(ch).filter(|replacement| {
+                    self.conditioner
+                        .coverage(replacement)
+                        .is_ok_and(|coverage| coverage.is_covered())
+                }),
+                UnsupportedCharPolicy::Strip | UnsupportedCharPolicy::Error => None,
+            };
+            unsupported.push(UnsupportedChar {
+                ch,
+                count,
+                replacement,
+            });
+        }
+        Ok(unsupported)
+    }
+
+    /// `text` with the unsupported-character policy applied
+    ///
+    /// Replaced and removed characters are logged with `tracing::warn!`.
+    fn supported_text(&self, text: &str, policy: UnsupportedCharPolicy) -> Result<String> {
+        let unsupported = self.unsupported_chars(text, policy)?;
+        if unsupported.is_empty() {
+            return Ok(text.to_string());
+        }
+        let described: Vec<String> = unsupported.iter().map(|c| c.describe(policy)).collect();
+        if policy == UnsupportedCharPolicy::Error {
+            anyhow::bail!(
+                "Text contains characters the model cannot voice: {}",
+                described.join(", ")
+            );
+        }
+        tracing::warn!("Unsupported characters: {}", described.join(", "));
+        Ok(replace_unsupported(text, &unsupported))
+    }
+
+    /// Segments for the long-form entry points, with the unsupported-character
+    /// policy applied to every text segment
+    fn input_segments(&self, text: &str, options: &GenerationOptions) -> Result<Vec<Segment>> {
+        let mut segments = Vec::new();
+        for segment in self.parse_segments(text, options)? {
+            let Segment::Text { text, prosody } = segment else {
+                segments.push(segment);
+                continue;
+            };
+            let text = self.supported_text(&text, options.unsupported_chars)?;
+            if !text.trim().is_empty() {
+                segments.push(Segment::Text { text, prosody });
             }
         }
+        Ok(segments)
+    }
 
-        let model = self;
-        segments.into_iter().flat_map(move |seg| match seg {
-            Segment::Text(s) => {
-                let iter = model.generate_stream(&s, voice_state);
-                Box::new(iter) as Box<dyn Iterator<Item = Result<Tensor>>>
+    /// Text, pause and audio clip segments for the long-form entry points
+    ///
+    /// SSML parse warnings and audio clips that fall back to their text are
+    /// logged with `tracing::warn!`.
+    fn parse_segments(&self, text: &str, options: &GenerationOptions) -> Result<Vec<Segment>> {
+        match options.input_format {
+            InputFormat::Text => {
+                return Ok(split_pause_segments(text, options, Prosody::default()));
             }
-            Segment::Pause(ms) => {
-                let n_samples = silence_samples(ms, model.sample_rate as u32);
-                let silence_res = Tensor::zeros(
-                    (1, model.mimi.channels, n_samples),
-                    DType::F32,
-                    &model.device,
-                );
-                Box::new(std::iter::once(silence_res.map_err(anyhow::Error::from)))
-                    as Box<dyn Iterator<Item = Result<Tensor>>>
+            InputFormat::Markdown => {
+                let mut segments = Vec::new();
+                for item in crate::markdown::parse_markdown(text, &options.markdown) {
+                    match item {
+                        MarkdownItem::Text(text) => segments.extend(split_pause_segments(
+                            &text,
+                            options,
+                            Prosody::default(),
+                        )),
+                        MarkdownItem::Break(ms) => segments.push(Segment::Pause(ms)),
+                    }
+                }
+                return Ok(segments);
             }
-        })
+            InputFormat::Ssml => {}
+        }
+
+        let document = crate::ssml::parse_ssml(text)?;
+        for warning in &document.warnings {
+            tracing::warn!("SSML: {}", warning);
+        }
+        let mut segments = Vec::new();
+        for item in document.items {
+            match item {
+                SsmlItem::Text { text, prosody } => {
+                    segments.extend(split_pause_segments(&text, options, prosody))
+                }
+                SsmlItem::Break(0) => {}
+                SsmlItem::Break(ms) => segments.push(Segment::Pause(ms)),
+                SsmlItem::Audio {
+                    src,
+                    fallback,
+                    prosody,
+                } => {
+                    let clip = if options.ssml_audio {
+                        self.load_audio_clip(&src)
+                    } else {
+                        Err(anyhow::anyhow!("loading audio files is disabled"))
+                    };
+                    match clip.and_then(|samples| apply_gain(samples, prosody.volume)) {
+                        Ok(samples) => segments.push(Segment::Audio { src, samples }),
+                        Err(e) => {
+                            tracing::warn!("SSML: <audio src=\"{}\"> not played: {}", src, e);
+                            segments.extend(split_pause_segments(&fallback, options, prosody));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(segments)
+    }
+
+    /// Load a WAV file as `[1, channels, samples]` at the model's sample rate
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load_audio_clip(&self, src: &str) -> Result<Tensor> {
+        let path = src.strip_prefix("file://").unwrap_or(src);
+        if path.contains("://") {
+            anyhow::bail!("only local WAV files are supported");
+        }
+        let (audio, sample_rate) = crate::audio::read_wav(path)?;
+        let audio = if audio.dims()[0] > 1 {
+            audio.mean_keepdim(0)?
+        } else {
+            audio
+        };
+        let audio = if sample_rate != self.sample_rate as u32 {
+            crate::audio::resample(&audio, sample_rate, self.sample_rate as u32)?
+        } else {
+            audio
+        };
+        Ok(audio.unsqueeze(0)?)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load_audio_clip(&self, _src: &str) -> Result<Tensor> {
+        anyhow::bail!("audio files cannot be loaded in WASM")
+    }
+
+    /// Silence `[1, channels, samples]` for a pause of `ms` milliseconds
+    fn pause_silence(&self, ms: u32) -> Result<Tensor> {
+        let n_samples = crate::pause::silence_samples(ms, self.sample_rate as u32);
+        Ok(Tensor::zeros(
+            (1, self.mimi.channels, n_samples),
+            DType::F32,
+            &self.device,
+        )?)
     }
     pub fn estimate_generation_steps(&self, text: &str) -> usize {
         let prepared = prepare_text_prompt(text);
@@ -1353,54 +2299,237 @@ impl TTSModel {
     }
 }
 
-/// Internal segment type for interleaving text and pauses
+/// Internal segment type for interleaving text, pauses, audio clips and
+/// voice switches
 enum Segment {
-    Text(String),
+    Text {
+        text: String,
+        prosody: Prosody,
+    },
     Pause(u32),
+    Audio {
+        src: String,
+        samples: Tensor,
+    },
+    /// Speak the following text segments with this voice
+    Voice(String),
+}
+
+/// Voice states picked by `[voice:...]` markers during one long-form call
+///
+/// Every spec is resolved once, through the model's [`VoiceResolver`].
+#[derive(Default)]
+struct VoiceSwitch {
+    resolved: HashMap<String, Arc<ModelState>>,
+    current: Option<Arc<ModelState>>,
+}
+
+impl VoiceSwitch {
+    fn switch(&mut self, model: &TTSModel, spec: &str) -> Result<()> {
+        let state = match self.resolved.get(spec) {
+            Some(state) => state.clone(),
+            None => {
+                let state = model
+                    .voice_resolver
+                    .resolve(model, spec)
+                    .map_err(|e| anyhow::anyhow!("Failed to resolve [voice:{}]: {}", spec, e))?;
+                self.resolved.insert(spec.to_string(), state.clone());
+                state
+            }
+        };
+        self.current = Some(state);
+        Ok(())
+    }
+
+    /// The switched-to voice, or `default` before the first marker
+    fn current<'a>(&'a self, default: &'a ModelState) -> &'a ModelState {
+        self.current.as_deref().unwrap_or(default)
+    }
+}
+
+/// Most recent generated audio frames, prompting the next chunk when
+/// `continuity_frames > 0`
+///
+/// Shared by the text segments of one long-form call, so the context survives
+/// pauses and punctuation splits. Clones share the same frames.
+#[derive(Clone, Default)]
+struct ContinuityTail(Arc<Mutex<VecDeque<Tensor>>>);
+
+impl ContinuityTail {
+    /// Append a frame, keeping at most `window` frames
+    fn push(&self, frame: &Tensor, window: usize) {
+        let mut frames = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        while frames.len() >= window.max(1) {
+            frames.pop_front();
+        }
+        frames.push_back(frame.clone());
+    }
+
+    fn frames(&self) -> VecDeque<Tensor> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
 }
 
-fn pack_kv_cache(keys: &Tensor, values: &Tensor, offset: usize) -> Result<Tensor> {
-    // Step 1: Stack the tensors along a new 0th dimension.
-    // keys shape example:   [1, 16, 128, 64]
-    // values shape example: [1, 16, 128, 64]
-    // stacked shape: [2, 1, 16, 128, 64]
-    let stacked = Tensor::stack(&[keys, values], 0)?;
-
-    // Step 2: Swap the 'heads' dimension (index 2, size 16) 
-    // with the 'seq_len' dimension (index 3, size 128).
-    // since that is what python emplementation uses
-    // transposed shape: [2, 1, 128, 16, 64]
-    let packed = stacked.transpose(2, 3)?;
-    
-    // Step 3: Respect the offset
-    // example offset: 50
-    // packed_with_offset shape: [2, 1, 50, 16, 64]
-    let packed_with_offset = packed.narrow(2, 0, offset)?;
-    
-    // Step 4: Make the tensor contiguous in memory.
-    let packed_contiguous = packed_with_offset.contiguous()?;
-
-    Ok(packed_contiguous)
+/// The non-empty audio frames of [`TTSModel::stream_chunks`]
+fn chunk_audio(
+    items: impl Iterator<Item = Result<ChunkItem>>,
+) -> impl Iterator<Item = Result<Tensor>> {
+    items.filter_map(|item| match item {
+        Ok(ChunkItem::Frame(frame)) if frame.num_samples() > 0 => Some(Ok(frame.audio)),
+        Ok(_) => None,
+        Err(e) => Some(Err(e)),
+    })
 }
 
-fn unpack_kv_cache(packed: &Tensor) -> Result<(Tensor, Tensor)> {
-    // Step 1: Swap the 'seq_len' and 'heads' dimensions back.
-    // python implementation shape example: [2, 1, 128, 16, 64]
-    // candle implementation shape example: [2, 1, 16, 128, 64]
-    let transposed = packed.transpose(2, 3)?;
+/// Options for a text segment spoken with `prosody`
+///
+/// The lexicon is dropped because [`split_pause_segments`] already applied it.
+fn prosody_options(options: &GenerationOptions, prosody: Prosody) -> GenerationOptions {
+    GenerationOptions {
+        speed: (options.speed * prosody.rate).clamp(MIN_SPEED, MAX_SPEED),
+        lexicon: None,
+        ..options.clone()
+    }
+}
 
-    // Step 2: Extract keys and values.
-    // keys shape: [1, 16, 128, 64]
-    let keys = transposed.get(0)?;
-    
-    // values shape: [1, 16, 128, 64]
-    let values = transposed.get(1)?;
+/// Scale audio by a linear gain
+fn apply_gain(audio: Tensor, gain: f32) -> Result<Tensor> {
+    if gain == 1.0 {
+        Ok(audio)
+    } else {
+        Ok((audio * gain as f64)?)
+    }
+}
 
-    // Step 3: Make them contiguous.
-    let keys_contiguous = keys.contiguous()?;
-    let values_contiguous = values.contiguous()?;
+/// Split text into text segments interleaved with its voice and pause markers
+///
+/// Each text segment then goes through the options' lexicon and, with
+/// `normalize_text`, [`normalize_text`]. Both run after splitting, so commas
+/// they insert do not become pauses of their own, and the lexicon runs first
+/// so its entries see the text as written.
+fn split_pause_segments(text: &str, options: &GenerationOptions, prosody: Prosody) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut last_pos = 0;
+    for marker in crate::voice::parse_voice_markers(text) {
+        segments.extend(pause_segments(
+            &text[last_pos..marker.position],
+            options,
+            prosody,
+        ));
+        segments.push(Segment::Voice(marker.voice));
+        last_pos = marker.position + marker.original.len();
+    }
+    segments.extend(pause_segments(&text[last_pos..], options, prosody));
+    segments
+}
 
-    Ok((keys_contiguous, values_contiguous))
+/// Text segments interleaved with pauses for text without voice markers
+fn pause_segments(text: &str, options: &GenerationOptions, prosody: Prosody) -> Vec<Segment> {
+    let parsed = crate::pause::parse_text_with_pauses_with_config(text, &options.pauses);
+    let mut segments = Vec::new();
+    let text_segment = |s: &str| {
+        let text = options.apply_lexicon(s);
+        Segment::Text {
+            text: if options.normalize_text {
+                normalize_text(&text)
+            } else {
+                text
+            },
+            prosody,
+        }
+    };
+
+    // Interleave text chunks and pauses
+    let mut last_pos = 0;
+    for pause in &parsed.pauses {
+        if pause.position > last_pos {
+            let text_seg = &parsed.clean_text[last_pos..pause.position];
+            if !text_seg.trim().is_empty() {
+                segments.push(text_segment(text_seg));
+            }
+        }
+        segments.push(Segment::Pause(pause.duration_ms));
+
+        // Explicit pauses were replaced by a single space in clean_text
+        // Natural pauses (punctuation, whitespace) are still in clean_text
+        let end = if pause.original.starts_with("[pause:") {
+            pause.position + 1
+        } else {
+            pause.position + pause.original.len()
+        };
+        // A sentence-end marker's whitespace can span an explicit marker
+        last_pos = last_pos.max(end);
+    }
+    if last_pos < parsed.clean_text.len() {
+        let text_seg = &parsed.clean_text[last_pos..];
+        if !text_seg.trim().is_empty() {
+            segments.push(text_segment(text_seg));
+        }
+    }
+    segments
+}
+
+/// One decoded Mimi frame from a text chunk
+struct SegmentFrame {
+    audio: Tensor,
+    step: usize,
+    /// Whether EOS was first detected at this step
+    eos_detected: bool,
+}
+
+impl SegmentFrame {
+    /// Samples in this frame; zero when the speed resampler skipped it
+    fn num_samples(&self) -> usize {
+        self.audio.dims().last().copied().unwrap_or(0)
+    }
+}
+
+type SegmentFrames = Box<dyn Iterator<Item = Result<SegmentFrame>>>;
+
+/// One FlowLM latent `[B, ldim]` sampled for a text chunk, before Mimi decoding
+struct LatentFrame {
+    latent: Tensor,
+    step: usize,
+    /// Whether EOS was first detected at this step
+    eos_detected: bool,
+}
+
+type LatentFrames = Box<dyn Iterator<Item = Result<LatentFrame>>>;
+
+/// Frames of the best-of-N candidate kept for a chunk, with every candidate's score
+struct SelectedCandidate {
+    selected: usize,
+    scores: Vec<CandidateScore>,
+    frames: Vec<SegmentFrame>,
+}
+
+/// Item of a chunked stream: chunk boundaries around generated frames
+enum ChunkItem {
+    Started {
+        index: usize,
+        text: String,
+    },
+    Selected {
+        index: usize,
+        selected: usize,
+        scores: Vec<CandidateScore>,
+    },
+    Frame(SegmentFrame),
+    Finished {
+        index: usize,
+    },
+}
+
+/// Build the per-call sampling RNG, seeded when a seed is given.
+fn generation_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
 }
 
 /// Find the config file path for a variant
@@ -1458,8 +2587,8 @@ fn find_config_path(variant: &str) -> Result<std::path::PathBuf> {
 
 /// Prepare text for generation, stripping pause markers for TTS processing
 fn prepare_text_prompt(text: &str) -> String {
-    // First strip any explicit pause markers
-    let text = crate::pause::strip_pause_markers(text);
+    // First strip any explicit pause and voice markers
+    let text = crate::voice::strip_voice_markers(&crate::pause::strip_pause_markers(text));
 
     let mut text = text.trim().to_string();
     if text.is_empty() {
@@ -1470,11 +2599,15 @@ fn prepare_text_prompt(text: &str) -> String {
 
     let word_count = text.split_whitespace().count();
 
-    // Ensure first character is uppercase
+    // Capitalize the first character if its script has a one-to-one case
+    // mapping; caseless scripts and characters like "ß" stay as they are
     if let Some(first) = text.chars().next()
-        && !first.is_uppercase()
+        && first.is_lowercase()
     {
-        text = format!("{}{}", first.to_uppercase(), &text[first.len_utf8()..]);
+        let mut upper = first.to_uppercase();
+        if let (Some(upper), None) = (upper.next(), upper.next()) {
+            text = format!("{}{}", upper, &text[first.len_utf8()..]);
+        }
     }
 
     // Ensure ends with punctuation
@@ -1502,10 +2635,41 @@ pub fn estimate_frames_after_eos(text: &str) -> usize {
     }
 }
 
+/// Read WAV files for [`TTSModel::prepare_voice_clips`]
+#[cfg(not(target_arch = "wasm32"))]
+fn read_voice_clips<P: AsRef<std::path::Path>>(audio_paths: &[P]) -> Result<Vec<(Tensor, u32)>> {
+    audio_paths
+        .iter()
+        .map(|path| {
+            let path = path.as_ref();
+            crate::audio::read_wav(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read voice clip {:?}: {}", path, e))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_continuity_tail_keeps_window_across_clones() {
+        let frame = |v: f32| Tensor::full(v, (1, 1, 4), &Device::Cpu).unwrap();
+        let tail = ContinuityTail::default();
+        let segment = tail.clone();
+        for v in [1.0, 2.0, 3.0] {
+            segment.push(&frame(v), 2);
+        }
+        let kept: Vec<f32> = tail
+            .frames()
+            .iter()
+            .map(|f| f.flatten_all().unwrap().to_vec1::<f32>().unwrap()[0])
+            .collect();
+        assert_eq!(kept, vec![2.0, 3.0]);
+        segment.clear();
+        assert!(tail.frames().is_empty());
+    }
+
     #[test]
     fn test_prepare_text_prompt() {
         // Short texts (<5 words) get 8 spaces prepended
@@ -1517,6 +2681,120 @@ mod tests {
             prepare_text_prompt("one two three four five"),
             "One two three four five."
         );
+        // Only cased scripts with a one-to-one mapping are capitalized
+        assert_eq!(prepare_text_prompt("élan vital"), "        Élan vital.");
+        assert_eq!(prepare_text_prompt("ßtraße"), "        ßtraße.");
+        assert_eq!(prepare_text_prompt("你好"), "        你好.");
+    }
+
+    #[test]
+    fn test_split_pause_segments_switches_voices() {
+        let segments = split_pause_segments(
+            "Hi there. [voice:alba]Hello [pause:200ms] again.[voice:/v/guest.wav]",
+            &GenerationOptions::default(),
+            Prosody::default(),
+        );
+        let described: Vec<String> = segments
+            .into_iter()
+            .map(|s| match s {
+                Segment::Text { text, .. } => format!("text:{}", text.trim()),
+                Segment::Pause(ms) => format!("pause:{}", ms),
+                Segment::Voice(voice) => format!("voice:{}", voice),
+                Segment::Audio { src, .. } => format!("audio:{}", src),
+            })
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                "text:Hi there.",
+                "voice:alba",
+                "text:Hello",
+                "pause:200",
+                "text:again.",
+                "voice:/v/guest.wav",
+            ]
+        );
+        assert_eq!(
+            prepare_text_prompt("[voice:alba] Hello there, how are you?"),
+            "Hello there, how are you?"
+        );
+    }
+
+    #[test]
+    fn test_split_pause_segments_normalizes_text() {
+        let normalize = GenerationOptions::default();
+        let verbatim = GenerationOptions {
+            normalize_text: false,
+            ..Default::default()
+        };
+        let texts = |segments: Vec<Segment>| -> Vec<String> {
+            segments
+                .into_iter()
+                .filter_map(|s| match s {
+                    Segment::Text { text, .. } => Some(text.trim().to_string()),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(
+            texts(split_pause_segments(
+                "Pay $5 [pause:300ms] on 3/4/2025",
+                &normalize,
+                Prosody::default()
+            )),
+            vec!["Pay five dollars", "on March fourth, twenty twenty-five"]
+        );
+        assert_eq!(
+            texts(split_pause_segments(
+                "Pay $5 [pause:300ms] now",
+                &verbatim,
+                Prosody::default()
+            )),
+            vec!["Pay $5", "now"]
+        );
+
+        let mut lexicon = crate::Lexicon::new();
+        lexicon.insert_word("ACME", "acme corp").unwrap();
+        let with_lexicon = GenerationOptions {
+            lexicon: Some(std::sync::Arc::new(lexicon)),
+            ..Default::default()
+        };
+        assert_eq!(
+            texts(split_pause_segments(
+                "ACME owes $5 [pause:300ms] to ACME",
+                &with_lexicon,
+                Prosody::default()
+            )),
+            vec!["acme corp owes five dollars", "to acme corp"]
+        );
+    }
+
+    #[test]
+    fn test_prosody_options_scales_and_clamps_speed() {
+        let options = GenerationOptions {
+            speed: 2.0,
+            ..Default::default()
+        };
+        let slow = Prosody {
+            rate: 0.75,
+            volume: 1.0,
+        };
+        let fast = Prosody {
+            rate: 4.0,
+            volume: 1.0,
+        };
+        assert_eq!(prosody_options(&options, slow).speed, 1.5);
+        assert_eq!(prosody_options(&options, fast).speed, MAX_SPEED);
+    }
+
+    #[test]
+    fn test_latents_from_tensors() {
+        let latents = Tensor::zeros((3, 32), DType::F32, &Device::Cpu).unwrap();
+        let tensors = HashMap::from([("latents".to_string(), latents)]);
+        let loaded = TTSModel::latents_from_tensors(tensors).unwrap();
+        assert_eq!(loaded.dims(), &[3, 32]);
+
+        assert!(TTSModel::latents_from_tensors(HashMap::new()).is_err());
     }
 
     #[test]
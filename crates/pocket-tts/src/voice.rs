@@ -0,0 +1,112 @@
+//! Inline `[voice:...]` markers and how their voices are resolved
+//!
+//! Long-form text can switch speakers between segments:
+//!
+//! ```text
+//! [voice:/voices/host.wav] Welcome back. [voice:/voices/guest.safetensors] Thanks for having me!
+//! ```
+//!
+//! Text before the first marker uses the voice state passed to the call.
+//! `generate_stream_long*` and `generate_events*` resolve every distinct spec
+//! once per call through the model's [`VoiceResolver`], set with
+//! [`crate::TTSModel::with_voice_resolver`].
+
+use crate::{ModelState, TTSModel};
+use anyhow::Result;
+use regex::Regex;
+use std::sync::{Arc, LazyLock};
+
+/// Voice switch marker found in text
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoiceMarker {
+    /// Original text that was matched
+    pub original: String,
+    /// Voice spec, e.g. `marius` or `/voices/host.safetensors`
+    pub voice: String,
+    /// Position in the original text (byte offset)
+    pub position: usize,
+}
+
+static VOICE_MARKER_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[voice:\s*([^\]\s][^\]]*?)\s*\]").unwrap());
+
+/// Parse `[voice:...]` markers from text
+///
+/// # Example
+/// ```
+/// use pocket_tts::voice::parse_voice_markers;
+///
+/// let voices = parse_voice_markers("[voice:alba] Hi! [voice: marius ] Hello.");
+/// assert_eq!(voices[0].voice, "alba");
+/// assert_eq!(voices[1].voice, "marius");
+/// assert_eq!(voices[1].position, 17);
+/// ```
+pub fn parse_voice_markers(text: &str) -> Vec<VoiceMarker> {
+    VOICE_MARKER_REGEX
+        .captures_iter(text)
+        .map(|cap| {
+            let full_match = cap.get(0).unwrap();
+            VoiceMarker {
+                original: full_match.as_str().to_string(),
+                voice: cap[1].to_string(),
+                position: full_match.start(),
+            }
+        })
+        .collect()
+}
+
+/// Remove voice markers from text, leaving a space in their place
+pub fn strip_voice_markers(text: &str) -> String {
+    VOICE_MARKER_REGEX.replace_all(text, " ").to_string()
+}
+
+/// Turns the spec of a `[voice:...]` marker into a voice state
+pub trait VoiceResolver: Send + Sync {
+    fn resolve(&self, model: &TTSModel, spec: &str) -> Result<Arc<ModelState>>;
+}
+
+/// Default resolver: local `.wav` audio prompts and `.safetensors` prompt files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileVoiceResolver;
+
+impl VoiceResolver for FileVoiceResolver {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn resolve(&self, model: &TTSModel, spec: &str) -> Result<Arc<ModelState>> {
+        let path = std::path::Path::new(spec);
+        if !path.exists() {
+            anyhow::bail!("voice file {:?} not found", path);
+        }
+        let is_safetensors = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("safetensors"));
+        let state = if is_safetensors {
+            model.get_voice_state_from_prompt_file(path)?
+        } else {
+            model.get_voice_state(path)?
+        };
+        Ok(Arc::new(state))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn resolve(&self, _model: &TTSModel, spec: &str) -> Result<Arc<ModelState>> {
+        anyhow::bail!("voice files cannot be loaded in WASM: {}", spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_voice_markers() {
+        let text = "Host line. [voice:/tmp/guest voice.wav]Guest line [voice:] [voice:alba]";
+        let voices = parse_voice_markers(text);
+        let specs: Vec<&str> = voices.iter().map(|v| v.voice.as_str()).collect();
+        assert_eq!(specs, vec!["/tmp/guest voice.wav", "alba"]);
+        assert_eq!(&text[voices[0].position..][..7], "[voice:");
+        assert_eq!(
+            strip_voice_markers(text),
+            "Host line.  Guest line [voice:]  "
+        );
+    }
+}
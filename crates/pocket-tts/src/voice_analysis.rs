@@ -0,0 +1,398 @@
+//! Quality report for reference audio before voice cloning
+//!
+//! A two-second, clipped or noisy clip clones into a poor voice, and nothing
+//! in the generated audio says why. [`analyze_voice_prompt`] measures the
+//! properties that matter for cloning and grades each one:
+//!
+//! | Check       | Warn                         | Fail            |
+//! |-------------|------------------------------|-----------------|
+//! | speech      | under 5 s, or clip over 60 s | under 3 s       |
+//! | sample rate | under 16 kHz                 | -               |
+//! | clipping    | over 0.1% of samples         | over 1%         |
+//! | noise       | estimated SNR under 20 dB    | under 10 dB     |
+//! | silence     | over half the clip           | -               |
+//! | loudness    | voiced RMS under -40 dBFS    | digital silence |
+//!
+//! The measurements are heuristics on 20 ms frames: frames within 40 dB of
+//! the loudest frame count as voiced, and the SNR estimate compares the level
+//! of the voiced frames with the quietest tenth of all frames.
+
+use anyhow::Result;
+use candle_core::Tensor;
+
+use crate::voice_preprocess::rms_db;
+
+/// Length of the analysis frames
+const FRAME_MS: usize = 20;
+/// Frames this many dB below the loudest frame count as silence
+const SILENCE_THRESHOLD_DB: f32 = 40.0;
+/// Level reported for digital silence
+const FLOOR_DB: f32 = -100.0;
+/// Samples at or above this magnitude count as clipped
+const CLIP_LEVEL: f32 = 0.999;
+/// Share of the quietest frames that estimates the noise floor
+const NOISE_FLOOR_QUANTILE: f32 = 0.1;
+
+const MIN_SPEECH_SECS: f32 = 3.0;
+const RECOMMENDED_SPEECH_SECS: f32 = 5.0;
+const MAX_DURATION_SECS: f32 = 60.0;
+const MIN_SAMPLE_RATE: u32 = 16_000;
+const CLIPPING_WARN_PERCENT: f32 = 0.1;
+const CLIPPING_FAIL_PERCENT: f32 = 1.0;
+const SNR_WARN_DB: f32 = 20.0;
+const SNR_FAIL_DB: f32 = 10.0;
+const SILENCE_WARN_RATIO: f32 = 0.5;
+const QUIET_LOUDNESS_DB: f32 = -40.0;
+
+/// Grade of a single check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceCheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl std::fmt::Display for VoiceCheckStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+        })
+    }
+}
+
+/// One graded property of the clip
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct VoiceCheck {
+    /// Short identifier, e.g. `clipping`
+    pub name: &'static str,
+    pub status: VoiceCheckStatus,
+    /// ASCII explanation, with a recommendation unless the check passed
+    pub message: String,
+}
+
+/// Result of [`analyze_voice_prompt`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct VoiceAnalysis {
+    pub duration_secs: f32,
+    /// Sample rate of the audio as given, before any resampling
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Highest sample magnitude in dBFS
+    pub peak_db: f32,
+    /// Share of samples at full scale, in percent
+    pub clipping_percent: f32,
+    /// Level of the voiced frames over the noise floor
+    pub snr_db: f32,
+    /// Share of silent frames, from 0 to 1
+    pub silence_ratio: f32,
+    /// RMS level of the voiced frames in dBFS
+    pub loudness_db: f32,
+    /// Every check, in the order of the table in the module docs
+    pub checks: Vec<VoiceCheck>,
+}
+
+impl VoiceAnalysis {
+    /// Worst status of all checks
+    pub fn status(&self) -> VoiceCheckStatus {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(VoiceCheckStatus::Pass)
+    }
+
+    /// `status: message` for every check that did not pass
+    pub fn warnings(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .filter(|check| check.status != VoiceCheckStatus::Pass)
+            .map(|check| format!("{}: {}", check.status, check.message))
+            .collect()
+    }
+
+    /// Seconds of voiced audio
+    pub fn speech_secs(&self) -> f32 {
+        self.duration_secs * (1.0 - self.silence_ratio)
+    }
+}
+
+/// Measure and grade reference audio (`[C, T]` at its original sample rate)
+pub fn analyze_voice_prompt(audio: &Tensor, sample_rate: u32) -> Result<VoiceAnalysis> {
+    let (channels, len) = audio.dims2()?;
+    if sample_rate == 0 {
+        anyhow::bail!("Invalid sample rate 0");
+    }
+    let samples = audio.to_dtype(candle_core::DType::F32)?;
+    let all: Vec<f32> = samples.flatten_all()?.to_vec1()?;
+    let mono: Vec<f32> = samples.mean(0)?.to_vec1()?;
+
+    let peak = all.iter().fold(0.0f32, |peak, &s| peak.max(s.abs()));
+    let clipped = all.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+    let clipping_percent = if all.is_empty() {
+        0.0
+    } else {
+        100.0 * clipped as f32 / all.len() as f32
+    };
+
+    let frame_len = (sample_rate as usize * FRAME_MS / 1000).max(1);
+    let levels: Vec<f32> = mono
+        .chunks(frame_len)
+        .map(|frame| rms_db(frame).max(FLOOR_DB))
+        .collect();
+    let loudest = levels.iter().copied().fold(FLOOR_DB, f32::max);
+    let voiced: Vec<bool> = levels
+        .iter()
+        .map(|&level| loudest > FLOOR_DB && level > loudest - SILENCE_THRESHOLD_DB)
+        .collect();
+    let voiced_samples: Vec<f32> = mono
+        .chunks(frame_len)
+        .zip(&voiced)
+        .filter(|(_, voiced)| **voiced)
+        .flat_map(|(frame, _)| frame.iter().copied())
+        .collect();
+    let loudness_db = rms_db(&voiced_samples).max(FLOOR_DB);
+    let silence_ratio = if levels.is_empty() {
+        1.0
+    } else {
+        voiced.iter().filter(|v| !**v).count() as f32 / levels.len() as f32
+    };
+    let snr_db = (loudness_db - noise_floor_db(&levels)).max(0.0);
+
+    let mut analysis = VoiceAnalysis {
+        duration_secs: len as f32 / sample_rate as f32,
+        sample_rate,
+        channels,
+        peak_db: to_db(peak),
+        clipping_percent,
+        snr_db,
+        silence_ratio,
+        loudness_db,
+        checks: Vec::new(),
+    };
+    analysis.checks = grade(&analysis);
+    Ok(analysis)
+}
+
+/// [`analyze_voice_prompt`] for the bytes of a WAV file
+pub fn analyze_voice_prompt_bytes(wav_bytes: &[u8]) -> Result<VoiceAnalysis> {
+    let (audio, sample_rate) = crate::audio::read_wav_from_bytes(wav_bytes)?;
+    analyze_voice_prompt(&audio, sample_rate)
+}
+
+/// Level of the quietest [`NOISE_FLOOR_QUANTILE`] of frames
+fn noise_floor_db(levels: &[f32]) -> f32 {
+    if levels.is_empty() {
+        return FLOOR_DB;
+    }
+    let mut sorted = levels.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let index = ((sorted.len() - 1) as f32 * NOISE_FLOOR_QUANTILE) as usize;
+    sorted[index]
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(FLOOR_DB)
+    } else {
+        FLOOR_DB
+    }
+}
+
+fn grade(a: &VoiceAnalysis) -> Vec<VoiceCheck> {
+    use VoiceCheckStatus::{Fail, Pass, Warn};
+    let check = |name, status, message: String| VoiceCheck {
+        name,
+        status,
+        message,
+    };
+
+    if a.peak_db <= FLOOR_DB {
+        return vec![check(
+            "loudness",
+            Fail,
+            "the clip is silent; record the speaker again".to_string(),
+        )];
+    }
+
+    let speech = a.speech_secs();
+    let speech_status = if speech < MIN_SPEECH_SECS {
+        Fail
+    } else if speech < RECOMMENDED_SPEECH_SECS || a.duration_secs > MAX_DURATION_SECS {
+        Warn
+    } else {
+        Pass
+    };
+    let speech_message = if a.duration_secs > MAX_DURATION_SECS && speech >= MIN_SPEECH_SECS {
+        format!(
+            "the clip is {:.0} s long; 10 to 30 s of speech is enough and clones faster",
+            a.duration_secs
+        )
+    } else if speech_status == Pass {
+        format!("{:.1} s of speech", speech)
+    } else {
+        format!(
+            "only {:.1} s of speech; use at least {:.0} s, ideally 10 s or more",
+            speech, RECOMMENDED_SPEECH_SECS
+        )
+    };
+
+    let (rate_status, rate_message) = if a.sample_rate < MIN_SAMPLE_RATE {
+        (
+            Warn,
+            format!(
+                "{} Hz is narrowband (telephone) audio; use a 16 kHz or better recording",
+                a.sample_rate
+            ),
+        )
+    } else {
+        (Pass, format!("{} Hz", a.sample_rate))
+    };
+
+    let clipping_status = if a.clipping_percent > CLIPPING_FAIL_PERCENT {
+        Fail
+    } else if a.clipping_percent > CLIPPING_WARN_PERCENT {
+        Warn
+    } else {
+        Pass
+    };
+    let clipping_message = match clipping_status {
+        Pass => format!("peak {:.1} dBFS", a.peak_db),
+        _ => format!(
+            "{:.2}% of samples are clipped; record with lower input gain",
+            a.clipping_percent
+        ),
+    };
+
+    let snr_status = if a.snr_db < SNR_FAIL_DB {
+        Fail
+    } else if a.snr_db < SNR_WARN_DB {
+        Warn
+    } else {
+        Pass
+    };
+    let snr_message = match snr_status {
+        Pass => format!("estimated SNR {:.0} dB", a.snr_db),
+        _ => format!(
+            "estimated SNR {:.0} dB; record in a quieter room or closer to the microphone",
+            a.snr_db
+        ),
+    };
+
+    let (silence_status, silence_message) = if a.silence_ratio > SILENCE_WARN_RATIO {
+        (
+            Warn,
+            format!(
+                "{:.0}% of the clip is silence; trim it or enable voice preprocessing",
+                a.silence_ratio * 100.0
+            ),
+        )
+    } else {
+        (Pass, format!("{:.0}% silence", a.silence_ratio * 100.0))
+    };
+
+    let (loudness_status, loudness_message) = if a.loudness_db < QUIET_LOUDNESS_DB {
+        (
+            Warn,
+            format!(
+                "speech is very quiet ({:.0} dBFS); raise the input gain or normalize the clip",
+                a.loudness_db
+            ),
+        )
+    } else {
+        (Pass, format!("speech at {:.0} dBFS", a.loudness_db))
+    };
+
+    vec![
+        check("speech", speech_status, speech_message),
+        check("sample_rate", rate_status, rate_message),
+        check("clipping", clipping_status, clipping_message),
+        check("noise", snr_status, snr_message),
+        check("silence", silence_status, silence_message),
+        check("loudness", loudness_status, loudness_message),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::Device;
+
+    const SAMPLE_RATE: u32 = 24_000;
+
+    /// 400 ms of a 220 Hz tone, then 100 ms of silence, repeated, plus
+    /// deterministic white noise of the given amplitude
+    fn tone_with_pauses(secs: f32, amplitude: f32, noise: f32) -> Tensor {
+        let len = (secs * SAMPLE_RATE as f32) as usize;
+        let mut state: u32 = 12345;
+        let samples: Vec<f32> = (0..len)
+            .map(|i| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let white = (state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 - 1.0;
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let tone = if (i % 12_000) < 9_600 {
+                    amplitude * (2.0 * std::f32::consts::PI * 220.0 * t).sin()
+                } else {
+                    0.0
+                };
+                (tone + noise * white).clamp(-1.0, 1.0)
+            })
+            .collect();
+        Tensor::from_vec(samples, (1, len), &Device::Cpu).unwrap()
+    }
+
+    fn status_of(analysis: &VoiceAnalysis, name: &str) -> VoiceCheckStatus {
+        analysis
+            .checks
+            .iter()
+            .find(|check| check.name == name)
+            .unwrap()
+            .status
+    }
+
+    #[test]
+    fn test_clean_clip_passes() {
+        let analysis =
+            analyze_voice_prompt(&tone_with_pauses(10.0, 0.3, 0.0), SAMPLE_RATE).unwrap();
+        assert!((analysis.duration_secs - 10.0).abs() < 1e-3);
+        assert_eq!(analysis.channels, 1);
+        assert!((analysis.silence_ratio - 0.2).abs() < 0.01);
+        assert!(analysis.snr_db > 40.0);
+        assert_eq!(analysis.clipping_percent, 0.0);
+        assert_eq!(analysis.status(), VoiceCheckStatus::Pass, "{:?}", analysis);
+        assert!(analysis.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_short_clip_fails() {
+        let analysis = analyze_voice_prompt(&tone_with_pauses(0.5, 0.3, 0.0), 8_000).unwrap();
+        assert_eq!(status_of(&analysis, "speech"), VoiceCheckStatus::Fail);
+        assert_eq!(status_of(&analysis, "sample_rate"), VoiceCheckStatus::Warn);
+        assert_eq!(analysis.status(), VoiceCheckStatus::Fail);
+        assert!(analysis.warnings()[0].starts_with("fail: only"));
+    }
+
+    #[test]
+    fn test_clipping_and_noise_are_graded() {
+        let clipped = analyze_voice_prompt(&tone_with_pauses(10.0, 2.0, 0.0), SAMPLE_RATE).unwrap();
+        assert!(clipped.clipping_percent > CLIPPING_FAIL_PERCENT);
+        assert_eq!(status_of(&clipped, "clipping"), VoiceCheckStatus::Fail);
+
+        let noisy = analyze_voice_prompt(&tone_with_pauses(10.0, 0.3, 0.2), SAMPLE_RATE).unwrap();
+        assert!(noisy.snr_db < SNR_FAIL_DB, "{}", noisy.snr_db);
+        assert_eq!(status_of(&noisy, "noise"), VoiceCheckStatus::Fail);
+        let hiss = analyze_voice_prompt(&tone_with_pauses(10.0, 0.3, 0.05), SAMPLE_RATE).unwrap();
+        assert_eq!(status_of(&hiss, "noise"), VoiceCheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_silent_clip_fails() {
+        let silence = Tensor::zeros((2, 48_000), candle_core::DType::F32, &Device::Cpu).unwrap();
+        let analysis = analyze_voice_prompt(&silence, SAMPLE_RATE).unwrap();
+        assert_eq!(analysis.channels, 2);
+        assert_eq!(analysis.status(), VoiceCheckStatus::Fail);
+        assert_eq!(analysis.silence_ratio, 1.0);
+    }
+}
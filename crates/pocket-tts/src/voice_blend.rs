@@ -0,0 +1,151 @@
+//! Blending cloned voices into new ones
+//!
+//! A conditioning tensor from [`crate::TTSModel::get_conditioning`] is a
+//! sequence of frames in FlowLM space. [`blend_voices`] stretches every
+//! input to a common length (the weighted mean of the input lengths) by
+//! linear interpolation over time, then takes the weighted average frame by
+//! frame. Time alignment keeps the prompt a plausible frame sequence, unlike
+//! pooling it into a single frame, so the result can be replayed through
+//! FlowLM like any recorded prompt:
+//!
+//! ```ignore
+//! let a = model.get_conditioning(&audio_a)?;
+//! let b = model.get_conditioning(&audio_b)?;
+//! let brand = pocket_tts::blend_voices(&[(a, 0.7), (b, 0.3)])?;
+//! let voice_state = model.get_voice_state_from_prompt_tensor(&brand)?;
+//! ```
+
+use anyhow::Result;
+use candle_core::{DType, Tensor};
+
+/// Weighted, time-aligned mix of conditioning tensors (`[1, T, dim]` each)
+///
+/// Weights must be non-negative with a positive sum; they are normalized, so
+/// `0.7`/`0.3` and `7`/`3` blend alike.
+pub fn blend_voices(voices: &[(Tensor, f32)]) -> Result<Tensor> {
+    let Some((first, _)) = voices.first() else {
+        anyhow::bail!("blend_voices needs at least one voice");
+    };
+    let dim = first.dim(2)?;
+    let mut total_weight = 0.0;
+    let mut weighted_frames = 0.0;
+    for (index, (conditioning, weight)) in voices.iter().enumerate() {
+        if !weight.is_finite() || *weight < 0.0 {
+            anyhow::bail!("Voice {} has invalid blend weight {}", index, weight);
+        }
+        let (batch, frames, voice_dim) = conditioning.dims3()?;
+        if batch != 1 || frames == 0 || voice_dim != dim {
+            anyhow::bail!(
+                "Voice {} has conditioning shape {:?}, expected [1, frames, {}]",
+                index,
+                conditioning.dims(),
+                dim
+            );
+        }
+        total_weight += *weight as f64;
+        weighted_frames += *weight as f64 * frames as f64;
+    }
+    if total_weight <= 0.0 {
+        anyhow::bail!("Blend weights must not all be zero");
+    }
+
+    let frames = ((weighted_frames / total_weight).round() as usize).max(1);
+    let mut blended: Option<Tensor> = None;
+    for (conditioning, weight) in voices {
+        if *weight == 0.0 {
+            continue;
+        }
+        let aligned = stretch_frames(&conditioning.to_dtype(DType::F32)?, frames)?;
+        let scaled = (aligned * (*weight as f64 / total_weight))?;
+        blended = Some(match blended {
+            Some(sum) => (sum + scaled.to_device(first.device())?)?,
+            None => scaled,
+        });
+    }
+    blended.ok_or_else(|| anyhow::anyhow!("Blend weights must not all be zero"))
+}
+
+/// Linearly interpolate `[B, T, D]` to `[B, frames, D]` along time
+fn stretch_frames(conditioning: &Tensor, frames: usize) -> Result<Tensor> {
+    let source = conditioning.dim(1)?;
+    if source == frames {
+        return Ok(conditioning.clone());
+    }
+
+    let mut lower = Vec::with_capacity(frames);
+    let mut upper = Vec::with_capacity(frames);
+    let mut fraction = Vec::with_capacity(frames);
+    for i in 0..frames {
+        let position = if frames == 1 {
+            (source - 1) as f64 / 2.0
+        } else {
+            i as f64 * (source - 1) as f64 / (frames - 1) as f64
+        };
+        let below = position.floor() as usize;
+        lower.push(below as u32);
+        upper.push((below + 1).min(source - 1) as u32);
+        fraction.push((position - below as f64) as f32);
+    }
+
+    let device = conditioning.device();
+    let lower = conditioning.index_select(&Tensor::new(lower, device)?, 1)?;
+    let upper = conditioning.index_select(&Tensor::new(upper, device)?, 1)?;
+    let fraction = Tensor::new(fraction, device)?.reshape((1, frames, 1))?;
+    let stretched =
+        (lower.broadcast_mul(&(1.0 - &fraction)?)? + upper.broadcast_mul(&fraction)?)?;
+    Ok(stretched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::Device;
+
+    fn ramp(frames: usize, scale: f32) -> Tensor {
+        let values: Vec<f32> = (0..frames * 2).map(|v| (v / 2) as f32 * scale).collect();
+        Tensor::from_vec(values, (1, frames, 2), &Device::Cpu).unwrap()
+    }
+
+    fn frames_of(t: &Tensor) -> Vec<f32> {
+        t.squeeze(0)
+            .unwrap()
+            .narrow(1, 0, 1)
+            .unwrap()
+            .flatten_all()
+            .unwrap()
+            .to_vec1()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_stretch_frames_interpolates() {
+        let stretched = stretch_frames(&ramp(3, 1.0), 5).unwrap();
+        assert_eq!(frames_of(&stretched), vec![0.0, 0.5, 1.0, 1.5, 2.0]);
+        let squeezed = stretch_frames(&ramp(5, 1.0), 3).unwrap();
+        assert_eq!(frames_of(&squeezed), vec![0.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_blend_weights_and_lengths() {
+        // 4 and 8 frames, weighted 3:1 -> 5 frames
+        let a = Tensor::ones((1, 4, 2), DType::F32, &Device::Cpu).unwrap();
+        let b = Tensor::zeros((1, 8, 2), DType::F32, &Device::Cpu).unwrap();
+        let blended = blend_voices(&[(a.clone(), 3.0), (b, 1.0)]).unwrap();
+        assert_eq!(blended.dims(), &[1, 5, 2]);
+        assert!(frames_of(&blended).iter().all(|v| (v - 0.75).abs() < 1e-6));
+
+        // A single voice comes back unchanged
+        let same = blend_voices(&[(a.clone(), 0.2)]).unwrap();
+        assert_eq!(frames_of(&same), frames_of(&a));
+    }
+
+    #[test]
+    fn test_blend_rejects_bad_input() {
+        let a = Tensor::ones((1, 4, 2), DType::F32, &Device::Cpu).unwrap();
+        let wide = Tensor::ones((1, 4, 3), DType::F32, &Device::Cpu).unwrap();
+        assert!(blend_voices(&[]).is_err());
+        assert!(blend_voices(&[(a.clone(), -1.0)]).is_err());
+        assert!(blend_voices(&[(a.clone(), 0.0)]).is_err());
+        assert!(blend_voices(&[(a, 1.0), (wide, 1.0)]).is_err());
+    }
+}
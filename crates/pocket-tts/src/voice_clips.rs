@@ -0,0 +1,161 @@
+//! Cloning one voice from several reference recordings
+//!
+//! A few short, clean clips of a speaker are easier to come by than one long
+//! recording. [`VoiceClipJoin`] levels every clip to the same loudness, mixes
+//! it down to mono and joins the clips with a short pause into a single
+//! reference, stopping at a total duration cap. The model then encodes the
+//! result like any other recording:
+//!
+//! ```ignore
+//! let voice_state = model.get_voice_state_from_files(&["take1.wav", "take2.wav"])?;
+//! ```
+
+use anyhow::Result;
+use candle_core::Tensor;
+
+use crate::voice_preprocess::VoicePreprocessing;
+
+/// A clip cut to fit the duration cap is dropped if less than this remains
+const MIN_CLIP_SECS: f32 = 1.0;
+
+/// How [`VoiceClipJoin::join`] combines reference clips
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct VoiceClipJoin {
+    /// Silence between consecutive clips
+    pub gap_ms: u32,
+    /// Total length of the joined reference, gaps included; later clips are
+    /// cut or dropped to fit
+    pub max_total_secs: f32,
+    /// RMS level of the voiced part of every clip in dBFS; `None` keeps the
+    /// clips as recorded
+    pub target_loudness_db: Option<f32>,
+}
+
+impl Default for VoiceClipJoin {
+    fn default() -> Self {
+        Self {
+            gap_ms: 300,
+            max_total_secs: 30.0,
+            target_loudness_db: Some(-20.0),
+        }
+    }
+}
+
+impl VoiceClipJoin {
+    /// Join clips (`[C, T]` at their own sample rates, in order) into one
+    /// mono `[1, T]` clip at `sample_rate`
+    pub fn join(&self, clips: &[(Tensor, u32)], sample_rate: u32) -> Result<Tensor> {
+        let Some((first, _)) = clips.first() else {
+            anyhow::bail!("No voice clips to join");
+        };
+        let leveller = self
+            .target_loudness_db
+            .map(|target_loudness_db| VoicePreprocessing {
+                remove_dc: false,
+                high_pass_hz: None,
+                trim_silence: false,
+                max_silence_ms: None,
+                target_loudness_db: Some(target_loudness_db),
+                max_duration_secs: None,
+                ..Default::default()
+            });
+
+        let rate = sample_rate as f32;
+        let max_len = (self.max_total_secs.max(0.0) * rate) as usize;
+        let gap_len = (self.gap_ms as f32 / 1000.0 * rate) as usize;
+        let min_len = (MIN_CLIP_SECS * rate) as usize;
+        let mut joined: Vec<f32> = Vec::with_capacity(max_len);
+        for (index, (clip, clip_rate)) in clips.iter().enumerate() {
+            let clip = crate::audio::resample(clip, *clip_rate, sample_rate)?;
+            let mono = clip.mean(0)?.to_vec1::<f32>()?;
+            let mut samples = match &leveller {
+                Some(leveller) => leveller.process(&mono, sample_rate),
+                None => mono,
+            };
+            if samples.is_empty() {
+                anyhow::bail!("Voice clip {} is empty", index);
+            }
+
+            let gap = if joined.is_empty() { 0 } else { gap_len };
+            let room = max_len.saturating_sub(joined.len() + gap);
+            if room < min_len.min(samples.len()) {
+                tracing::info!(
+                    "Voice clips {}..{} dropped: over the {} s cap",
+                    index,
+                    clips.len(),
+                    self.max_total_secs
+                );
+                break;
+            }
+            samples.truncate(room);
+            joined.resize(joined.len() + gap, 0.0);
+            joined.extend(samples);
+        }
+
+        if joined.is_empty() {
+            anyhow::bail!("Voice clips do not fit into {} s", self.max_total_secs);
+        }
+        let len = joined.len();
+        Ok(Tensor::from_vec(joined, (1, len), first.device())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candle_core::Device;
+
+    const RATE: u32 = 1000;
+
+    fn tone(secs: f32, amplitude: f32, channels: usize) -> Tensor {
+        let len = (secs * RATE as f32) as usize;
+        let samples: Vec<f32> = (0..channels * len)
+            .map(|i| amplitude * (i as f32 * 0.3).sin())
+            .collect();
+        Tensor::from_vec(samples, (channels, len), &Device::Cpu).unwrap()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_join_levels_clips_and_inserts_gaps() {
+        let join = VoiceClipJoin::default();
+        let clips = [(tone(2.0, 0.01, 1), RATE), (tone(3.0, 0.5, 2), RATE)];
+        let joined = join.join(&clips, RATE).unwrap();
+        let samples = joined.squeeze(0).unwrap().to_vec1::<f32>().unwrap();
+        assert_eq!(samples.len(), 2000 + 300 + 3000);
+
+        assert!(samples[2000..2300].iter().all(|&s| s == 0.0));
+        let (a, b) = (rms(&samples[..2000]), rms(&samples[2300..]));
+        assert!((a / b - 1.0).abs() < 0.05, "{a} vs {b}");
+        assert!((20.0 * a.log10() + 20.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_join_respects_duration_cap() {
+        let join = VoiceClipJoin {
+            max_total_secs: 5.0,
+            ..Default::default()
+        };
+        // 3 s + gap + 1.7 s of the second clip; the third does not fit
+        let clips = [
+            (tone(3.0, 0.2, 1), RATE),
+            (tone(4.0, 0.2, 1), RATE),
+            (tone(4.0, 0.2, 1), RATE),
+        ];
+        let joined = join.join(&clips, RATE).unwrap();
+        assert_eq!(joined.dims(), &[1, 5000]);
+
+        // A clip at another sample rate is resampled
+        let joined = VoiceClipJoin::default()
+            .join(&[(tone(1.0, 0.2, 1), 2 * RATE)], RATE)
+            .unwrap();
+        let len = joined.dim(1).unwrap();
+        assert!((490..=500).contains(&len), "{len}");
+
+        assert!(VoiceClipJoin::default().join(&[], RATE).is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Cleanup of reference audio before voice cloning
+//!
+//! Uploaded clips often start with seconds of silence, carry room rumble or
+//! a DC offset, and range from whispered to clipped. Mimi encodes all of
+//! that into the voice prompt. [`VoicePreprocessing`] runs these steps on the
+//! mono signal at the model sample rate:
+//!
+//! 1. remove the DC offset
+//! 2. high-pass filter (second-order Butterworth)
+//! 3. trim silence at both ends
+//! 4. shorten internal pauses to `max_silence_ms`
+//! 5. crop to the `max_duration_secs` window with the most energy
+//! 6. normalize the RMS of the voiced frames to `target_loudness_db`, then
+//!    lower the gain again if that would push peaks past full scale
+//!
+//! Set it with [`crate::TTSModel::with_voice_preprocessing`]. Every entry point
+//! that clones a voice from audio (`get_voice_state`,
+//! `get_voice_state_from_bytes`, `create_voice_prompt`) then applies it.
+
+use anyhow::Result;
+use candle_core::Tensor;
+
+/// Length of the frames used for silence detection and energy
+const FRAME_MS: usize = 20;
+/// Audio kept around speech when trimming the ends
+const TRIM_MARGIN_MS: usize = 60;
+/// Highest peak after loudness normalization
+const PEAK_LIMIT: f32 = 0.99;
+
+/// Settings of the reference audio cleanup; `None` disables a step
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct VoicePreprocessing {
+    /// Subtract the mean of the signal
+    pub remove_dc: bool,
+    /// High-pass cutoff in Hz, removing rumble and hum below the voice
+    pub high_pass_hz: Option<f32>,
+    /// Remove silence before the first and after the last voiced frame
+    pub trim_silence: bool,
+    /// Shorten pauses longer than this many milliseconds to this length
+    pub max_silence_ms: Option<u32>,
+    /// Frames this many dB below the loudest frame count as silence
+    pub silence_threshold_db: f32,
+    /// RMS level of the voiced frames in dBFS
+    pub target_loudness_db: Option<f32>,
+    /// Keep at most this many seconds, choosing the window with the most
+    /// energy
+    pub max_duration_secs: Option<f32>,
+}
+
+impl Default for VoicePreprocessing {
+    fn default() -> Self {
+        Self {
+            remove_dc: true,
+            high_pass_hz: Some(80.0),
+            trim_silence: true,
+            max_silence_ms: Some(300),
+            silence_threshold_db: 40.0,
+            target_loudness_db: Some(-20.0),
+            max_duration_secs: Some(30.0),
+        }
+    }
+}
+
+impl VoicePreprocessing {
+    /// Clean up `audio` (`[C, T]`), returning mono `[1, T']`
+    pub fn apply(&self, audio: &Tensor, sample_rate: u32) -> Result<Tensor> {
+        let mono = audio.mean_keepdim(0)?.squeeze(0)?.to_vec1::<f32>()?;
+        let processed = self.process(&mono, sample_rate);
+        let len = processed.len();
+        Ok(Tensor::from_vec(processed, (1, len), audio.device())?)
+    }
+
+    /// Clean up mono samples
+    pub fn process(&self, samples: &[f32], sample_rate: u32) -> Vec<f32> {
+        let mut samples = samples.to_vec();
+        if samples.is_empty() {
+            return samples;
+        }
+        if self.remove_dc {
+            let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64;
+            samples.iter_mut().for_each(|s| *s -= mean as f32);
+        }
+        if let Some(cutoff) = self.high_pass_hz
+            && cutoff > 0.0
+            && cutoff < sample_rate as f32 / 2.0
+        {
+            high_pass(&mut samples, cutoff, sample_rate as f32);
+        }
+
+        let frame_len = (sample_rate as usize * FRAME_MS / 1000).max(1);
+        let voiced = self.voiced_frames(&samples, frame_len);
+        if voiced.iter().any(|&v| v) {
+            if self.trim_silence {
+                samples = trim_ends(&samples, &voiced, frame_len, sample_rate);
+            }
+            if let Some(max_ms) = self.max_silence_ms {
+                let voiced = self.voiced_frames(&samples, frame_len);
+                let max_frames = (max_ms as usize / FRAME_MS).max(1);
+                samples = collapse_silences(&samples, &voiced, frame_len, max_frames);
+            }
+        }
+        if let Some(max_secs) = self.max_duration_secs {
+            let max_len = (max_secs.max(0.0) * sample_rate as f32) as usize;
+            samples = crop_loudest(&samples, max_len.max(frame_len), frame_len);
+        }
+        if let Some(target_db) = self.target_loudness_db {
+            let voiced = self.voiced_frames(&samples, frame_len);
+            normalize_loudness(&mut samples, &voiced, frame_len, target_db);
+        }
+        samples
+    }
+
+    /// Per-frame flags: within `silence_threshold_db` of the loudest frame
+    fn voiced_frames(&self, samples: &[f32], frame_len: usize) -> Vec<bool> {
+        let levels: Vec<f32> = samples.chunks(frame_len).map(rms_db).collect();
+        let loudest = levels.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let threshold = loudest - self.silence_threshold_db.abs();
+        levels
+            .iter()
+            .map(|&level| loudest.is_finite() && level > threshold)
+            .collect()
+    }
+}
+
+/// RMS level in dBFS (`-inf` for digital silence)
+pub(crate) fn rms_db(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let mean_square = samples.iter().map(|&s| s * s).sum::<f32>() / samples.len() as f32;
+    10.0 * mean_square.log10()
+}
+
+/// Second-order Butterworth high-pass (RBJ biquad), in place
+fn high_pass(samples: &mut [f32], cutoff: f32, sample_rate: f32) {
+    let w0 = 2.0 * std::f32::consts::PI * cutoff / sample_rate;
+    let alpha = w0.sin() / (2.0 * std::f32::consts::FRAC_1_SQRT_2);
+    let cos = w0.cos();
+    let a0 = 1.0 + alpha;
+    let b0 = (1.0 + cos) / 2.0 / a0;
+    let b1 = -(1.0 + cos) / a0;
+    let b2 = b0;
+    let a1 = -2.0 * cos / a0;
+    let a2 = (1.0 - alpha) / a0;
+
+    let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+    for sample in samples.iter_mut() {
+        let x = *sample;
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        (x2, x1, y2, y1) = (x1, x, y1, y);
+        *sample = y;
+    }
+}
+
+/// Cut everything outside the first and last voiced frame, keeping a short
+/// margin
+fn trim_ends(samples: &[f32], voiced: &[bool], frame_len: usize, sample_rate: u32) -> Vec<f32> {
+    let (Some(first), Some(last)) = (
+        voiced.iter().position(|&v| v),
+        voiced.iter().rposition(|&v| v),
+    ) else {
+        return samples.to_vec();
+    };
+    let margin = sample_rate as usize * TRIM_MARGIN_MS / 1000;
+    let start = (first * frame_len).saturating_sub(margin);
+    let end = ((last + 1) * frame_len + margin).min(samples.len());
+    samples[start..end].to_vec()
+}
+
+/// Shorten runs of more than `max_frames` silent frames to `max_frames`,
+/// keeping the start and end of each pause so the cut lands in silence
+fn collapse_silences(
+    samples: &[f32],
+    voiced: &[bool],
+    frame_len: usize,
+    max_frames: usize,
+) -> Vec<f32> {
+    let mut kept = Vec::with_capacity(samples.len());
+    let mut frame = 0;
+    while frame < voiced.len() {
+        let run_end = voiced[frame..]
+            .iter()
+            .position(|&v| v != voiced[frame])
+            .map_or(voiced.len(), |len| frame + len);
+        let run = run_end - frame;
+        let range = |from: usize, to: usize| from * frame_len..(to * frame_len).min(samples.len());
+        if voiced[frame] || run <= max_frames {
+            kept.extend_from_slice(&samples[range(frame, run_end)]);
+        } else {
+            let head = max_frames / 2;
+            let tail = max_frames - head;
+            kept.extend_from_slice(&samples[range(frame, frame + head)]);
+            kept.extend_from_slice(&samples[range(run_end - tail, run_end)]);
+        }
+        frame = run_end;
+    }
+    kept
+}
+
+/// The `max_len` samples (frame aligned) with the highest total energy
+fn crop_loudest(samples: &[f32], max_len: usize, frame_len: usize) -> Vec<f32> {
+    if samples.len() <= max_len {
+        return samples.to_vec();
+    }
+    let energies: Vec<f64> = samples
+        .chunks(frame_len)
+        .map(|frame| frame.iter().map(|&s| (s * s) as f64).sum())
+        .collect();
+    let window = (max_len / frame_len).max(1).min(energies.len());
+
+    let mut sum: f64 = energies[..window].iter().sum();
+    let (mut best, mut best_sum) = (0, sum);
+    for start in 1..=energies.len() - window {
+        sum += energies[start + window - 1] - energies[start - 1];
+        if sum > best_sum {
+            (best, best_sum) = (start, sum);
+        }
+    }
+    let start = best * frame_len;
+    samples[start..(start + max_len).min(samples.len())].to_vec()
+}
+
+/// Scale so the voiced frames have an RMS of `target_db` dBFS without
+/// peaks above [`PEAK_LIMIT`]
+fn normalize_loudness(samples: &mut [f32], voiced: &[bool], frame_len: usize, target_db: f32) {
+    let voiced_samples: Vec<f32> = samples
+        .chunks(frame_len)
+        .zip(voiced)
+        .filter(|(_, voiced)| **voiced)
+        .flat_map(|(frame, _)| frame.iter().copied())
+        .collect();
+    let level = rms_db(&voiced_samples);
+    if !level.is_finite() {
+        return;
+    }
+    let peak = samples.iter().fold(0.0f32, |peak, &s| peak.max(s.abs()));
+    let gain = 10f32
+        .powf((target_db - level) / 20.0)
+        .min(PEAK_LIMIT / peak);
+    samples.iter_mut().for_each(|s| *s *= gain);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 1000;
+
+    fn tone(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                amplitude * (2.0 * std::f32::consts::PI * 100.0 * i as f32 / RATE as f32).sin()
+            })
+            .collect()
+    }
+
+    /// Every step disabled
+    fn none() -> VoicePreprocessing {
+        VoicePreprocessing {
+            remove_dc: false,
+            high_pass_hz: None,
+            trim_silence: false,
+            max_silence_ms: None,
+            target_loudness_db: None,
+            max_duration_secs: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_remove_dc_and_high_pass() {
+        let offset = vec![0.5; 2000];
+        let out = VoicePreprocessing {
+            remove_dc: true,
+            ..none()
+        }
+        .process(&offset, RATE);
+        assert!(out.iter().all(|s| s.abs() < 1e-6));
+
+        // A 5 Hz wobble is removed by an 80 Hz high-pass, a 100 Hz tone kept
+        let rumble: Vec<f32> = (0..2000)
+            .map(|i| (2.0 * std::f32::consts::PI * 5.0 * i as f32 / RATE as f32).sin())
+            .collect();
+        let filter = VoicePreprocessing {
+            high_pass_hz: Some(80.0),
+            ..none()
+        };
+        assert!(rms_db(&filter.process(&rumble, RATE)[1000..]) < -30.0);
+        assert!(rms_db(&filter.process(&tone(2000, 1.0), RATE)[1000..]) > -6.0);
+    }
+
+    #[test]
+    fn test_trim_and_collapse_silence() {
+        // 1 s silence, 0.2 s tone, 1 s silence, 0.2 s tone, 1 s silence
+        let mut samples = vec![0.0; 1000];
+        samples.extend(tone(200, 0.5));
+        samples.extend(vec![0.0; 1000]);
+        samples.extend(tone(200, 0.5));
+        samples.extend(vec![0.0; 1000]);
+
+        let trimmed = VoicePreprocessing {
+            trim_silence: true,
+            ..none()
+        }
+        .process(&samples, RATE);
+        assert_eq!(trimmed.len(), 200 + 1000 + 200 + 2 * 60);
+
+        let collapsed = VoicePreprocessing {
+            trim_silence: true,
+            max_silence_ms: Some(300),
+            ..none()
+        }
+        .process(&samples, RATE);
+        assert_eq!(collapsed.len(), 200 + 300 + 200 + 2 * 60);
+    }
+
+    #[test]
+    fn test_crop_to_loudest_window() {
+        let mut samples = tone(1000, 0.1);
+        samples.extend(tone(1000, 0.8));
+        samples.extend(tone(1000, 0.1));
+        let cropped = VoicePreprocessing {
+            max_duration_secs: Some(1.0),
+            ..none()
+        }
+        .process(&samples, RATE);
+        assert_eq!(cropped.len(), 1000);
+        assert!((rms_db(&cropped) - rms_db(&tone(1000, 0.8))).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_normalize_loudness_respects_peak() {
+        let quiet = tone(1000, 0.01);
+        let normalized = VoicePreprocessing {
+            target_loudness_db: Some(-20.0),
+            ..none()
+        }
+        .process(&quiet, RATE);
+        assert!((rms_db(&normalized) + 20.0).abs() < 0.1);
+
+        // A sine at -3 dBFS RMS would need peaks above full scale
+        let limited = VoicePreprocessing {
+            target_loudness_db: Some(-1.0),
+            ..none()
+        }
+        .process(&quiet, RATE);
+        let peak = limited.iter().fold(0.0f32, |p, s| p.max(s.abs()));
+        assert!((peak - PEAK_LIMIT).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_apply_mixes_down_to_mono() {
+        let stereo = Tensor::from_vec(
+            vec![0.2f32, 0.2, 0.2, 0.4, 0.4, 0.4],
+            (2, 3),
+            &candle_core::Device::Cpu,
+        )
+        .unwrap();
+        let mono = none().apply(&stereo, RATE).unwrap();
+        assert_eq!(mono.dims(), &[1, 3]);
+        let values = mono.flatten_all().unwrap().to_vec1::<f32>().unwrap();
+        assert!(values.iter().all(|v| (v - 0.3).abs() < 1e-6));
+    }
+}
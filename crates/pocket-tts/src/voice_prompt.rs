@@ -0,0 +1,639 @@
+//! Self-describing voice prompt files
+//!
+//! A voice prompt is a `.safetensors` file that stores a cloned voice, so the
+//! reference audio does not have to be encoded again on every load. Three
+//! layouts exist, told apart by their tensor names:
+//!
+//! | Format | Tensors | Load cost |
+//! |--------|---------|-----------|
+//! | [`VoicePromptFormat::AudioPrompt`] (v1) | `audio_prompt` | FlowLM prompt pass |
+//! | [`VoicePromptFormat::KvCacheCurrentEnd`] (v2) | `*/cache`, `*/current_end` | none |
+//! | [`VoicePromptFormat::KvCacheOffset`] (v3) | `*/cache`, `*/offset` | none |
+//!
+//! [`VoicePrompt::save`] also writes [`VoicePromptMetadata`] into the
+//! safetensors header. Files written by older releases have no metadata and
+//! still load.
+
+use crate::ModelState;
+use crate::voice_state::{ATTN_K_BUF_KEY, ATTN_LEN_KEY, ATTN_POS_KEY, ATTN_V_BUF_KEY};
+use anyhow::Result;
+use candle_core::{DType, Device, Tensor};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Version of the metadata layout written by [`VoicePrompt::save`]
+pub const VOICE_PROMPT_FORMAT_VERSION: u32 = 1;
+
+/// Tensor of the v1 format
+const AUDIO_PROMPT_KEY: &str = "audio_prompt";
+/// Per-layer KV cache of the v2 and v3 formats
+const CACHE_SUFFIX: &str = "/cache";
+const CURRENT_END_SUFFIX: &str = "/current_end";
+const OFFSET_SUFFIX: &str = "/offset";
+/// Module prefix that KV cache tensor names leave out
+const FLOW_LM_PREFIX: &str = "flow_lm.";
+
+const FORMAT_VERSION_KEY: &str = "format_version";
+const FORMAT_KEY: &str = "format";
+const MODEL_VARIANT_KEY: &str = "model_variant";
+const SOURCE_SAMPLE_RATE_KEY: &str = "source_sample_rate";
+const DURATION_KEY: &str = "duration_secs";
+const CREATED_BY_KEY: &str = "created_by";
+
+/// Tensor layout of a voice prompt file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoicePromptFormat {
+    /// v1: Mimi latents projected to FlowLM space, replayed through FlowLM
+    /// on load. Smallest file.
+    #[default]
+    AudioPrompt,
+    /// v2: FlowLM KV cache, prompt length stored as the length of a
+    /// `current_end` tensor
+    KvCacheCurrentEnd,
+    /// v3: FlowLM KV cache, prompt length stored as an `offset` scalar
+    KvCacheOffset,
+}
+
+impl VoicePromptFormat {
+    /// Legacy version number (the `_v2`/`_v3` suffix of the old functions)
+    pub fn version(self) -> u32 {
+        match self {
+            Self::AudioPrompt => 1,
+            Self::KvCacheCurrentEnd => 2,
+            Self::KvCacheOffset => 3,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AudioPrompt => "audio_prompt",
+            Self::KvCacheCurrentEnd => "kv_cache_current_end",
+            Self::KvCacheOffset => "kv_cache_offset",
+        }
+    }
+
+    /// Format of a file holding tensors named `names`
+    pub fn detect<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let mut detected: Option<Self> = None;
+        for name in names {
+            let format = if name == AUDIO_PROMPT_KEY {
+                Self::AudioPrompt
+            } else if name.ends_with(CURRENT_END_SUFFIX) {
+                Self::KvCacheCurrentEnd
+            } else if name.ends_with(OFFSET_SUFFIX) {
+                Self::KvCacheOffset
+            } else {
+                continue;
+            };
+            match detected {
+                Some(other) if other != format => anyhow::bail!(
+                    "Voice prompt mixes {} and {} tensors",
+                    other.name(),
+                    format.name()
+                ),
+                _ => detected = Some(format),
+            }
+        }
+        detected.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Not a voice prompt: expected an 'audio_prompt' tensor or per-layer \
+                 '*/current_end' or '*/offset' tensors"
+            )
+        })
+    }
+}
+
+impl std::fmt::Display for VoicePromptFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (v{})", self.name(), self.version())
+    }
+}
+
+/// Provenance stored in the safetensors header
+///
+/// Every field is optional because files written before the metadata was
+/// introduced carry none of it.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct VoicePromptMetadata {
+    /// Variant of the model that encoded the prompt, e.g. `b6369a24`
+    pub model_variant: Option<String>,
+    /// Sample rate of the reference audio before resampling
+    pub source_sample_rate: Option<u32>,
+    /// Length of the reference audio in seconds
+    pub duration_secs: Option<f32>,
+    /// Tool that wrote the file, e.g. `pocket-tts 0.6.0`
+    pub created_by: Option<String>,
+}
+
+impl VoicePromptMetadata {
+    /// Metadata for a prompt created now by this crate with `model_variant`
+    pub fn new(model_variant: Option<String>) -> Self {
+        Self {
+            model_variant,
+            source_sample_rate: None,
+            duration_secs: None,
+            created_by: Some(format!("pocket-tts {}", env!("CARGO_PKG_VERSION"))),
+        }
+    }
+
+    fn to_header(&self, format: VoicePromptFormat) -> HashMap<String, String> {
+        let mut header = HashMap::from([
+            (
+                FORMAT_VERSION_KEY.to_string(),
+                VOICE_PROMPT_FORMAT_VERSION.to_string(),
+            ),
+            (FORMAT_KEY.to_string(), format.name().to_string()),
+        ]);
+        let fields = [
+            (MODEL_VARIANT_KEY, self.model_variant.clone()),
+            (
+                SOURCE_SAMPLE_RATE_KEY,
+                self.source_sample_rate.map(|rate| rate.to_string()),
+            ),
+            (
+                DURATION_KEY,
+                self.duration_secs.map(|secs| secs.to_string()),
+            ),
+            (CREATED_BY_KEY, self.created_by.clone()),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value {
+                header.insert(key.to_string(), value);
+            }
+        }
+        header
+    }
+
+    fn from_header(header: &HashMap<String, String>) -> Result<Self> {
+        if let Some(version) = header.get(FORMAT_VERSION_KEY) {
+            let version: u32 = version.parse().map_err(|_| {
+                anyhow::anyhow!("Invalid voice prompt format version {:?}", version)
+            })?;
+            if version > VOICE_PROMPT_FORMAT_VERSION {
+                anyhow::bail!(
+                    "Voice prompt format version {} is newer than the supported version {}; \
+                     upgrade pocket-tts to load it",
+                    version,
+                    VOICE_PROMPT_FORMAT_VERSION
+                );
+            }
+        }
+        Ok(Self {
+            model_variant: header.get(MODEL_VARIANT_KEY).cloned(),
+            source_sample_rate: header
+                .get(SOURCE_SAMPLE_RATE_KEY)
+                .and_then(|rate| rate.parse().ok()),
+            duration_secs: header.get(DURATION_KEY).and_then(|secs| secs.parse().ok()),
+            created_by: header.get(CREATED_BY_KEY).cloned(),
+        })
+    }
+}
+
+/// A cloned voice as stored on disk
+///
+/// Create one with [`crate::TTSModel::create_voice_prompt`] and turn it into a
+/// voice state with [`crate::TTSModel::get_voice_state_from_voice_prompt`].
+#[derive(Debug, Clone)]
+pub struct VoicePrompt {
+    pub format: VoicePromptFormat,
+    pub metadata: VoicePromptMetadata,
+    tensors: HashMap<String, Tensor>,
+}
+
+impl VoicePrompt {
+    /// v1 prompt from conditioning latents (`[B, T, dim]`)
+    pub fn from_conditioning(conditioning: Tensor, metadata: VoicePromptMetadata) -> Self {
+        Self {
+            format: VoicePromptFormat::AudioPrompt,
+            metadata,
+            tensors: HashMap::from([(AUDIO_PROMPT_KEY.to_string(), conditioning)]),
+        }
+    }
+
+    /// v2 or v3 prompt from the FlowLM attention state after the voice
+    /// prompt pass
+    pub fn from_voice_state(
+        state: &ModelState,
+        format: VoicePromptFormat,
+        metadata: VoicePromptMetadata,
+    ) -> Result<Self> {
+        let suffix = match format {
+            VoicePromptFormat::AudioPrompt => {
+                anyhow::bail!("A voice state cannot be stored as an audio prompt")
+            }
+            VoicePromptFormat::KvCacheCurrentEnd => CURRENT_END_SUFFIX,
+            VoicePromptFormat::KvCacheOffset => OFFSET_SUFFIX,
+        };
+
+        let mut tensors = HashMap::new();
+        for (module, layer) in state {
+            let name = module.strip_prefix(FLOW_LM_PREFIX).ok_or_else(|| {
+                anyhow::anyhow!("Expected prefix 'flow_lm.' not found in key: {}", module)
+            })?;
+            let get = |key: &str| {
+                layer.get(key).ok_or_else(|| {
+                    anyhow::anyhow!("Missing '{}' in voice state of {}", key, module)
+                })
+            };
+            let pos = get(ATTN_POS_KEY)?.to_device(&Device::Cpu)?;
+            let offset = pos.to_scalar::<u32>()? as usize;
+            let k_buf = get(ATTN_K_BUF_KEY)?.to_device(&Device::Cpu)?;
+            let v_buf = get(ATTN_V_BUF_KEY)?.to_device(&Device::Cpu)?;
+
+            let position = match format {
+                VoicePromptFormat::KvCacheCurrentEnd => {
+                    Tensor::zeros(offset, DType::F32, &Device::Cpu)?
+                }
+                _ => pos.to_dtype(DType::I64)?.unsqueeze(0)?,
+            };
+            tensors.insert(
+                format!("{}{}", name, CACHE_SUFFIX),
+                pack_kv_cache(&k_buf, &v_buf, offset)?,
+            );
+            tensors.insert(format!("{}{}", name, suffix), position);
+        }
+
+        Ok(Self {
+            format,
+            metadata,
+            tensors,
+        })
+    }
+
+    /// Read a prompt file of any format
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read voice prompt {:?}: {}", path, e))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Parse prompt file bytes of any format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let (_, header) = safetensors::SafeTensors::read_metadata(bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid safetensors data: {}", e))?;
+        let metadata = match header.metadata() {
+            Some(header) => VoicePromptMetadata::from_header(header)?,
+            None => VoicePromptMetadata::default(),
+        };
+        let tensors = candle_core::safetensors::load_buffer(bytes, &Device::Cpu)?;
+        let format = VoicePromptFormat::detect(tensors.keys().map(String::as_str))?;
+        Ok(Self {
+            format,
+            metadata,
+            tensors,
+        })
+    }
+
+    /// Write the prompt and its metadata to a `.safetensors` file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_bytes()?)
+            .map_err(|e| anyhow::anyhow!("Failed to write voice prompt {:?}: {}", path, e))?;
+        Ok(())
+    }
+
+    /// Serialize the prompt and its metadata in safetensors format
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut tensors = Vec::with_capacity(self.tensors.len());
+        for (name, tensor) in &self.tensors {
+            tensors.push((name.as_str(), RawTensor::new(tensor)?));
+        }
+        let header = Some(self.metadata.to_header(self.format));
+        Ok(safetensors::serialize(tensors, &header)?)
+    }
+
+    /// Named tensors as stored in the file
+    pub fn tensors(&self) -> &HashMap<String, Tensor> {
+        &self.tensors
+    }
+
+    /// Conditioning latents of a v1 prompt
+    pub fn conditioning(&self) -> Option<&Tensor> {
+        self.tensors.get(AUDIO_PROMPT_KEY)
+    }
+
+    /// Rebuild the FlowLM attention state of a v2 or v3 prompt on `device`
+    pub fn kv_cache_state(&self, device: &Device) -> Result<ModelState> {
+        let suffix = match self.format {
+            VoicePromptFormat::AudioPrompt => {
+                anyhow::bail!("Audio prompts have no KV cache; run them through the model")
+            }
+            VoicePromptFormat::KvCacheCurrentEnd => CURRENT_END_SUFFIX,
+            VoicePromptFormat::KvCacheOffset => OFFSET_SUFFIX,
+        };
+
+        let mut state = ModelState::new();
+        for (name, cache) in &self.tensors {
+            let Some(layer) = name.strip_suffix(CACHE_SUFFIX) else {
+                continue;
+            };
+            let position_name = format!("{}{}", layer, suffix);
+            let position = self
+                .tensors
+                .get(&position_name)
+                .ok_or_else(|| anyhow::anyhow!("Missing expected tensor: {}", position_name))?;
+            let offset = match self.format {
+                VoicePromptFormat::KvCacheCurrentEnd => position.dim(0)?,
+                _ => position
+                    .flatten_all()?
+                    .to_dtype(DType::I64)?
+                    .to_vec1::<i64>()?[0] as usize,
+            };
+
+            let (k_buf, v_buf) = unpack_kv_cache(&cache.to_device(device)?)?;
+            let layer_state = HashMap::from([
+                (ATTN_K_BUF_KEY.to_string(), k_buf),
+                (ATTN_V_BUF_KEY.to_string(), v_buf),
+                (
+                    ATTN_POS_KEY.to_string(),
+                    Tensor::new(offset as u32, device)?,
+                ),
+                (
+                    ATTN_LEN_KEY.to_string(),
+                    Tensor::new(offset as i64, device)?,
+                ),
+            ]);
+            state.insert(format!("{}{}", FLOW_LM_PREFIX, layer), layer_state);
+        }
+        if state.is_empty() {
+            anyhow::bail!("Voice prompt has no '*/cache' tensors");
+        }
+        Ok(state)
+    }
+
+    /// Features per frame (`dim`) the prompt was encoded with
+    pub(crate) fn feature_dim(&self) -> Result<usize> {
+        match self.conditioning() {
+            Some(conditioning) => Ok(conditioning.dim(conditioning.rank() - 1)?),
+            None => {
+                let cache = self
+                    .tensors
+                    .iter()
+                    .find(|(name, _)| name.ends_with(CACHE_SUFFIX))
+                    .map(|(_, cache)| cache)
+                    .ok_or_else(|| anyhow::anyhow!("Voice prompt has no '*/cache' tensors"))?;
+                // [2, B, T, heads, head_dim]
+                let (_, _, _, heads, head_dim) = cache.dims5()?;
+                Ok(heads * head_dim)
+            }
+        }
+    }
+}
+
+/// Owned little-endian tensor data for `safetensors::serialize`, which
+/// (unlike `candle_core::safetensors::save`) writes header metadata
+struct RawTensor {
+    dtype: safetensors::Dtype,
+    shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+impl RawTensor {
+    fn new(tensor: &Tensor) -> Result<Self> {
+        let flat = tensor.flatten_all()?.to_device(&Device::Cpu)?;
+        let (dtype, data) = match tensor.dtype() {
+            DType::F32 => (
+                safetensors::Dtype::F32,
+                flat.to_vec1::<f32>()?
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect(),
+            ),
+            DType::I64 => (
+                safetensors::Dtype::I64,
+                flat.to_vec1::<i64>()?
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect(),
+            ),
+            DType::U32 => (
+                safetensors::Dtype::U32,
+                flat.to_vec1::<u32>()?
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect(),
+            ),
+            other => anyhow::bail!("Unsupported voice prompt tensor dtype {:?}", other),
+        };
+        Ok(Self {
+            dtype,
+            shape: tensor.dims().to_vec(),
+            data,
+        })
+    }
+}
+
+impl safetensors::View for RawTensor {
+    fn dtype(&self) -> safetensors::Dtype {
+        self.dtype
+    }
+
+    fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    fn data(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(&self.data)
+    }
+
+    fn data_len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// Pack per-layer key and value buffers into the Python layout
+pub(crate) fn pack_kv_cache(keys: &Tensor, values: &Tensor, offset: usize) -> Result<Tensor> {
+    // Step 1: Stack the tensors along a new 0th dimension.
+    // keys shape example:   [1, 16, 128, 64]
+    // values shape example: [1, 16, 128, 64]
+    // stacked shape: [2, 1, 16, 128, 64]
+    let stacked = Tensor::stack(&[keys, values], 0)?;
+
+    // Step 2: Swap the 'heads' dimension (index 2, size 16)
+    // with the 'seq_len' dimension (index 3, size 128).
+    // since that is what python emplementation uses
+    // transposed shape: [2, 1, 128, 16, 64]
+    let packed = stacked.transpose(2, 3)?;
+
+    // Step 3: Respect the offset
+    // example offset: 50
+    // packed_with_offset shape: [2, 1, 50, 16, 64]
+    let packed_with_offset = packed.narrow(2, 0, offset)?;
+
+    // Step 4: Make the tensor contiguous in memory.
+    let packed_contiguous = packed_with_offset.contiguous()?;
+
+    Ok(packed_contiguous)
+}
+
+/// Inverse of [`pack_kv_cache`]
+pub(crate) fn unpack_kv_cache(packed: &Tensor) -> Result<(Tensor, Tensor)> {
+    // Step 1: Swap the 'seq_len' and 'heads' dimensions back.
+    // python implementation shape example: [2, 1, 128, 16, 64]
+    // candle implementation shape example: [2, 1, 16, 128, 64]
+    let transposed = packed.transpose(2, 3)?;
+
+    // Step 2: Extract keys and values.
+    // keys shape: [1, 16, 128, 64]
+    let keys = transposed.get(0)?;
+
+    // values shape: [1, 16, 128, 64]
+    let values = transposed.get(1)?;
+
+    // Step 3: Make them contiguous.
+    let keys_contiguous = keys.contiguous()?;
+    let values_contiguous = values.contiguous()?;
+
+    Ok((keys_contiguous, values_contiguous))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kv_state(layers: usize, offset: usize) -> ModelState {
+        let mut state = ModelState::new();
+        for layer in 0..layers {
+            let k_buf = Tensor::arange(0f32, 2.0 * 8.0 * 3.0, &Device::Cpu)
+                .unwrap()
+                .reshape((1, 2, 8, 3))
+                .unwrap();
+            let v_buf = (&k_buf * 2.0).unwrap();
+            state.insert(
+                format!("flow_lm.transformer.layers.{}.self_attn", layer),
+                HashMap::from([
+                    (ATTN_K_BUF_KEY.to_string(), k_buf),
+                    (ATTN_V_BUF_KEY.to_string(), v_buf),
+                    (
+                        ATTN_POS_KEY.to_string(),
+                        Tensor::new(offset as u32, &Device::Cpu).unwrap(),
+                    ),
+                    (
+                        ATTN_LEN_KEY.to_string(),
+                        Tensor::new(offset as i64, &Device::Cpu).unwrap(),
+                    ),
+                ]),
+            );
+        }
+        state
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            VoicePromptFormat::detect(["audio_prompt"]).unwrap(),
+            VoicePromptFormat::AudioPrompt
+        );
+        assert_eq!(
+            VoicePromptFormat::detect([
+                "transformer.layers.0.self_attn/cache",
+                "transformer.layers.0.self_attn/current_end",
+            ])
+            .unwrap(),
+            VoicePromptFormat::KvCacheCurrentEnd
+        );
+        assert_eq!(
+            VoicePromptFormat::detect([
+                "transformer.layers.0.self_attn/offset",
+                "transformer.layers.0.self_attn/cache",
+            ])
+            .unwrap(),
+            VoicePromptFormat::KvCacheOffset
+        );
+        assert!(VoicePromptFormat::detect(["latents"]).is_err());
+        assert!(VoicePromptFormat::detect(["audio_prompt", "a/offset"]).is_err());
+    }
+
+    #[test]
+    fn test_audio_prompt_roundtrip_with_metadata() {
+        let conditioning = Tensor::ones((1, 4, 6), DType::F32, &Device::Cpu).unwrap();
+        let metadata = VoicePromptMetadata {
+            source_sample_rate: Some(44100),
+            duration_secs: Some(2.5),
+            ..VoicePromptMetadata::new(Some("b6369a24".to_string()))
+        };
+        let prompt = VoicePrompt::from_conditioning(conditioning, metadata.clone());
+
+        let loaded = VoicePrompt::from_bytes(&prompt.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded.format, VoicePromptFormat::AudioPrompt);
+        assert_eq!(loaded.metadata, metadata);
+        assert!(
+            loaded
+                .metadata
+                .created_by
+                .as_deref()
+                .unwrap()
+                .starts_with("pocket-tts ")
+        );
+        assert_eq!(loaded.feature_dim().unwrap(), 6);
+        assert_eq!(
+            loaded.conditioning().unwrap().dims(),
+            &[1, 4, 6],
+            "conditioning shape survives the roundtrip"
+        );
+    }
+
+    #[test]
+    fn test_kv_cache_roundtrip() {
+        let state = kv_state(2, 5);
+        for format in [
+            VoicePromptFormat::KvCacheCurrentEnd,
+            VoicePromptFormat::KvCacheOffset,
+        ] {
+            let prompt =
+                VoicePrompt::from_voice_state(&state, format, VoicePromptMetadata::default())
+                    .unwrap();
+            let loaded = VoicePrompt::from_bytes(&prompt.to_bytes().unwrap()).unwrap();
+            assert_eq!(loaded.format, format);
+            assert_eq!(loaded.feature_dim().unwrap(), 2 * 3);
+
+            let restored = loaded.kv_cache_state(&Device::Cpu).unwrap();
+            assert_eq!(restored.len(), 2);
+            let layer = &restored["flow_lm.transformer.layers.1.self_attn"];
+            assert_eq!(layer[ATTN_POS_KEY].to_scalar::<u32>().unwrap(), 5);
+            assert_eq!(layer[ATTN_LEN_KEY].to_scalar::<i64>().unwrap(), 5);
+            let expected = state["flow_lm.transformer.layers.1.self_attn"][ATTN_V_BUF_KEY]
+                .narrow(2, 0, 5)
+                .unwrap();
+            let diff = (&layer[ATTN_V_BUF_KEY] - &expected)
+                .unwrap()
+                .abs()
+                .unwrap()
+                .sum_all()
+                .unwrap()
+                .to_scalar::<f32>()
+                .unwrap();
+            assert_eq!(diff, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_legacy_file_without_metadata_and_newer_version() {
+        let tensors = HashMap::from([(
+            "audio_prompt",
+            Tensor::zeros((1, 2, 3), DType::F32, &Device::Cpu).unwrap(),
+        )]);
+        let path = std::env::temp_dir().join(format!(
+            "pocket_tts_legacy_prompt_{}.safetensors",
+            std::process::id()
+        ));
+        candle_core::safetensors::save(&tensors, &path).unwrap();
+        let loaded = VoicePrompt::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.format, VoicePromptFormat::AudioPrompt);
+        assert_eq!(loaded.metadata, VoicePromptMetadata::default());
+
+        let raw = RawTensor::new(loaded.conditioning().unwrap()).unwrap();
+        let header = Some(HashMap::from([(
+            FORMAT_VERSION_KEY.to_string(),
+            (VOICE_PROMPT_FORMAT_VERSION + 1).to_string(),
+        )]));
+        let bytes = safetensors::serialize([("audio_prompt", raw)], &header).unwrap();
+        let err = VoicePrompt::from_bytes(&bytes).unwrap_err().to_string();
+        assert!(err.contains("newer"), "{}", err);
+    }
+}
@@ -12,6 +12,8 @@ pub const ATTN_LEN_KEY: &str = "l";
 pub const ATTN_HEAD_KEY: &str = "head";
 pub const ATTN_K_BUF_KEY: &str = "k_buf";
 pub const ATTN_V_BUF_KEY: &str = "v_buf";
+/// Per-row count of leading padding slots in a batched KV cache (`[B]`, u32).
+pub const ATTN_PAD_KEY: &str = "pad";
 
 /// Cursor/scalar metadata for attention cache state.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -5,9 +5,11 @@
 
 #![cfg(target_arch = "wasm32")]
 
+use crate::generation::{GenerationHandle, GenerationProgress};
 use crate::tts_model::TTSModel;
 use candle_core::Tensor;
 use js_sys::{Date, Float32Array, Object, Reflect};
+use std::sync::{Arc, Mutex};
 use wasm_bindgen::prelude::*;
 
 type StreamIter = Box<dyn Iterator<Item = std::result::Result<Tensor, anyhow::Error>>>;
@@ -25,12 +27,15 @@ pub struct WasmTTSModel {
     model: Option<TTSModel>,
     voice_state: Option<crate::ModelState>,
     sample_rate: u32,
+    speed: f32,
 }
 
 /// WASM-compatible streaming audio iterator
 #[wasm_bindgen]
 pub struct WasmTTSStream {
     iter: Option<StreamIter>,
+    handle: GenerationHandle,
+    progress: Arc<Mutex<Option<GenerationProgress>>>,
     last_samples: u32,
     last_compute_ms: f64,
     last_chunks_merged: u32,
@@ -45,6 +50,7 @@ impl WasmTTSModel {
             model: None,
             voice_state: None,
             sample_rate: 24000,
+            speed: 1.0,
         }
     }
 
@@ -102,6 +108,30 @@ impl WasmTTSModel {
         Ok(())
     }
 
+    /// Clean up audio passed to `load_voice_from_buffer` before cloning
+    ///
+    /// `settings_json` holds `VoicePreprocessing` fields as JSON, e.g.
+    /// `{"high_pass_hz": 120, "max_duration_secs": null}`; missing fields
+    /// keep their defaults, so `"{}"` trims and shortens silences, removes DC
+    /// and rumble, normalizes loudness and keeps the best 30 seconds.
+    /// `null` turns the cleanup off.
+    #[wasm_bindgen]
+    pub fn set_voice_preprocessing(
+        &mut self,
+        settings_json: Option<String>,
+    ) -> Result<(), JsValue> {
+        let preprocessing = settings_json
+            .map(|json| serde_json::from_str::<crate::VoicePreprocessing>(&json))
+            .transpose()
+            .map_err(|e| JsValue::from_str(&format!("Invalid voice preprocessing: {}", e)))?;
+        let model = self
+            .model
+            .as_mut()
+            .ok_or_else(|| JsValue::from_str("Model not loaded. Call load_from_buffer first."))?;
+        model.voice_preprocessing = preprocessing;
+        Ok(())
+    }
+
     /// Load voice from safetensors buffer (pre-calculated embedding)
     #[wasm_bindgen]
     pub fn load_voice_from_safetensors(&mut self, bytes: &[u8]) -> Result<(), JsValue> {
@@ -125,6 +155,28 @@ impl WasmTTSModel {
         self.sample_rate
     }
 
+    /// Set the speaking rate used by `generate` and `start_stream`
+    ///
+    /// 1.0 is the natural pace; values from 0.25 to 4.0 are accepted.
+    #[wasm_bindgen]
+    pub fn set_speed(&mut self, speed: f32) -> Result<(), JsValue> {
+        let options = crate::GenerationOptions {
+            speed,
+            ..Default::default()
+        };
+        options
+            .validate()
+            .map_err(|e| JsValue::from_str(&format!("{}", e)))?;
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// Get the current speaking rate
+    #[wasm_bindgen(getter)]
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
     /// Generate audio from text
     ///
     /// # Arguments
@@ -144,8 +196,12 @@ impl WasmTTSModel {
             .clone()
             .unwrap_or_else(|| crate::voice_state::init_states(1, 0));
 
+        let options = crate::GenerationOptions {
+            speed: self.speed,
+            ..model.generation_options()
+        };
         let audio_tensor = model
-            .generate(text, &voice_state)
+            .generate_with_options(text, &voice_state, &options)
             .map_err(|e| JsValue::from_str(&format!("Generation failed: {:?}", e)))?;
 
         // Flatten tensor to Vec<f32>
@@ -175,10 +231,24 @@ impl WasmTTSModel {
             .clone()
             .unwrap_or_else(|| crate::voice_state::init_states(1, 0));
 
-        let iter = model.generate_stream_owned(text, &voice_state);
+        let progress = Arc::new(Mutex::new(None));
+        let sink = progress.clone();
+        let handle = GenerationHandle::new().with_progress(move |p| {
+            if let Ok(mut last) = sink.lock() {
+                *last = Some(*p);
+            }
+        });
+        let options = crate::GenerationOptions {
+            speed: self.speed,
+            ..model.generation_options()
+        }
+        .with_handle(handle.clone());
+        let iter = model.generate_stream_owned_with_options(text, &voice_state, &options);
 
         Ok(WasmTTSStream {
             iter: Some(iter),
+            handle,
+            progress,
             last_samples: 0,
             last_compute_ms: 0.0,
             last_chunks_merged: 0,
@@ -271,6 +341,41 @@ impl WasmTTSStream {
         Ok(Some(array))
     }
 
+    /// Cancel the stream; later `next_chunk` calls return None
+    #[wasm_bindgen]
+    pub fn cancel(&mut self) {
+        self.handle.cancel();
+        self.iter = None;
+    }
+
+    /// Get progress of the most recently generated frame.
+    ///
+    /// Returns null before the first frame, otherwise a JS object with keys:
+    /// - chunk_index: number
+    /// - num_chunks: number
+    /// - step: number
+    /// - max_gen_len: number
+    #[wasm_bindgen]
+    pub fn progress(&self) -> JsValue {
+        let Some(progress) = self.progress.lock().ok().and_then(|p| *p) else {
+            return JsValue::NULL;
+        };
+        let obj = Object::new();
+        for (key, value) in [
+            ("chunk_index", progress.chunk_index),
+            ("num_chunks", progress.num_chunks),
+            ("step", progress.step),
+            ("max_gen_len", progress.max_gen_len),
+        ] {
+            let _ = Reflect::set(
+                &obj,
+                &JsValue::from_str(key),
+                &JsValue::from_f64(value as f64),
+            );
+        }
+        JsValue::from(obj)
+    }
+
     /// Get stats for the most recently produced chunk.
     ///
     /// Returns a JS object with keys:
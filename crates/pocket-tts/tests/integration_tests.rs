@@ -183,6 +183,401 @@ fn test_audio_generation_produces_valid_output() {
     println!("Test audio written to {:?}", output_path);
 }
 
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_seeded_generation_is_reproducible() {
+    if !require_hf_token("test_seeded_generation_is_reproducible") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let text = "Hello world. This is a seeded test.";
+    let options = model.generation_options().with_seed(Some(42));
+    let first = model
+        .generate_with_options(text, &voice_state, &options)
+        .expect("Failed to generate audio");
+    let second = model
+        .generate_with_options(text, &voice_state, &options)
+        .expect("Failed to generate audio");
+
+    assert_eq!(
+        first.dims(),
+        second.dims(),
+        "Same seed should give same length"
+    );
+    let first = first.flatten_all().unwrap().to_vec1::<f32>().unwrap();
+    let second = second.flatten_all().unwrap().to_vec1::<f32>().unwrap();
+    assert_eq!(first, second, "Same seed should give identical samples");
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_cancelled_handle_stops_stream() {
+    if !require_hf_token("test_cancelled_handle_stops_stream") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let handle = pocket_tts::GenerationHandle::new();
+    let cancel = handle.clone();
+    let steps = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let seen = steps.clone();
+    let handle = handle.with_progress(move |p| {
+        seen.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if p.step == 2 {
+            cancel.cancel();
+        }
+    });
+    let options = model.generation_options().with_handle(handle);
+
+    let frames = model
+        .generate_stream_with_options(
+            "Hello world. This should stop early.",
+            &voice_state,
+            &options,
+        )
+        .count();
+    assert_eq!(frames, 3, "Stream should stop right after cancellation");
+    assert_eq!(steps.load(std::sync::atomic::Ordering::Relaxed), 3);
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_event_stream_matches_long_stream_audio() {
+    use pocket_tts::GenerationEventKind;
+
+    if !require_hf_token("test_event_stream_matches_long_stream_audio") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let text = "Hello there. [pause:200ms] How are you?";
+    let options = model.generation_options().with_seed(Some(11));
+
+    let expected: usize = model
+        .generate_stream_long_with_options(text, &voice_state, &options)
+        .map(|chunk| chunk.expect("Failed to generate chunk").dims()[2])
+        .sum();
+
+    let events: Vec<_> = model
+        .generate_events_with_options(text, &voice_state, &options)
+        .collect::<Result<_, _>>()
+        .expect("Failed to generate events");
+
+    let started = events
+        .iter()
+        .filter(|e| matches!(e.kind, GenerationEventKind::ChunkStarted { .. }))
+        .count();
+    let finished = events
+        .iter()
+        .filter(|e| matches!(e.kind, GenerationEventKind::ChunkFinished { .. }))
+        .count();
+    assert!(started >= 2);
+    assert_eq!(started, finished);
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e.kind, GenerationEventKind::Pause { ms: 200, .. }))
+    );
+
+    let last = events.last().unwrap();
+    assert_eq!(last.sample_offset + last.kind.num_samples(), expected);
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_generate_batch_returns_one_clip_per_input() {
+    if !require_hf_token("test_generate_batch_returns_one_clip_per_input") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let clips = model
+        .generate_batch(&[
+            ("Hello.", &voice_state),
+            ("This sentence is quite a bit longer.", &voice_state),
+        ])
+        .expect("Failed to generate batch");
+
+    assert_eq!(clips.len(), 2);
+    for clip in &clips {
+        assert_eq!(
+            clip.dims().len(),
+            2,
+            "Each clip should be [channels, samples]"
+        );
+        assert!(clip.dims()[1] > 0, "Each clip should contain audio");
+    }
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_continuity_frames_generates_across_chunks() {
+    if !require_hf_token("test_continuity_frames_generates_across_chunks") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let options = pocket_tts::GenerationOptions {
+        continuity_frames: 25,
+        max_tokens_per_chunk: 8,
+        seed: Some(7),
+        ..model.generation_options()
+    };
+    let text = "The first sentence sets the tone. The second one should follow it smoothly.";
+    assert!(
+        model
+            .split_into_best_sentences_with_max_tokens(text, 8)
+            .len()
+            > 1
+    );
+
+    let audio = model
+        .generate_with_options(text, &voice_state, &options)
+        .expect("Failed to generate audio");
+    assert!(audio.dims()[1] > 0, "Should produce audio");
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_continuity_survives_pauses_in_long_form() {
+    use pocket_tts::GenerationEventKind;
+
+    if !require_hf_token("test_continuity_survives_pauses_in_long_form") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    // Audio of each chunk; the pause splits the text into two segments
+    let chunk_audio = |continuity_frames: usize| {
+        let options = pocket_tts::GenerationOptions {
+            continuity_frames,
+            seed: Some(11),
+            ..model.generation_options()
+        };
+        let text = "The first sentence sets the tone. [pause:300ms] The second one follows it.";
+        let mut chunks: Vec<Vec<f32>> = Vec::new();
+        for event in model.generate_events_with_options(text, &voice_state, &options) {
+            match event.expect("Generation failed").kind {
+                GenerationEventKind::ChunkStarted { .. } => chunks.push(Vec::new()),
+                GenerationEventKind::Audio { samples, .. } => chunks
+                    .last_mut()
+                    .unwrap()
+                    .extend(samples.flatten_all().unwrap().to_vec1::<f32>().unwrap()),
+                _ => {}
+            }
+        }
+        chunks
+    };
+
+    let fresh = chunk_audio(0);
+    let carried = chunk_audio(25);
+    assert_eq!(fresh.len(), 2);
+    assert_eq!(carried.len(), 2);
+    // Nothing precedes the first chunk, so only the second one changes
+    assert_eq!(fresh[0], carried[0]);
+    assert_ne!(fresh[1], carried[1]);
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_best_of_n_reports_selection() {
+    use pocket_tts::GenerationEventKind;
+
+    if !require_hf_token("test_best_of_n_reports_selection") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let options = pocket_tts::GenerationOptions {
+        candidates: 2,
+        seed: Some(5),
+        ..model.generation_options()
+    };
+    let mut selections = Vec::new();
+    let mut audio_samples = 0;
+    for event in model.generate_events_with_options("Pick the better take.", &voice_state, &options)
+    {
+        let event = event.expect("Generation failed");
+        audio_samples += event.kind.num_samples();
+        if let GenerationEventKind::CandidateSelected {
+            selected, scores, ..
+        } = event.kind
+        {
+            assert_eq!(scores.len(), 2);
+            assert!(scores.iter().all(|s| s.score <= scores[selected].score));
+            selections.push(selected);
+        }
+    }
+    assert_eq!(selections.len(), 1);
+    assert!(audio_samples > 0);
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_latents_roundtrip_matches_generate() {
+    if !require_hf_token("test_latents_roundtrip_matches_generate") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let text = "Latents can be decoded later.";
+    let options = model.generation_options().with_seed(Some(11));
+    let latents = model
+        .generate_latents_with_options(text, &voice_state, &options)
+        .expect("Failed to generate latents");
+    assert_eq!(latents.dims()[1], model.ldim);
+
+    let path = std::env::temp_dir().join("pocket_tts_latents_roundtrip.safetensors");
+    model
+        .save_latents(&latents, &path)
+        .expect("Failed to save latents");
+    let loaded = model.load_latents(&path).expect("Failed to load latents");
+    let _ = std::fs::remove_file(&path);
+
+    let decoded = model.decode_latents(&loaded).expect("Failed to decode");
+    let direct = model
+        .generate_with_options(text, &voice_state, &options)
+        .expect("Failed to generate audio");
+    assert_eq!(decoded.dims(), direct.dims());
+
+    let diff = (decoded - direct)
+        .unwrap()
+        .abs()
+        .unwrap()
+        .max_all()
+        .unwrap()
+        .to_scalar::<f32>()
+        .unwrap();
+    assert!(
+        diff < 1e-4,
+        "decoded latents differ from generate by {diff}"
+    );
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_speed_shortens_audio() {
+    if !require_hf_token("test_speed_shortens_audio") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let text = "Speaking faster should give a shorter clip.";
+    let normal = pocket_tts::GenerationOptions {
+        seed: Some(3),
+        ..model.generation_options()
+    };
+    let fast = pocket_tts::GenerationOptions {
+        speed: 2.0,
+        ..normal.clone()
+    };
+
+    let normal_len = model
+        .generate_with_options(text, &voice_state, &normal)
+        .expect("Failed to generate audio")
+        .dims()[1];
+    let fast_len = model
+        .generate_with_options(text, &voice_state, &fast)
+        .expect("Failed to generate audio")
+        .dims()[1];
+    assert!(fast_len > 0);
+    assert!(
+        fast_len < normal_len * 3 / 4,
+        "speed 2.0 gave {fast_len} samples vs {normal_len}"
+    );
+}
+
 #[test]
 // #[ignore = "requires HF_TOKEN and model download"]
 fn test_mimi_encode_decode_roundtrip() {
@@ -260,6 +655,223 @@ fn test_mimi_encode_decode_roundtrip() {
     );
 }
 
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_codec_container_roundtrip() {
+    use pocket_tts::{EncodedAudio, LatentPrecision};
+
+    if !require_hf_token("test_codec_container_roundtrip") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let (audio, sample_rate) = read_wav(&ref_wav_path).expect("Failed to read ref.wav");
+    let encoded = model
+        .encode_audio(&audio, sample_rate)
+        .expect("Failed to encode");
+    assert_eq!(encoded.latent_dim(), model.mimi.output_dimension);
+
+    let bytes = encoded.to_bytes(LatentPrecision::I8).unwrap();
+    let parsed = EncodedAudio::from_bytes(&bytes, &model.device).expect("Failed to parse");
+    let decoded = model.decode_audio(&parsed).expect("Failed to decode");
+
+    assert_eq!(decoded.dims()[1] as u64, encoded.num_samples);
+    assert!(bytes.len() < encoded.num_samples as usize * 2 / 4);
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_ssml_breaks_and_audio_clips() {
+    use pocket_tts::{GenerationEventKind, InputFormat};
+
+    if !require_hf_token("test_ssml_breaks_and_audio_clips") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let ssml = format!(
+        r#"<speak><prosody rate="fast">Hello.</prosody><break time="700ms"/><audio src="{}">chime</audio></speak>"#,
+        ref_wav_path.display()
+    );
+    let options = pocket_tts::GenerationOptions {
+        input_format: InputFormat::Ssml,
+        ssml_audio: true,
+        seed: Some(3),
+        ..model.generation_options()
+    };
+    let mut pauses = Vec::new();
+    let mut clips = 0;
+    let mut speech_samples = 0;
+    for event in model.generate_events_with_options(&ssml, &voice_state, &options) {
+        let event = event.expect("Generation failed");
+        match event.kind {
+            GenerationEventKind::Pause { ms, .. } => pauses.push(ms),
+            GenerationEventKind::AudioClip { samples, .. } => {
+                assert!(samples.dims()[2] > 0);
+                clips += 1;
+            }
+            GenerationEventKind::Audio { samples, .. } => speech_samples += samples.dims()[2],
+            _ => {}
+        }
+    }
+    assert_eq!(pauses, vec![700]);
+    assert_eq!(clips, 1);
+    assert!(speech_samples > 0);
+
+    let malformed = model
+        .generate_events_with_options("<speak>Hello", &voice_state, &options)
+        .next()
+        .expect("Expected an error event");
+    assert!(malformed.is_err());
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_sentence_splitter_keeps_abbreviations_and_is_pluggable() {
+    use pocket_tts::SentenceSplitter;
+
+    if !require_hf_token("test_sentence_splitter_keeps_abbreviations_and_is_pluggable") {
+        return;
+    }
+    let model = get_model();
+
+    let text = "Dr. Smith met Mr. Jones at 10:30. \"Pi is 3.14!\" he said. It was, e.g., fine.";
+    let chunks = model.split_into_best_sentences_with_max_tokens(text, 12);
+    assert!(chunks.len() > 1);
+    assert!(chunks[0].starts_with("Dr. Smith met Mr. Jones at 10:30."));
+    assert!(
+        chunks
+            .iter()
+            .any(|c| c.contains("\"Pi is 3.14!\" he said."))
+    );
+
+    struct Pipes;
+    impl SentenceSplitter for Pipes {
+        fn sentences(&self, text: &str) -> Vec<String> {
+            text.split('|').map(|s| s.trim().to_string()).collect()
+        }
+
+        // One chunk per piece, whatever the token budget
+        fn chunks(&self, text: &str, _: usize, _: &dyn Fn(&str) -> usize) -> Vec<String> {
+            self.sentences(text)
+        }
+    }
+    let model = model.clone().with_sentence_splitter(Pipes);
+    assert_eq!(
+        model.split_into_best_sentences_with_max_tokens("One. Two | Three", 50),
+        vec!["One. Two", "Three."]
+    );
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_inline_voice_markers_switch_voice() {
+    use pocket_tts::GenerationEventKind;
+
+    if !require_hf_token("test_inline_voice_markers_switch_voice") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let spec = ref_wav_path.display().to_string();
+    let text = format!("Hello there. [voice:{spec}] Hi, nice to meet you. [voice:{spec}] Bye.");
+    let options = pocket_tts::GenerationOptions {
+        seed: Some(11),
+        ..model.generation_options()
+    };
+    let mut voices = Vec::new();
+    let mut chunks = 0;
+    for event in model.generate_events_with_options(&text, &voice_state, &options) {
+        match event.expect("Generation failed").kind {
+            GenerationEventKind::VoiceChanged { voice } => voices.push(voice),
+            GenerationEventKind::ChunkStarted { .. } => chunks += 1,
+            _ => {}
+        }
+    }
+    assert_eq!(voices, vec![spec.clone(), spec]);
+    assert_eq!(chunks, 3);
+
+    let missing = model
+        .generate_stream_long_with_options(
+            "Hi. [voice:/no/such/voice.wav] Bye.",
+            &voice_state,
+            &options,
+        )
+        .find_map(Result::err);
+    assert!(missing.is_some(), "Unknown voices should fail");
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_markdown_input_pauses_around_headings() {
+    use pocket_tts::{GenerationEventKind, InputFormat};
+
+    if !require_hf_token("test_markdown_input_pauses_around_headings") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to get voice state");
+
+    let markdown =
+        "Intro.\n\n## Usage\n\n```sh\npocket-tts generate\n```\n\n- Run [it](https://x.dev)";
+    let options = pocket_tts::GenerationOptions {
+        input_format: InputFormat::Markdown,
+        seed: Some(5),
+        ..model.generation_options()
+    };
+    let mut pauses = Vec::new();
+    let mut chunks = Vec::new();
+    for event in model.generate_events_with_options(markdown, &voice_state, &options) {
+        let event = event.expect("Generation failed");
+        match event.kind {
+            GenerationEventKind::Pause { ms, .. } => pauses.push(ms),
+            GenerationEventKind::ChunkStarted { text, .. } => chunks.push(text),
+            _ => {}
+        }
+    }
+    assert_eq!(pauses, vec![700, 700]);
+    assert!(
+        chunks
+            .iter()
+            .all(|c| !c.contains('#') && !c.contains("https"))
+    );
+}
+
 #[test]
 // #[ignore = "requires HF_TOKEN and model download"]
 fn test_generate_with_pauses_adds_silence() {
@@ -391,3 +1003,268 @@ fn test_quantize_module_integration() {
     assert_eq!(config.num_levels, 256);
     assert!(config.skip_layers.contains(&"embed".to_string()));
 }
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_analyze_text_reports_unsupported_chars() {
+    use pocket_tts::UnsupportedCharPolicy;
+
+    if !require_hf_token("test_analyze_text_reports_unsupported_chars") {
+        return;
+    }
+    let model = get_model();
+    let mut options = model.generation_options();
+
+    let analysis = model
+        .analyze_text("Plain English text, nothing unusual.", &options)
+        .expect("analysis failed");
+    assert!(analysis.unsupported.is_empty());
+    assert!(!analysis.chunks.is_empty());
+    assert!(analysis.chunks.iter().all(|c| c.tokens > 0));
+
+    let text = "Party time 🎉 with friends 你好.";
+    let analysis = model.analyze_text(text, &options).expect("analysis failed");
+    let emoji = analysis
+        .unsupported
+        .iter()
+        .find(|c| c.ch == '🎉')
+        .expect("emoji not reported");
+    assert_eq!(emoji.replacement, None);
+    assert!(!analysis.warnings().is_empty());
+    // Transliterate (the default) leaves nothing for the tokenizer to guess
+    assert!(
+        analysis
+            .chunks
+            .iter()
+            .all(|c| c.unknown_tokens + c.byte_fallback_tokens == 0)
+    );
+
+    // The lexicon is applied once, as when generating
+    let mut lexicon = pocket_tts::Lexicon::new();
+    lexicon.insert_word("tomato", "tomato tomato").unwrap();
+    let lexicon_options = pocket_tts::GenerationOptions {
+        lexicon: Some(std::sync::Arc::new(lexicon)),
+        ..options.clone()
+    };
+    let analysis = model
+        .analyze_text("I like tomato", &lexicon_options)
+        .expect("analysis failed");
+    let spoken: String = analysis.chunks.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(spoken.matches("tomato").count(), 2, "{spoken}");
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+    options.unsupported_chars = UnsupportedCharPolicy::Error;
+    let voice_state = model
+        .get_voice_state(&ref_wav_path)
+        .expect("voice state failed");
+    let first = model
+        .generate_stream_long_with_options(text, &voice_state, &options)
+        .next()
+        .expect("no result");
+    assert!(first.is_err());
+    // The short-form entry points follow the same policy
+    assert!(
+        model
+            .generate_with_options(text, &voice_state, &options)
+            .is_err()
+    );
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_voice_prompt_formats_are_detected_on_load() {
+    use pocket_tts::{VoicePrompt, VoicePromptFormat};
+
+    if !require_hf_token("test_voice_prompt_formats_are_detected_on_load") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let text = "Every prompt format clones the same voice.";
+    let options = model.generation_options().with_seed(Some(5));
+    let mut outputs = Vec::new();
+    for format in [
+        VoicePromptFormat::AudioPrompt,
+        VoicePromptFormat::KvCacheCurrentEnd,
+        VoicePromptFormat::KvCacheOffset,
+    ] {
+        let path = std::env::temp_dir().join(format!(
+            "pocket_tts_voice_prompt_v{}.safetensors",
+            format.version()
+        ));
+        model
+            .create_voice_prompt(&ref_wav_path, format)
+            .expect("Failed to create voice prompt")
+            .save(&path)
+            .expect("Failed to save voice prompt");
+
+        let prompt = VoicePrompt::load(&path).expect("Failed to load voice prompt");
+        assert_eq!(prompt.format, format);
+        assert_eq!(prompt.metadata.model_variant.as_deref(), Some("b6369a24"));
+        assert!(prompt.metadata.source_sample_rate.is_some());
+        assert!(prompt.metadata.duration_secs.unwrap() > 0.0);
+
+        let voice_state = model
+            .get_voice_state_from_prompt_file(&path)
+            .expect("Failed to load voice state");
+        let _ = std::fs::remove_file(&path);
+        outputs.push(
+            model
+                .generate_with_options(text, &voice_state, &options)
+                .expect("Failed to generate audio"),
+        );
+    }
+    assert!(outputs.iter().all(|audio| audio.dims()[1] > 0));
+
+    let mut prompt = model
+        .create_voice_prompt(&ref_wav_path, VoicePromptFormat::AudioPrompt)
+        .expect("Failed to create voice prompt");
+    prompt.metadata.model_variant = Some("0000000".to_string());
+    let err = model
+        .get_voice_state_from_voice_prompt(&prompt)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("model variant '0000000'"), "{err}");
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_blend_voices_of_different_lengths() {
+    if !require_hf_token("test_blend_voices_of_different_lengths") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let (audio, sample_rate) = read_wav(&ref_wav_path).expect("Failed to read ref.wav");
+    let audio = pocket_tts::audio::resample(&audio, sample_rate, model.sample_rate as u32)
+        .expect("Failed to resample")
+        .unsqueeze(0)
+        .unwrap();
+    let samples = audio.dims()[2];
+    let short = audio.narrow(2, 0, samples / 2).unwrap();
+
+    let long = model.get_conditioning(&audio).expect("conditioning failed");
+    let short = model.get_conditioning(&short).expect("conditioning failed");
+    let (long_frames, short_frames) = (long.dims()[1], short.dims()[1]);
+    assert!(short_frames < long_frames);
+
+    let blended =
+        pocket_tts::blend_voices(&[(long, 0.5), (short, 0.5)]).expect("Failed to blend voices");
+    let frames = blended.dims()[1];
+    assert!(frames > short_frames && frames < long_frames);
+    assert_eq!(blended.dims()[2], model.dim);
+
+    let voice_state = model
+        .get_voice_state_from_prompt_tensor(&blended)
+        .expect("Failed to build voice state");
+    let audio = model
+        .generate("A blended voice.", &voice_state)
+        .expect("Failed to generate audio");
+    assert!(audio.dims()[1] > 0);
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_voice_preprocessing_trims_padded_reference() {
+    if !require_hf_token("test_voice_preprocessing_trims_padded_reference") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let (audio, sample_rate) = read_wav(&ref_wav_path).expect("Failed to read ref.wav");
+    // Two seconds of silence on both sides
+    let silence = candle_core::Tensor::zeros(
+        (audio.dims()[0], sample_rate as usize * 2),
+        audio.dtype(),
+        audio.device(),
+    )
+    .unwrap();
+    let padded = candle_core::Tensor::cat(&[&silence, &audio, &silence], 1).unwrap();
+
+    let raw = model
+        .prepare_voice_audio(&padded, sample_rate)
+        .expect("Failed to prepare audio");
+    let cleaned_model = model
+        .clone()
+        .with_voice_preprocessing(Some(pocket_tts::VoicePreprocessing::default()));
+    let cleaned = cleaned_model
+        .prepare_voice_audio(&padded, sample_rate)
+        .expect("Failed to preprocess audio");
+    assert_eq!(cleaned.dims()[1], 1);
+    assert!(cleaned.dims()[2] + model.sample_rate * 3 < raw.dims()[2]);
+
+    let voice_state = cleaned_model
+        .get_voice_state(&ref_wav_path)
+        .expect("Failed to clone preprocessed voice");
+    let audio = cleaned_model
+        .generate("A cleaned up voice.", &voice_state)
+        .expect("Failed to generate audio");
+    assert!(audio.dims()[1] > 0);
+}
+
+#[test]
+// #[ignore = "requires HF_TOKEN and model download"]
+fn test_voice_state_from_multiple_clips() {
+    if !require_hf_token("test_voice_state_from_multiple_clips") {
+        return;
+    }
+    let model = get_model();
+
+    let ref_wav_path = get_ref_wav_path();
+    if !ref_wav_path.exists() {
+        eprintln!("ref.wav not found at {:?}, skipping test", ref_wav_path);
+        return;
+    }
+
+    let (audio, sample_rate) = read_wav(&ref_wav_path).expect("Failed to read ref.wav");
+    let samples = audio.dims()[1];
+    let first = audio.narrow(1, 0, samples / 2).unwrap();
+    let second = audio.narrow(1, samples / 2, samples - samples / 2).unwrap();
+    let clips = [(first, sample_rate), (second, sample_rate)];
+
+    let joined = model
+        .prepare_voice_clips(&clips, &pocket_tts::VoiceClipJoin::default())
+        .expect("Failed to join clips");
+    assert_eq!(joined.dims()[..2], [1, 1]);
+    let capped = model
+        .prepare_voice_clips(
+            &clips,
+            &pocket_tts::VoiceClipJoin {
+                max_total_secs: 2.0,
+                ..Default::default()
+            },
+        )
+        .expect("Failed to join clips");
+    assert!(capped.dims()[2] <= model.sample_rate * 2);
+    assert!(capped.dims()[2] < joined.dims()[2]);
+
+    let voice_state = model
+        .get_voice_state_from_clips(&clips)
+        .expect("Failed to clone voice from clips");
+    let audio = model
+        .generate("Two clips, one voice.", &voice_state)
+        .expect("Failed to generate audio");
+    assert!(audio.dims()[1] > 0);
+}
@@ -0,0 +1,33 @@
+using System;
+using System.Threading;
+using Microsoft.Win32.SafeHandles;
+
+namespace PocketTTS;
+
+public sealed class GenerationHandle : SafeHandleZeroOrMinusOneIsInvalid
+{
+    private GenerationHandle() : base(true)
+    {
+    }
+
+    public static GenerationHandle Create()
+    {
+        return NativeApi.pocket_tts_generation_handle_new();
+    }
+
+    public void Cancel()
+    {
+        NativeApi.pocket_tts_generation_handle_cancel(this);
+    }
+
+    public CancellationTokenRegistration CancelOn(CancellationToken ct)
+    {
+        return ct.Register(Cancel);
+    }
+
+    protected override bool ReleaseHandle()
+    {
+        NativeApi.pocket_tts_generation_handle_free(handle);
+        return true;
+    }
+}
@@ -120,7 +120,7 @@ public sealed class PocketTtsInferenceService : IPocketTtsInferenceService
             SingleReader = true,
             SingleWriter = true
         });
-        var job = new StreamingJob(text, voiceName, chunksChannel.Writer);
+        var job = new StreamingJob(text, voiceName, chunksChannel.Writer, ct);
         
         await _jobsChannel.Writer.WriteAsync(job, ct);
         while (await chunksChannel.Reader.WaitToReadAsync(ct))
@@ -1,5 +1,6 @@
 using System;
 using System.Collections.Concurrent;
+using System.Threading;
 using System.Threading.Channels;
 
 namespace PocketTTS.Inference;
@@ -7,18 +8,22 @@ namespace PocketTTS.Inference;
 internal class StreamingJob : JobBase
 {
     private readonly ChannelWriter<float[]> _chunksChannel;
+    private readonly CancellationToken _ct;
 
-    public StreamingJob(string text, string voiceName, ChannelWriter<float[]> chunksChannel) : base(text, voiceName)
+    public StreamingJob(string text, string voiceName, ChannelWriter<float[]> chunksChannel, CancellationToken ct) : base(text, voiceName)
     {
         _chunksChannel = chunksChannel;
+        _ct = ct;
     }
 
     public override void Execute(ModelHandle model, ConcurrentDictionary<string, ModelStateHandle> voices)
     {
         using var voice = voices[VoiceName].Clone();
+        using var generation = GenerationHandle.Create();
+        using var registration = generation.CancelOn(_ct);
         try
         {
-            model.GenerateStream(Text, voice, OnChunk, OnFinished, OnError);
+            model.GenerateStream(Text, voice, generation, OnChunk, OnFinished, OnError);
         }
         catch(Exception e)
         {
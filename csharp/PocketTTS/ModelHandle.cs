@@ -35,6 +35,17 @@ public sealed class ModelHandle : SafeHandleZeroOrMinusOneIsInvalid
         var voice = NativeApi.pocket_tts_get_voice_state_from_wav(this, path);
         return voice;
     }
+
+    public ModelStateHandle GetModelStateFromWav(string path, VoicePreprocessing preprocessing)
+    {
+        if(!File.Exists(path))
+        {
+            throw new FileNotFoundException("Voice file not found", path);
+        }
+
+        var voice = NativeApi.pocket_tts_get_voice_state_from_wav_preprocessed(this, path, in preprocessing);
+        return voice;
+    }
         
     public ModelStateHandle GetModelStateFromSafetensors(string path)
     {
@@ -46,6 +57,7 @@ public sealed class ModelHandle : SafeHandleZeroOrMinusOneIsInvalid
         return voice;
     }
         
+    [Obsolete("GetModelStateFromSafetensors detects the voice prompt format")]
     public ModelStateHandle GetModelStateFromSafetensorsV2(string path)
     {
         if(!File.Exists(path))
@@ -56,6 +68,7 @@ public sealed class ModelHandle : SafeHandleZeroOrMinusOneIsInvalid
         return voice;
     }
         
+    [Obsolete("GetModelStateFromSafetensors detects the voice prompt format")]
     public ModelStateHandle GetModelStateFromSafetensorsV3(string path)
     {
         if(!File.Exists(path))
@@ -76,7 +89,48 @@ public sealed class ModelHandle : SafeHandleZeroOrMinusOneIsInvalid
         return bufferHandle.GetAudio();
     }
 
+    public float[] Generate(string text, ModelStateHandle modelState, ulong seed)
+    {
+        if (string.IsNullOrEmpty(text))
+            throw new ArgumentNullException(nameof(text));
+        ArgumentNullException.ThrowIfNull(modelState);
+
+        using var bufferHandle = NativeApi.pocket_tts_generate_with_seed(this, text, modelState, seed);
+        return bufferHandle.GetAudio();
+    }
+
+    public float[] GenerateWithSpeed(string text, ModelStateHandle modelState, float speed)
+    {
+        if (string.IsNullOrEmpty(text))
+            throw new ArgumentNullException(nameof(text));
+        ArgumentNullException.ThrowIfNull(modelState);
+
+        using var bufferHandle = NativeApi.pocket_tts_generate_with_speed(this, text, modelState, speed);
+        return bufferHandle.GetAudio();
+    }
+
     public void GenerateStream(string text, ModelStateHandle modelState, Func<float[], bool> onChunk, Action onFinished, Action onError)
+    {
+        GenerateStream(text, modelState, (ulong?)null, onChunk, onFinished, onError);
+    }
+
+    public void GenerateStream(string text, ModelStateHandle modelState, ulong? seed, Func<float[], bool> onChunk, Action onFinished, Action onError)
+    {
+        GenerateStream(text, modelState, seed, null, null, onChunk, onFinished, onError);
+    }
+
+    public void GenerateStreamWithSpeed(string text, ModelStateHandle modelState, float speed, Func<float[], bool> onChunk, Action onFinished, Action onError)
+    {
+        GenerateStream(text, modelState, null, speed, null, onChunk, onFinished, onError);
+    }
+
+    public void GenerateStream(string text, ModelStateHandle modelState, GenerationHandle generation, Func<float[], bool> onChunk, Action onFinished, Action onError)
+    {
+        ArgumentNullException.ThrowIfNull(generation);
+        GenerateStream(text, modelState, null, null, generation, onChunk, onFinished, onError);
+    }
+
+    private void GenerateStream(string text, ModelStateHandle modelState, ulong? seed, float? speed, GenerationHandle generation, Func<float[], bool> onChunk, Action onFinished, Action onError)
     {
         if (string.IsNullOrEmpty(text))
             throw new ArgumentNullException(nameof(text));
@@ -85,14 +139,53 @@ public sealed class ModelHandle : SafeHandleZeroOrMinusOneIsInvalid
         ArgumentNullException.ThrowIfNull(onFinished);
         ArgumentNullException.ThrowIfNull(onError);
 
-        NativeApi.pocket_tts_generate_stream(
-            this,
-            text,
-            modelState,
-            ChunkCallback,
-            FinishedCallback,
-            ErrorCallback,
-            IntPtr.Zero);
+        if (generation != null)
+        {
+            NativeApi.pocket_tts_generate_stream_cancellable(
+                this,
+                text,
+                modelState,
+                generation,
+                ChunkCallback,
+                FinishedCallback,
+                ErrorCallback,
+                IntPtr.Zero);
+        }
+        else if (speed.HasValue)
+        {
+            NativeApi.pocket_tts_generate_stream_with_speed(
+                this,
+                text,
+                modelState,
+                speed.Value,
+                ChunkCallback,
+                FinishedCallback,
+                ErrorCallback,
+                IntPtr.Zero);
+        }
+        else if (seed.HasValue)
+        {
+            NativeApi.pocket_tts_generate_stream_with_seed(
+                this,
+                text,
+                modelState,
+                seed.Value,
+                ChunkCallback,
+                FinishedCallback,
+                ErrorCallback,
+                IntPtr.Zero);
+        }
+        else
+        {
+            NativeApi.pocket_tts_generate_stream(
+                this,
+                text,
+                modelState,
+                ChunkCallback,
+                FinishedCallback,
+                ErrorCallback,
+                IntPtr.Zero);
+        }
 
         return;
 
@@ -150,6 +243,17 @@ public sealed class ModelHandle : SafeHandleZeroOrMinusOneIsInvalid
         NativeApi.pocket_tts_create_safetensors_from_wav_v3(this, wavPath, safetensorsPath);
     }
 
+    public void CreateSafetensorsFromWav(string wavPath, string safetensorsPath, VoicePreprocessing preprocessing, uint version = 1)
+    {
+        if (!File.Exists(wavPath))
+        {
+            throw new FileNotFoundException("Wave file not found", wavPath);
+        }
+        if (version is < 1 or > 3)
+            throw new ArgumentOutOfRangeException(nameof(version));
+        NativeApi.pocket_tts_create_safetensors_from_wav_preprocessed(this, wavPath, safetensorsPath, version, in preprocessing);
+    }
+
     protected override bool ReleaseHandle()
     {
         NativeApi.pocket_tts_free(handle);
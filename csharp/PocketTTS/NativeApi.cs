@@ -52,9 +52,41 @@ internal static class NativeApi
         [MarshalAs(UnmanagedType.LPUTF8Str)] string text,
         ModelStateHandle modelStateHandle);
 
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Ansi)]
+    internal static extern AudioBufferHanlde pocket_tts_generate_with_seed(
+        ModelHandle modelHandle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string text,
+        ModelStateHandle modelStateHandle,
+        ulong seed);
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Ansi)]
+    internal static extern AudioBufferHanlde pocket_tts_generate_with_speed(
+        ModelHandle modelHandle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string text,
+        ModelStateHandle modelStateHandle,
+        float speed);
+
     [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern uint pocket_tts_sample_rate(ModelHandle modelHandle);
 
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void pocket_tts_voice_preprocessing_default(
+        out VoicePreprocessing settings);
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern ModelStateHandle pocket_tts_get_voice_state_from_wav_preprocessed(
+        ModelHandle modelHandle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string path,
+        in VoicePreprocessing settings);
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void pocket_tts_create_safetensors_from_wav_preprocessed(
+        ModelHandle modelHandle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string wavPath,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string safetensorsPath,
+        uint version,
+        in VoicePreprocessing settings);
+
     [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
     internal static extern void pocket_tts_free_audio(IntPtr bufferHandle);
 
@@ -112,6 +144,48 @@ internal static class NativeApi
         StreamErrorCallback onError,
         IntPtr userData);
 
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Ansi)]
+    internal static extern void pocket_tts_generate_stream_with_seed(
+        ModelHandle modelHandle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string text,
+        ModelStateHandle modelStateHandle,
+        ulong seed,
+        StreamChunkCallback onChunk,
+        StreamFinishedCallback onFinished,
+        StreamErrorCallback onError,
+        IntPtr userData);
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Ansi)]
+    internal static extern void pocket_tts_generate_stream_with_speed(
+        ModelHandle modelHandle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string text,
+        ModelStateHandle modelStateHandle,
+        float speed,
+        StreamChunkCallback onChunk,
+        StreamFinishedCallback onFinished,
+        StreamErrorCallback onError,
+        IntPtr userData);
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl, CharSet = CharSet.Ansi)]
+    internal static extern void pocket_tts_generate_stream_cancellable(
+        ModelHandle modelHandle,
+        [MarshalAs(UnmanagedType.LPUTF8Str)] string text,
+        ModelStateHandle modelStateHandle,
+        GenerationHandle generationHandle,
+        StreamChunkCallback onChunk,
+        StreamFinishedCallback onFinished,
+        StreamErrorCallback onError,
+        IntPtr userData);
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern GenerationHandle pocket_tts_generation_handle_new();
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void pocket_tts_generation_handle_cancel(GenerationHandle generationHandle);
+
+    [DllImport(LibraryName, CallingConvention = CallingConvention.Cdecl)]
+    internal static extern void pocket_tts_generation_handle_free(IntPtr generationHandle);
+
     [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
     internal delegate StreamControlCode StreamChunkCallback(IntPtr bufferHandle, IntPtr userData);
 
@@ -0,0 +1,29 @@
+using System.Runtime.InteropServices;
+
+namespace PocketTTS;
+
+// Reference audio cleanup before voice cloning. Start from Default; a zero
+// HighPassHz, MaxSilenceMs or MaxDurationSecs and a NaN TargetLoudnessDb turn
+// that step off.
+[StructLayout(LayoutKind.Sequential)]
+public struct VoicePreprocessing
+{
+    [MarshalAs(UnmanagedType.U1)]
+    public bool RemoveDc;
+    public float HighPassHz;
+    [MarshalAs(UnmanagedType.U1)]
+    public bool TrimSilence;
+    public uint MaxSilenceMs;
+    public float SilenceThresholdDb;
+    public float TargetLoudnessDb;
+    public float MaxDurationSecs;
+
+    public static VoicePreprocessing Default
+    {
+        get
+        {
+            NativeApi.pocket_tts_voice_preprocessing_default(out var settings);
+            return settings;
+        }
+    }
+}